@@ -0,0 +1,808 @@
+//! Service configuration, loaded once at startup.
+//!
+//! Each setting is read from its `SERVICE_...` environment variable first,
+//! then from the TOML file given by `SERVICE_CONFIG_FILE` (if any),
+//! then falls back to its default (if it has one).
+//! See `config/dev.toml` for all the settings.
+
+use crate::{Error, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+static INSTANCE: OnceLock<Config> = OnceLock::new();
+
+/// Load and validate the config, if not already loaded.
+/// Called first thing in `main`, so a bad config fails the startup with a clear error.
+pub fn init_config() -> Result<&'static Config> {
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+    let config = Config::load()?;
+
+    Ok(INSTANCE.get_or_init(|| config))
+}
+
+pub fn config() -> &'static Config {
+    init_config().unwrap_or_else(|ex| panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    // -- Web
+    pub bind_addr: SocketAddr,
+    pub static_dir: PathBuf,
+    pub auth_cookie_name: String,
+    /// How long the in-flight requests may drain on shutdown.
+    pub shutdown_timeout: Duration,
+
+    // -- Crypt
+    pub token_secret: Vec<u8>,
+    /// Of the auth token, refreshed on each request.
+    pub token_ttl: Duration,
+    /// Login sessions without requests for this long are expired.
+    pub session_idle_timeout: Duration,
+
+    // -- Tracing
+    /// `EnvFilter` directives, e.g., `info,rust_axum_intro=debug`.
+    pub trace_filter: String,
+    pub trace_format: TraceFormat,
+
+    // -- Request Log
+    pub log_sink: LogSinkConfig,
+
+    // -- Store
+    pub store: StoreConfig,
+    /// How long deleted tickets stay in the trash before being purged.
+    pub trash_retention: Duration,
+    pub trash_purge_interval: Duration,
+    /// How long the response of an `Idempotency-Key` is replayed.
+    pub idempotency_ttl: Duration,
+
+    // -- Rate Limit
+    /// Per client IP, for the routes without auth (but login).
+    pub rate_limit_public: RateLimit,
+    /// Per client IP, for login, logout, and register.
+    pub rate_limit_login: RateLimit,
+    /// Per user, for the `/api` routes.
+    pub rate_limit_api: RateLimit,
+    /// Failed logins in a row locking the username for `login_lockout`.
+    pub login_max_failures: u32,
+    pub login_lockout: Duration,
+
+    // -- Webhook
+    pub webhook: WebhookConfig,
+
+    // -- Admin
+    /// The admin account created (or promoted) on startup, if any.
+    pub admin_seed: Option<AdminSeed>,
+}
+
+/// Output format of the tracing events (to stdout).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, strum_macros::EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TraceFormat {
+    /// Human readable, multi-line, for dev.
+    #[default]
+    Pretty,
+    /// One JSON object per line, with the request span fields.
+    Json,
+}
+
+/// Where the request log lines (JSON, one per line) are written.
+#[derive(Debug, Clone)]
+pub enum LogSinkConfig {
+    Stdout,
+    /// Rotated when it reaches `max_bytes`, or is older than `max_age`.
+    File {
+        path: PathBuf,
+        max_bytes: u64,
+        max_age: Duration,
+    },
+    /// Local collector, one datagram per line.
+    Udp {
+        addr: String,
+    },
+    /// Local collector, newline delimited.
+    Tcp {
+        addr: String,
+    },
+}
+
+/// Which backend the `ModelController` persists to.
+#[derive(Debug, Clone)]
+pub enum StoreConfig {
+    /// Non persistent, everything is lost on restart.
+    Memory,
+    /// SQLite database file (created and migrated on startup).
+    Sqlite { path: PathBuf },
+}
+
+/// Token bucket of `burst` requests, refilled at `burst` per `period`.
+/// Written `<burst>/<period_sec>`, e.g., `10/60`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct RateLimit {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let err = || format!("rate limit '{s}' is not '<burst>/<period_sec>', both > 0");
+
+        let (burst, period_sec) = s.split_once('/').ok_or_else(err)?;
+        let burst: u32 = burst.trim().parse().map_err(|_| err())?;
+        let period_sec: u64 = period_sec.trim().parse().map_err(|_| err())?;
+        if burst == 0 || period_sec == 0 {
+            return Err(err());
+        }
+
+        Ok(Self {
+            burst,
+            period: Duration::from_secs(period_sec),
+        })
+    }
+}
+
+impl TryFrom<String> for RateLimit {
+    type Error = String;
+
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Delivery of the outgoing webhooks (see `model::webhook`).
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookConfig {
+    /// Tries of each delivery, the first one included.
+    pub max_attempts: u32,
+    /// Wait before the second try, doubled for each next one.
+    pub backoff: Duration,
+    /// Failed deliveries in a row disabling the webhook.
+    pub disable_after: u32,
+    /// Of each request.
+    pub timeout: Duration,
+}
+
+/// The bootstrap `Admin` account, as the registered accounts are all members.
+#[derive(Clone)]
+pub struct AdminSeed {
+    pub username: String,
+    /// Only set when the account is created.
+    pub pwd: String,
+}
+
+impl std::fmt::Debug for AdminSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminSeed")
+            .field("username", &self.username)
+            .field("pwd", &"[redacted]")
+            .finish()
+    }
+}
+
+// region:    --- Config Keys
+
+/// A setting, with its environment variable and its key in the config file.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ConfigKey {
+    pub env: &'static str,
+    pub file: &'static str,
+}
+
+const fn key(env: &'static str, file: &'static str) -> ConfigKey {
+    ConfigKey { env, file }
+}
+
+const CONFIG_FILE_ENV: &str = "SERVICE_CONFIG_FILE";
+
+const BIND_ADDR: ConfigKey = key("SERVICE_BIND_ADDR", "web.bind_addr");
+const STATIC_DIR: ConfigKey = key("SERVICE_STATIC_DIR", "web.static_dir");
+const AUTH_COOKIE_NAME: ConfigKey = key("SERVICE_AUTH_COOKIE_NAME", "web.auth_cookie_name");
+const SHUTDOWN_TIMEOUT_SEC: ConfigKey =
+    key("SERVICE_SHUTDOWN_TIMEOUT_SEC", "web.shutdown_timeout_sec");
+const TOKEN_SECRET: ConfigKey = key("SERVICE_TOKEN_SECRET", "token.secret");
+const TOKEN_TTL_SEC: ConfigKey = key("SERVICE_TOKEN_TTL_SEC", "token.ttl_sec");
+const SESSION_IDLE_TIMEOUT_SEC: ConfigKey = key(
+    "SERVICE_SESSION_IDLE_TIMEOUT_SEC",
+    "token.session_idle_timeout_sec",
+);
+const TRACE_FILTER: ConfigKey = key("SERVICE_TRACE_FILTER", "trace.filter");
+const TRACE_FORMAT: ConfigKey = key("SERVICE_TRACE_FORMAT", "trace.format");
+const LOG_SINK: ConfigKey = key("SERVICE_LOG_SINK", "log.sink");
+const LOG_FILE_MAX_BYTES: ConfigKey = key("SERVICE_LOG_FILE_MAX_BYTES", "log.file_max_bytes");
+const LOG_FILE_MAX_AGE_SEC: ConfigKey = key("SERVICE_LOG_FILE_MAX_AGE_SEC", "log.file_max_age_sec");
+const DB_PATH: ConfigKey = key("SERVICE_DB_PATH", "store.db_path");
+const TRASH_RETENTION_SEC: ConfigKey =
+    key("SERVICE_TRASH_RETENTION_SEC", "store.trash_retention_sec");
+const TRASH_PURGE_INTERVAL_SEC: ConfigKey = key(
+    "SERVICE_TRASH_PURGE_INTERVAL_SEC",
+    "store.trash_purge_interval_sec",
+);
+const IDEMPOTENCY_TTL_SEC: ConfigKey =
+    key("SERVICE_IDEMPOTENCY_TTL_SEC", "store.idempotency_ttl_sec");
+const RATE_LIMIT_PUBLIC: ConfigKey = key("SERVICE_RATE_LIMIT_PUBLIC", "rate_limit.public");
+const RATE_LIMIT_LOGIN: ConfigKey = key("SERVICE_RATE_LIMIT_LOGIN", "rate_limit.login");
+const RATE_LIMIT_API: ConfigKey = key("SERVICE_RATE_LIMIT_API", "rate_limit.api");
+const LOGIN_MAX_FAILURES: ConfigKey = key(
+    "SERVICE_LOGIN_MAX_FAILURES",
+    "rate_limit.login_max_failures",
+);
+const LOGIN_LOCKOUT_SEC: ConfigKey =
+    key("SERVICE_LOGIN_LOCKOUT_SEC", "rate_limit.login_lockout_sec");
+const WEBHOOK_MAX_ATTEMPTS: ConfigKey = key("SERVICE_WEBHOOK_MAX_ATTEMPTS", "webhook.max_attempts");
+const WEBHOOK_BACKOFF_SEC: ConfigKey = key("SERVICE_WEBHOOK_BACKOFF_SEC", "webhook.backoff_sec");
+const WEBHOOK_DISABLE_AFTER: ConfigKey =
+    key("SERVICE_WEBHOOK_DISABLE_AFTER", "webhook.disable_after");
+const WEBHOOK_TIMEOUT_SEC: ConfigKey = key("SERVICE_WEBHOOK_TIMEOUT_SEC", "webhook.timeout_sec");
+const ADMIN_USERNAME: ConfigKey = key("SERVICE_ADMIN_USERNAME", "admin.username");
+const ADMIN_PWD: ConfigKey = key("SERVICE_ADMIN_PWD", "admin.pwd");
+
+// endregion: --- Config Keys
+
+// region:    --- Config File
+
+/// The TOML config file. All settings are optional here,
+/// as they can come from the environment or have a default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    web: WebSection,
+    token: TokenSection,
+    trace: TraceSection,
+    log: LogSection,
+    store: StoreSection,
+    rate_limit: RateLimitSection,
+    webhook: WebhookSection,
+    admin: AdminSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WebSection {
+    bind_addr: Option<SocketAddr>,
+    static_dir: Option<PathBuf>,
+    auth_cookie_name: Option<String>,
+    shutdown_timeout_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TokenSection {
+    /// Base64url (no padding).
+    secret: Option<String>,
+    ttl_sec: Option<u64>,
+    session_idle_timeout_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TraceSection {
+    filter: Option<String>,
+    format: Option<TraceFormat>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LogSection {
+    /// e.g., `stdout`, `file:logs/requests.jsonl`, `udp:127.0.0.1:5140`
+    sink: Option<String>,
+    file_max_bytes: Option<u64>,
+    file_max_age_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StoreSection {
+    /// When none, the in-memory store is used.
+    db_path: Option<PathBuf>,
+    trash_retention_sec: Option<u64>,
+    trash_purge_interval_sec: Option<u64>,
+    idempotency_ttl_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RateLimitSection {
+    public: Option<RateLimit>,
+    login: Option<RateLimit>,
+    api: Option<RateLimit>,
+    login_max_failures: Option<u32>,
+    login_lockout_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WebhookSection {
+    max_attempts: Option<u32>,
+    backoff_sec: Option<u64>,
+    disable_after: Option<u32>,
+    timeout_sec: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AdminSection {
+    username: Option<String>,
+    pwd: Option<String>,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|ex| Error::ConfigFileRead {
+            path: path.display().to_string(),
+            cause: ex.to_string(),
+        })?;
+
+        Self::parse(&content).map_err(|cause| Error::ConfigFileInvalid {
+            path: path.display().to_string(),
+            cause,
+        })
+    }
+
+    /// On error, returns the message with the line of the issue.
+    fn parse(content: &str) -> core::result::Result<Self, String> {
+        toml::from_str(content).map_err(|ex| {
+            let message = ex.message().to_string();
+            match ex.span() {
+                Some(span) => {
+                    let line = content[..span.start].matches('\n').count() + 1;
+                    format!("line {line} - {message}")
+                }
+                None => message,
+            }
+        })
+    }
+}
+
+// endregion: --- Config File
+
+// region:    --- Config Loader
+
+impl Config {
+    fn load() -> Result<Config> {
+        let file = match get_env_opt(CONFIG_FILE_ENV) {
+            Some(path) => ConfigFile::load(Path::new(&path))?,
+            None => ConfigFile::default(),
+        };
+
+        Config::from_sources(file, &get_env_opt)
+    }
+
+    /// Resolve and validate the settings from the file and the environment
+    /// (given as a lookup function, so this can be tested without touching the env).
+    fn from_sources(file: ConfigFile, env: &dyn Fn(&str) -> Option<String>) -> Result<Config> {
+        let setting = Setting { env };
+
+        // -- Web
+        let bind_addr = setting
+            .get(BIND_ADDR, file.web.bind_addr)?
+            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 8080)));
+        let static_dir = setting
+            .get(STATIC_DIR, file.web.static_dir)?
+            .unwrap_or_else(|| PathBuf::from("web-folder/"));
+        let auth_cookie_name = setting
+            .get(AUTH_COOKIE_NAME, file.web.auth_cookie_name)?
+            .unwrap_or_else(|| "auth-token".to_string());
+        let shutdown_timeout_sec = setting
+            .get(SHUTDOWN_TIMEOUT_SEC, file.web.shutdown_timeout_sec)?
+            .unwrap_or(30);
+
+        // -- Crypt
+        let token_secret = setting
+            .get::<String>(TOKEN_SECRET, file.token.secret)?
+            .ok_or(Error::ConfigMissing(TOKEN_SECRET))?;
+        let token_secret = URL_SAFE_NO_PAD
+            .decode(token_secret)
+            .map_err(|_| Error::ConfigWrongFormat(TOKEN_SECRET))?;
+        let token_ttl_sec = setting
+            .get(TOKEN_TTL_SEC, file.token.ttl_sec)?
+            .unwrap_or(1800);
+        let session_idle_timeout_sec = setting
+            .get(
+                SESSION_IDLE_TIMEOUT_SEC,
+                file.token.session_idle_timeout_sec,
+            )?
+            .unwrap_or(1800);
+
+        // -- Tracing
+        let trace_filter = setting
+            .get(TRACE_FILTER, file.trace.filter)?
+            .unwrap_or_else(|| "info".to_string());
+        let trace_format = setting
+            .get(TRACE_FORMAT, file.trace.format)?
+            .unwrap_or_default();
+
+        // -- Request Log
+        let log_sink = setting
+            .get(LOG_SINK, file.log.sink)?
+            .unwrap_or_else(|| "stdout".to_string());
+        let log_sink = match log_sink.split_once(':') {
+            None if log_sink == "stdout" => LogSinkConfig::Stdout,
+            Some(("file", path)) => LogSinkConfig::File {
+                path: path.into(),
+                max_bytes: setting
+                    .get(LOG_FILE_MAX_BYTES, file.log.file_max_bytes)?
+                    .unwrap_or(10 * 1024 * 1024),
+                max_age: Duration::from_secs(
+                    setting
+                        .get(LOG_FILE_MAX_AGE_SEC, file.log.file_max_age_sec)?
+                        .unwrap_or(24 * 3600),
+                ),
+            },
+            Some(("udp", addr)) => LogSinkConfig::Udp {
+                addr: addr.to_string(),
+            },
+            Some(("tcp", addr)) => LogSinkConfig::Tcp {
+                addr: addr.to_string(),
+            },
+            _ => return Err(Error::ConfigWrongFormat(LOG_SINK)),
+        };
+
+        // -- Store
+        let store = match setting.get(DB_PATH, file.store.db_path)? {
+            Some(path) => StoreConfig::Sqlite { path },
+            None => StoreConfig::Memory,
+        };
+        let trash_retention_sec = setting
+            .get(TRASH_RETENTION_SEC, file.store.trash_retention_sec)?
+            .unwrap_or(30 * 24 * 3600);
+        let trash_purge_interval_sec = setting
+            .get(
+                TRASH_PURGE_INTERVAL_SEC,
+                file.store.trash_purge_interval_sec,
+            )?
+            .unwrap_or(3600);
+        let idempotency_ttl_sec = setting
+            .get(IDEMPOTENCY_TTL_SEC, file.store.idempotency_ttl_sec)?
+            .unwrap_or(24 * 3600);
+
+        // -- Rate Limit
+        let rate_limit_public = setting
+            .get(RATE_LIMIT_PUBLIC, file.rate_limit.public)?
+            .unwrap_or(RateLimit {
+                burst: 300,
+                period: Duration::from_secs(60),
+            });
+        let rate_limit_login = setting
+            .get(RATE_LIMIT_LOGIN, file.rate_limit.login)?
+            .unwrap_or(RateLimit {
+                burst: 10,
+                period: Duration::from_secs(60),
+            });
+        let rate_limit_api =
+            setting
+                .get(RATE_LIMIT_API, file.rate_limit.api)?
+                .unwrap_or(RateLimit {
+                    burst: 600,
+                    period: Duration::from_secs(60),
+                });
+        let login_max_failures = setting
+            .get(LOGIN_MAX_FAILURES, file.rate_limit.login_max_failures)?
+            .unwrap_or(5);
+        let login_lockout_sec = setting
+            .get(LOGIN_LOCKOUT_SEC, file.rate_limit.login_lockout_sec)?
+            .unwrap_or(300);
+
+        // -- Webhook
+        let webhook = WebhookConfig {
+            max_attempts: setting
+                .get(WEBHOOK_MAX_ATTEMPTS, file.webhook.max_attempts)?
+                .unwrap_or(5),
+            backoff: Duration::from_secs(
+                setting
+                    .get(WEBHOOK_BACKOFF_SEC, file.webhook.backoff_sec)?
+                    .unwrap_or(10),
+            ),
+            disable_after: setting
+                .get(WEBHOOK_DISABLE_AFTER, file.webhook.disable_after)?
+                .unwrap_or(5),
+            timeout: Duration::from_secs(
+                setting
+                    .get(WEBHOOK_TIMEOUT_SEC, file.webhook.timeout_sec)?
+                    .unwrap_or(10),
+            ),
+        };
+
+        // -- Admin
+        let admin_username: Option<String> = setting.get(ADMIN_USERNAME, file.admin.username)?;
+        let admin_pwd: Option<String> = setting.get(ADMIN_PWD, file.admin.pwd)?;
+        let admin_seed = match (admin_username, admin_pwd) {
+            (Some(username), Some(pwd)) => Some(AdminSeed { username, pwd }),
+            (None, None) => None,
+            (Some(_), None) => return Err(Error::ConfigMissing(ADMIN_PWD)),
+            (None, Some(_)) => return Err(Error::ConfigMissing(ADMIN_USERNAME)),
+        };
+
+        let config = Config {
+            // -- Web
+            bind_addr,
+            static_dir,
+            auth_cookie_name,
+            shutdown_timeout: Duration::from_secs(shutdown_timeout_sec),
+
+            // -- Crypt
+            token_secret,
+            token_ttl: Duration::from_secs(token_ttl_sec),
+            session_idle_timeout: Duration::from_secs(session_idle_timeout_sec),
+
+            // -- Tracing
+            trace_filter,
+            trace_format,
+
+            // -- Request Log
+            log_sink,
+
+            // -- Store
+            store,
+            trash_retention: Duration::from_secs(trash_retention_sec),
+            trash_purge_interval: Duration::from_secs(trash_purge_interval_sec),
+            idempotency_ttl: Duration::from_secs(idempotency_ttl_sec),
+
+            // -- Rate Limit
+            rate_limit_public,
+            rate_limit_login,
+            rate_limit_api,
+            login_max_failures,
+            login_lockout: Duration::from_secs(login_lockout_sec),
+
+            // -- Webhook
+            webhook,
+
+            // -- Admin
+            admin_seed,
+        };
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |key, reason| Err(Error::ConfigInvalid { key, reason });
+
+        // -- Web
+        if !self.static_dir.is_dir() {
+            return invalid(STATIC_DIR, "not an existing directory");
+        }
+        let cookie_name_ok = self
+            .auth_cookie_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if self.auth_cookie_name.is_empty() || !cookie_name_ok {
+            return invalid(
+                AUTH_COOKIE_NAME,
+                "must be non empty, with only [A-Za-z0-9_-]",
+            );
+        }
+        if self.shutdown_timeout.is_zero() {
+            return invalid(SHUTDOWN_TIMEOUT_SEC, "must be greater than 0");
+        }
+
+        // -- Crypt
+        if self.token_secret.len() < 32 {
+            return invalid(TOKEN_SECRET, "must be at least 32 bytes");
+        }
+        if self.token_ttl.is_zero() {
+            return invalid(TOKEN_TTL_SEC, "must be greater than 0");
+        }
+        // The session `last_seen` is only written once a minute.
+        if self.session_idle_timeout < Duration::from_secs(60) {
+            return invalid(SESSION_IDLE_TIMEOUT_SEC, "must be at least 60");
+        }
+
+        // -- Tracing
+        if EnvFilter::try_new(&self.trace_filter).is_err() {
+            return invalid(TRACE_FILTER, "not valid filter directives");
+        }
+
+        // -- Request Log
+        if let LogSinkConfig::File {
+            path,
+            max_bytes,
+            max_age,
+        } = &self.log_sink
+        {
+            if path.as_os_str().is_empty() {
+                return invalid(LOG_SINK, "file sink without a path");
+            }
+            if *max_bytes == 0 {
+                return invalid(LOG_FILE_MAX_BYTES, "must be greater than 0");
+            }
+            if max_age.is_zero() {
+                return invalid(LOG_FILE_MAX_AGE_SEC, "must be greater than 0");
+            }
+        }
+
+        // -- Store
+        if let StoreConfig::Sqlite { path } = &self.store {
+            // SQLite creates the db file, but not its directory.
+            let dir_ok = path
+                .parent()
+                .is_none_or(|dir| dir.as_os_str().is_empty() || dir.is_dir());
+            if !dir_ok {
+                return invalid(DB_PATH, "directory does not exist");
+            }
+        }
+        if self.trash_purge_interval.is_zero() {
+            return invalid(TRASH_PURGE_INTERVAL_SEC, "must be greater than 0");
+        }
+        if self.idempotency_ttl.is_zero() {
+            return invalid(IDEMPOTENCY_TTL_SEC, "must be greater than 0");
+        }
+        if self.login_max_failures == 0 {
+            return invalid(LOGIN_MAX_FAILURES, "must be greater than 0");
+        }
+
+        // -- Webhook
+        if self.webhook.max_attempts == 0 {
+            return invalid(WEBHOOK_MAX_ATTEMPTS, "must be greater than 0");
+        }
+        if self.webhook.disable_after == 0 {
+            return invalid(WEBHOOK_DISABLE_AFTER, "must be greater than 0");
+        }
+        if self.webhook.timeout.is_zero() {
+            return invalid(WEBHOOK_TIMEOUT_SEC, "must be greater than 0");
+        }
+
+        // -- Admin
+        if let Some(admin) = &self.admin_seed {
+            if admin.username.trim().is_empty() {
+                return invalid(ADMIN_USERNAME, "must be non empty");
+            }
+            if admin.pwd.is_empty() {
+                return invalid(ADMIN_PWD, "must be non empty");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Environment first, then the config file value.
+struct Setting<'a> {
+    env: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl Setting<'_> {
+    fn get<T: FromStr>(&self, key: ConfigKey, file_value: Option<T>) -> Result<Option<T>> {
+        match (self.env)(key.env) {
+            Some(v) => v
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| Error::ConfigWrongFormat(key)),
+            None => Ok(file_value),
+        }
+    }
+}
+
+fn get_env_opt(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+// endregion: --- Config Loader
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const SECRET: &str = "swZVdN6rOEjvju8roSBNCX5P6QzHsE_06fGxcLddbaEXoeaPs4VS3koOUQE8_Gul";
+
+    fn from_sources(toml: &str, env: &[(&str, &str)]) -> Result<Config> {
+        let file = ConfigFile::parse(toml).map_err(|cause| Error::ConfigFileInvalid {
+            path: "test.toml".to_string(),
+            cause,
+        })?;
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        Config::from_sources(file, &|name| env.get(name).cloned())
+    }
+
+    #[test]
+    fn test_config_file_and_env_override() -> Result<()> {
+        let toml = format!(
+            r#"
+            [web]
+            bind_addr = "0.0.0.0:3000"
+
+            [token]
+            secret = "{SECRET}"
+            ttl_sec = 60
+
+            [log]
+            sink = "file:logs/requests.jsonl"
+
+            [rate_limit]
+            login = "3/10"
+            api = "100/60"
+            "#
+        );
+
+        let config = from_sources(
+            &toml,
+            &[
+                ("SERVICE_TOKEN_TTL_SEC", "120"),
+                ("SERVICE_SESSION_IDLE_TIMEOUT_SEC", "600"),
+                ("SERVICE_RATE_LIMIT_API", "20/1"),
+            ],
+        )?;
+
+        assert_eq!(config.bind_addr, SocketAddr::from(([0, 0, 0, 0], 3000)));
+        assert_eq!(config.token_ttl, Duration::from_secs(120));
+        assert_eq!(config.session_idle_timeout, Duration::from_secs(600));
+        assert_eq!(config.auth_cookie_name, "auth-token");
+        assert!(matches!(config.log_sink, LogSinkConfig::File { .. }));
+        assert!(matches!(config.store, StoreConfig::Memory));
+        assert_eq!(config.rate_limit_login, "3/10".parse().unwrap());
+        assert_eq!(config.rate_limit_api, "20/1".parse().unwrap());
+        assert!(config.admin_seed.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_errors() -> Result<()> {
+        let secret = format!("[token]\nsecret = \"{SECRET}\"");
+
+        let res = from_sources("", &[]);
+        assert!(matches!(res, Err(Error::ConfigMissing(TOKEN_SECRET))));
+
+        let res = from_sources(&secret, &[("SERVICE_BIND_ADDR", "localhost")]);
+        assert!(matches!(res, Err(Error::ConfigWrongFormat(BIND_ADDR))));
+
+        let res = from_sources(&secret, &[("SERVICE_TOKEN_TTL_SEC", "0")]);
+        assert!(matches!(
+            res,
+            Err(Error::ConfigInvalid {
+                key: TOKEN_TTL_SEC,
+                ..
+            })
+        ));
+
+        let res = from_sources(&secret, &[("SERVICE_SESSION_IDLE_TIMEOUT_SEC", "30")]);
+        assert!(matches!(
+            res,
+            Err(Error::ConfigInvalid {
+                key: SESSION_IDLE_TIMEOUT_SEC,
+                ..
+            })
+        ));
+
+        let res = from_sources(&secret, &[("SERVICE_RATE_LIMIT_LOGIN", "10/0")]);
+        assert!(matches!(
+            res,
+            Err(Error::ConfigWrongFormat(RATE_LIMIT_LOGIN))
+        ));
+
+        let res = from_sources(&format!("{secret}\n\n[web]\nbind = 1"), &[]);
+        let Err(Error::ConfigFileInvalid { cause, .. }) = res else {
+            panic!("should be ConfigFileInvalid, was: {res:?}");
+        };
+        assert!(cause.starts_with("line 5 - "), "{cause}");
+
+        let res = from_sources(&format!("{secret}\n\n[admin]\nusername = \"admin\""), &[]);
+        assert!(matches!(res, Err(Error::ConfigMissing(ADMIN_PWD))));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,3 @@
+pub mod pwd;
+pub mod token;
+pub mod webhook;
@@ -0,0 +1,105 @@
+//! Password hashing, with versioned schemes.
+//!
+//! Stored hashes have the format `#[scheme]#[hash]`, so a new scheme can be
+//! added later and old hashes upgraded on the next successful login.
+//!
+//! Schemes:
+//! - `01`: argon2id, default params, PHC string format.
+
+use crate::{Error, Result};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use lazy_regex::regex_captures;
+
+pub const DEFAULT_SCHEME: &str = "01";
+
+/// Result of a successful password validation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SchemeStatus {
+    /// The hash uses the default scheme.
+    Ok,
+    /// The hash should be re-computed with the default scheme.
+    Outdated,
+}
+
+/// Hash `pwd` with a new random salt and the default scheme.
+pub async fn hash_pwd(pwd: String) -> Result<String> {
+    // Hashing is CPU heavy (by design), keep it off the async workers.
+    tokio::task::spawn_blocking(move || hash_for_scheme(DEFAULT_SCHEME, &pwd))
+        .await
+        .map_err(|_| Error::PwdFailSpawnBlock)?
+}
+
+/// Validate `pwd` against a `#[scheme]#[hash]` reference.
+pub async fn validate_pwd(pwd: String, pwd_ref: String) -> Result<SchemeStatus> {
+    tokio::task::spawn_blocking(move || {
+        let (scheme, hash) = split_pwd_ref(&pwd_ref)?;
+        validate_for_scheme(scheme, &pwd, hash)?;
+
+        if scheme == DEFAULT_SCHEME {
+            Ok(SchemeStatus::Ok)
+        } else {
+            Ok(SchemeStatus::Outdated)
+        }
+    })
+    .await
+    .map_err(|_| Error::PwdFailSpawnBlock)?
+}
+
+fn hash_for_scheme(scheme: &str, pwd: &str) -> Result<String> {
+    match scheme {
+        "01" => {
+            let salt = SaltString::generate(&mut OsRng);
+            let hash = Argon2::default()
+                .hash_password(pwd.as_bytes(), &salt)
+                .map_err(|_| Error::PwdFailHash)?;
+
+            Ok(format!("#{scheme}#{hash}"))
+        }
+        _ => Err(Error::PwdSchemeUnknown(scheme.to_string())),
+    }
+}
+
+fn validate_for_scheme(scheme: &str, pwd: &str, hash: &str) -> Result<()> {
+    match scheme {
+        "01" => {
+            let hash = PasswordHash::new(hash).map_err(|_| Error::PwdFailHash)?;
+
+            Argon2::default()
+                .verify_password(pwd.as_bytes(), &hash)
+                .map_err(|_| Error::PwdNotMatching)
+        }
+        _ => Err(Error::PwdSchemeUnknown(scheme.to_string())),
+    }
+}
+
+fn split_pwd_ref(pwd_ref: &str) -> Result<(&str, &str)> {
+    let (_whole, scheme, hash) =
+        regex_captures!(r#"^#(\w+)#(.*)$"#, pwd_ref).ok_or(Error::PwdRefWrongFormat)?;
+
+    Ok((scheme, hash))
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pwd_hash_and_validate() -> Result<()> {
+        let pwd_ref = hash_pwd("welcome".to_string()).await?;
+
+        assert!(pwd_ref.starts_with("#01#$argon2id$"));
+        let status = validate_pwd("welcome".to_string(), pwd_ref.clone()).await?;
+        assert_eq!(status, SchemeStatus::Ok);
+
+        let res = validate_pwd("not-welcome".to_string(), pwd_ref).await;
+        assert!(matches!(res, Err(Error::PwdNotMatching)));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
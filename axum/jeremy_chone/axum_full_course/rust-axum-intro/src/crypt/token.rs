@@ -0,0 +1,143 @@
+//! Auth token of format `session-[session-id].[expiration].[signature]`
+//!
+//! - session-id: the simple (32 hex chars) uuid of the login session (see `model::Session`).
+//! - expiration: unix timestamp in seconds.
+//! - signature: base64url (no pad) of the HMAC-SHA256 of `session-[session-id].[expiration]`
+//!   with the server secret.
+
+use crate::{Error, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use lazy_regex::regex_captures;
+use sha2::Sha256;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub session_id: Uuid,
+    pub exp: u64,
+    pub sign: String,
+}
+
+impl FromStr for Token {
+    type Err = Error;
+
+    fn from_str(token: &str) -> Result<Self> {
+        let (_whole, session_id, exp, sign) = regex_captures!(
+            r#"^session-([0-9a-f]{32})\.(\d+)\.([A-Za-z0-9_-]+)$"#, // a literal regex
+            token
+        )
+        .ok_or(Error::AuthFailTokenWrongFormat)?;
+
+        let session_id =
+            Uuid::parse_str(session_id).map_err(|_| Error::AuthFailTokenWrongFormat)?;
+        let exp: u64 = exp.parse().map_err(|_| Error::AuthFailTokenWrongFormat)?;
+
+        Ok(Self {
+            session_id,
+            exp,
+            sign: sign.to_string(),
+        })
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "session-{}.{}.{}",
+            self.session_id.simple(),
+            self.exp,
+            self.sign
+        )
+    }
+}
+
+/// Create a new token for `session_id`, expiring `ttl` from now.
+pub fn generate_token(session_id: Uuid, ttl: Duration, secret: &[u8]) -> Token {
+    let exp = now_unix_sec() + ttl.as_secs();
+    let mac = token_mac(session_id, exp, secret);
+
+    Token {
+        session_id,
+        exp,
+        sign: URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()),
+    }
+}
+
+/// Check the token signature first, then its expiration.
+pub fn validate_token(token: &Token, secret: &[u8]) -> Result<()> {
+    let sign = URL_SAFE_NO_PAD
+        .decode(&token.sign)
+        .map_err(|_| Error::AuthFailTokenBadSignature)?;
+
+    // `verify_slice` does a constant time comparison.
+    token_mac(token.session_id, token.exp, secret)
+        .verify_slice(&sign)
+        .map_err(|_| Error::AuthFailTokenBadSignature)?;
+
+    if token.exp <= now_unix_sec() {
+        return Err(Error::AuthFailTokenExpired);
+    }
+
+    Ok(())
+}
+
+fn token_mac(session_id: Uuid, exp: u64, secret: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(format!("session-{}.{exp}", session_id.simple()).as_bytes());
+
+    mac
+}
+
+fn now_unix_sec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"some-test-secret";
+
+    #[test]
+    fn test_token_roundtrip_ok() -> Result<()> {
+        let token = generate_token(Uuid::new_v4(), Duration::from_secs(60), SECRET);
+        let parsed: Token = token.to_string().parse()?;
+
+        assert_eq!(parsed, token);
+        validate_token(&parsed, SECRET)
+    }
+
+    #[test]
+    fn test_token_tampered_err() -> Result<()> {
+        let mut token = generate_token(Uuid::new_v4(), Duration::from_secs(60), SECRET);
+        token.session_id = Uuid::new_v4();
+
+        let res = validate_token(&token, SECRET);
+        assert!(matches!(res, Err(Error::AuthFailTokenBadSignature)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_expired_err() -> Result<()> {
+        let token = generate_token(Uuid::new_v4(), Duration::ZERO, SECRET);
+
+        let res = validate_token(&token, SECRET);
+        assert!(matches!(res, Err(Error::AuthFailTokenExpired)));
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
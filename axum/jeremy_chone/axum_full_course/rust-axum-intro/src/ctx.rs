@@ -1,12 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
 #[derive(Debug, Clone)]
 pub struct Ctx {
     user_id: u64,
+    role: Role,
+    /// Request the ctx was resolved for, if any (recorded in the audit).
+    req_uuid: Option<Uuid>,
+    /// Login session of the request, if any (see `model::Session`).
+    session_id: Option<Uuid>,
+}
+
+/// Role of a user, checked by the model layer on each operation.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    strum_macros::AsRefStr,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Role {
+    /// Can read and change all tickets.
+    Admin,
+    /// Can create tickets, and read/change only their own.
+    Member,
+    /// Can read all tickets, but not change anything.
+    Viewer,
 }
 
 // Constructor
 impl Ctx {
-    pub fn new(user_id: u64) -> Self {
-        Self { user_id }
+    pub fn new(user_id: u64, role: Role) -> Self {
+        Self {
+            user_id,
+            role,
+            req_uuid: None,
+            session_id: None,
+        }
+    }
+
+    pub fn with_req_uuid(mut self, req_uuid: Uuid) -> Self {
+        self.req_uuid = Some(req_uuid);
+        self
+    }
+
+    pub fn with_session_id(mut self, session_id: Uuid) -> Self {
+        self.session_id = Some(session_id);
+        self
     }
 }
 
@@ -15,4 +63,16 @@ impl Ctx {
     pub fn user_id(&self) -> u64 {
         self.user_id
     }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn req_uuid(&self) -> Option<Uuid> {
+        self.req_uuid
+    }
+
+    pub fn session_id(&self) -> Option<Uuid> {
+        self.session_id
+    }
 }
@@ -1,7 +1,11 @@
+use crate::config::ConfigKey;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use serde::Serialize;
 use std::fmt::Formatter;
+use tracing::debug;
+use utoipa::ToSchema;
+use uuid::Uuid;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -9,19 +13,173 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[serde(tag = "type", content = "data")]
 pub enum Error {
     LoginFail,
+    LoginLocked {
+        retry_after_sec: u64,
+    },
+    RegisterFailEmptyField {
+        field: &'static str,
+    },
+    RegisterFailUsernameExists {
+        username: String,
+    },
+
+    // -- Config errors.
+    ConfigFileRead {
+        path: String,
+        cause: String,
+    },
+    ConfigFileInvalid {
+        path: String,
+        cause: String,
+    },
+    ConfigMissing(ConfigKey),
+    ConfigWrongFormat(ConfigKey),
+    ConfigInvalid {
+        key: ConfigKey,
+        reason: &'static str,
+    },
+
+    // -- Crypt errors.
+    PwdFailSpawnBlock,
+    PwdFailHash,
+    PwdNotMatching,
+    PwdRefWrongFormat,
+    PwdSchemeUnknown(String),
+
+    // -- Store errors.
+    Store(String),
+    StoreSchemaTooNew {
+        version: usize,
+        supported: usize,
+    },
 
     // -- Auth errors.
     AuthFailNoAuthTokenCookie,
     AuthFailTokenWrongFormat,
+    AuthFailTokenExpired,
+    AuthFailTokenBadSignature,
     AuthFailCtxNotInRequestExt,
+    AuthFailUserNotFound,
+    AuthFailSessionNotFound,
+    AuthFailSessionExpired,
+
+    // -- Request errors.
+    ReqStampNotInRequestExt,
+    CsrfTokenInvalid,
+
+    // -- Html errors.
+    TemplateRenderFail {
+        cause: String,
+    },
+
+    // -- Rate limit errors.
+    RateLimited {
+        retry_after_sec: u64,
+    },
+
+    // -- Access errors.
+    AccessDenied {
+        action: &'static str,
+    },
 
     // -- Model errors.
-    TicketDeleteFailIdNotFound { id: u64 },
+    TicketDeleteFailIdNotFound {
+        id: u64,
+    },
+    TicketNotFound {
+        id: u64,
+    },
+    TicketNotInTrash {
+        id: u64,
+    },
+    /// The ticket is at `version`, not the one the change was made from.
+    TicketPreconditionFailed {
+        id: u64,
+        version: u64,
+    },
+    TicketTitleEmpty,
+    TicketStatusUnknown {
+        status: String,
+    },
+    TicketPriorityUnknown {
+        priority: String,
+    },
+    TicketAssigneeNotFound {
+        assignee: u64,
+    },
+    TicketLabelNotFound {
+        label_id: u64,
+    },
+    LabelNotFound {
+        id: u64,
+    },
+    LabelNameInvalid {
+        name: String,
+    },
+    LabelNameTaken {
+        name: String,
+    },
+    LabelColorInvalid {
+        color: String,
+    },
+    CommentNotFound {
+        id: u64,
+    },
+    CommentBodyEmpty,
+    WebhookNotFound {
+        id: u64,
+    },
+    WebhookUrlInvalid {
+        url: String,
+    },
+    WebhookEventUnknown {
+        event: String,
+    },
+    SessionNotFound {
+        id: Uuid,
+    },
+    UserNotFound {
+        id: u64,
+    },
+    ListOrderByUnknown {
+        order_by: String,
+    },
+    ListCursorInvalid {
+        cursor: String,
+    },
+    AuditTimeInvalid {
+        time: String,
+    },
+    SearchQueryEmpty {
+        q: String,
+    },
+    IdempotencyKeyInvalid {
+        key: String,
+    },
+    BulkFormatUnknown {
+        format: String,
+    },
+    BulkEncodeFail {
+        cause: String,
+    },
+    TicketImportInvalid {
+        reason: String,
+    },
+    TicketImportRowInvalid {
+        cause: String,
+    },
+    TicketImportTooManyRows {
+        max: usize,
+    },
+    /// Same key as an earlier create, but not the same create.
+    IdempotencyKeyReused {
+        key: String,
+    },
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        println!("->> {:<12} - {self:?}", "INTO_RES");
+        debug!("{:<12} - {self:?}", "INTO_RES");
 
         // Create a placeholder Axum response.
         let mut response = StatusCode::INTERNAL_SERVER_ERROR.into_response();
@@ -38,14 +196,68 @@ impl Error {
         #[allow(unreachable_patterns)]
         match self {
             Self::LoginFail => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
+            Self::LoginLocked { .. } => (StatusCode::TOO_MANY_REQUESTS, ClientError::LOGIN_LOCKED),
+            Self::RegisterFailEmptyField { .. } => {
+                (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS)
+            }
+            Self::RegisterFailUsernameExists { .. } => {
+                (StatusCode::CONFLICT, ClientError::USERNAME_TAKEN)
+            }
             // -- Auth.
             Self::AuthFailCtxNotInRequestExt
             | Self::AuthFailNoAuthTokenCookie
-            | Self::AuthFailTokenWrongFormat => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
+            | Self::AuthFailTokenWrongFormat
+            | Self::AuthFailTokenExpired
+            | Self::AuthFailTokenBadSignature
+            | Self::AuthFailUserNotFound
+            | Self::AuthFailSessionNotFound
+            | Self::AuthFailSessionExpired => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
+            // -- Access.
+            Self::AccessDenied { .. } | Self::CsrfTokenInvalid => {
+                (StatusCode::FORBIDDEN, ClientError::ACCESS_DENIED)
+            }
+            // -- Rate limit.
+            Self::RateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, ClientError::RATE_LIMITED),
+            // -- Concurrency.
+            Self::TicketPreconditionFailed { .. } => (
+                StatusCode::PRECONDITION_FAILED,
+                ClientError::PRECONDITION_FAILED,
+            ),
             // -- Model.
-            Self::TicketDeleteFailIdNotFound { .. } => {
+            Self::TicketDeleteFailIdNotFound { .. }
+            | Self::TicketNotFound { .. }
+            | Self::TicketNotInTrash { .. }
+            | Self::TicketTitleEmpty
+            | Self::TicketStatusUnknown { .. }
+            | Self::TicketPriorityUnknown { .. }
+            | Self::TicketAssigneeNotFound { .. }
+            | Self::TicketLabelNotFound { .. }
+            | Self::LabelNotFound { .. }
+            | Self::LabelNameInvalid { .. }
+            | Self::LabelColorInvalid { .. }
+            | Self::CommentNotFound { .. }
+            | Self::CommentBodyEmpty
+            | Self::WebhookNotFound { .. }
+            | Self::WebhookUrlInvalid { .. }
+            | Self::WebhookEventUnknown { .. }
+            | Self::SessionNotFound { .. }
+            | Self::UserNotFound { .. }
+            | Self::ListOrderByUnknown { .. }
+            | Self::ListCursorInvalid { .. }
+            | Self::AuditTimeInvalid { .. }
+            | Self::SearchQueryEmpty { .. }
+            | Self::IdempotencyKeyInvalid { .. }
+            | Self::BulkFormatUnknown { .. }
+            | Self::TicketImportInvalid { .. }
+            | Self::TicketImportRowInvalid { .. }
+            | Self::TicketImportTooManyRows { .. } => {
                 (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS)
             }
+            Self::LabelNameTaken { .. } => (StatusCode::CONFLICT, ClientError::LABEL_NAME_TAKEN),
+            Self::IdempotencyKeyReused { .. } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::IDEMPOTENCY_KEY_REUSED,
+            ),
             // -- Fallback.
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -53,6 +265,16 @@ impl Error {
             ),
         }
     }
+
+    /// For the `Retry-After` header of the 429 responses.
+    pub fn retry_after_sec(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited { retry_after_sec } | Self::LoginLocked { retry_after_sec } => {
+                Some(*retry_after_sec)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -63,11 +285,38 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
-#[derive(Debug, strum_macros::AsRefStr)]
+impl From<rusqlite::Error> for Error {
+    fn from(val: rusqlite::Error) -> Self {
+        Self::Store(val.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema, strum_macros::AsRefStr)]
 #[allow(non_camel_case_types)]
 pub enum ClientError {
     LOGIN_FAIL,
+    LOGIN_LOCKED,
+    RATE_LIMITED,
+    USERNAME_TAKEN,
+    LABEL_NAME_TAKEN,
     NO_AUTH,
+    ACCESS_DENIED,
+    PRECONDITION_FAILED,
+    IDEMPOTENCY_KEY_REUSED,
     INVALID_PARAMS,
     SERVICE_ERROR,
 }
+
+/// Body of all the error responses (built by the `main_response_mapper`).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClientErrorBody {
+    pub error: ClientErrorDetail,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClientErrorDetail {
+    #[serde(rename = "type")]
+    pub error_type: ClientError,
+    /// Same as the `x-request-id` response header.
+    pub req_uuid: Uuid,
+}
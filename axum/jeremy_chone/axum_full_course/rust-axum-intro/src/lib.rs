@@ -0,0 +1,203 @@
+pub use self::error::{Error, Result};
+
+use crate::error::{ClientErrorBody, ClientErrorDetail};
+
+use crate::config::config;
+use crate::ctx::Ctx;
+use crate::log::log_request;
+use crate::model::ModelController;
+use crate::shutdown::Shutdown;
+use crate::web::mw_auth::mw_require_auth;
+use crate::web::mw_rate_limit::{mw_rate_limit, RateLimiter};
+use crate::web::mw_req_stamp::{mw_req_stamp, ReqStamp};
+use axum::extract::{MatchedPath, Path, Query};
+use axum::http::{Method, Uri};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, get_service};
+use axum::{http, middleware, Json, Router};
+use serde::Deserialize;
+use tower_cookies::CookieManagerLayer;
+use tower_http::services::ServeDir;
+use tracing::debug;
+
+pub mod config;
+mod crypt;
+pub mod ctx;
+mod error;
+pub mod log;
+mod metrics;
+pub mod model;
+pub mod shutdown;
+pub mod trace;
+mod web;
+
+/// Build the full app router (apis, middlewares, and static fallback).
+/// Used by `main`, and by the tests to run the app in-process.
+///
+/// Must be served with `into_make_service_with_connect_info::<SocketAddr>`,
+/// for the per IP rate limits.
+/// The `shutdown` handle is the one draining the server, for `/readyz`.
+pub fn app(mc: ModelController, shutdown: Shutdown) -> Router {
+    let rate_limit = |limiter| middleware::from_fn_with_state(limiter, mw_rate_limit);
+
+    let routes_apis = web::routes_tickets::routes(mc.clone())
+        .merge(web::routes_ticket_events::routes(mc.clone()))
+        .merge(web::routes_comments::routes(mc.clone()))
+        .merge(web::routes_labels::routes(mc.clone()))
+        .merge(web::routes_users::routes(mc.clone()))
+        .merge(web::routes_audit::routes(mc.clone()))
+        .merge(web::routes_webhooks::routes(mc.clone()))
+        .merge(web::routes_sessions::routes(mc.clone()))
+        .route_layer(middleware::from_fn(mw_require_auth))
+        .route_layer(rate_limit(RateLimiter::per_user(config().rate_limit_api)));
+
+    let routes_login = web::routes_login::routes(mc.clone())
+        .merge(web::routes_board::routes_login(mc.clone()))
+        .route_layer(rate_limit(RateLimiter::per_ip(config().rate_limit_login)));
+
+    // The html board, redirecting to its login page when not logged in.
+    let routes_board = web::routes_board::routes(mc.clone())
+        .route_layer(rate_limit(RateLimiter::per_user(config().rate_limit_api)));
+
+    let routes_public = routes_hello()
+        .merge(web::routes_metrics::routes(mc.clone()))
+        .merge(web::routes_docs::routes())
+        .route_layer(rate_limit(RateLimiter::per_ip(config().rate_limit_public)));
+
+    // No rate limit on the probes.
+    let routes_health = web::routes_health::routes(mc.clone(), shutdown);
+
+    Router::new()
+        .merge(routes_health)
+        .merge(routes_public)
+        .merge(routes_login)
+        .merge(routes_board)
+        .nest("/api", routes_apis)
+        .layer(middleware::map_response(main_response_mapper))
+        .layer(middleware::from_fn_with_state(
+            mc.clone(),
+            web::mw_auth::mw_ctx_resolver,
+        ))
+        .layer(CookieManagerLayer::new())
+        .layer(middleware::from_fn(mw_req_stamp))
+        .fallback_service(routes_static())
+}
+
+async fn main_response_mapper(
+    ctx: Option<Ctx>,
+    req_stamp: ReqStamp,
+    matched_path: Option<MatchedPath>,
+    uri: Uri,
+    req_method: Method,
+    res: Response,
+) -> Response {
+    debug!("{:<12} - main_response_mapper", "RES_MAPPER");
+    let uuid = req_stamp.uuid;
+
+    // -- Get the eventual response error.
+    let service_error = res.extensions().get::<Error>().cloned();
+    let client_status_error = service_error
+        .as_ref()
+        .map(|se| se.client_status_and_error());
+
+    // -- If client error, build the new response.
+    let error_response = client_status_error
+        .as_ref()
+        .map(|(status_code, client_error)| {
+            let client_error_body = ClientErrorBody {
+                error: ClientErrorDetail {
+                    error_type: *client_error,
+                    req_uuid: uuid,
+                },
+            };
+
+            debug!(
+                "{:<12} - client_error_body: {client_error_body:?}",
+                "RES_MAPPER"
+            );
+
+            // Build the new response from the client_error_body
+            let mut res = (*status_code, Json(client_error_body)).into_response();
+            let retry_after_sec = service_error.as_ref().and_then(Error::retry_after_sec);
+            if let Some(retry_after_sec) = retry_after_sec {
+                res.headers_mut()
+                    .insert(http::header::RETRY_AFTER, retry_after_sec.into());
+            }
+            res
+        });
+
+    //  Build and log the server log line.
+    let client_error = client_status_error.unzip().1;
+    let res = error_response.unwrap_or(res);
+    let route = matched_path
+        .as_ref()
+        .map_or(metrics::ROUTE_UNMATCHED, |mp| mp.as_str());
+    metrics::record_request(
+        &req_method,
+        route,
+        res.status(),
+        req_stamp.instant_in.elapsed(),
+        service_error.as_ref(),
+        client_error.as_ref(),
+    );
+    log_request(
+        req_stamp,
+        req_method,
+        uri,
+        res.status(),
+        ctx,
+        service_error.as_ref(),
+        client_error,
+    )
+    .await;
+
+    res
+}
+
+fn routes_static() -> Router {
+    Router::new().nest_service("/", get_service(ServeDir::new(&config().static_dir)))
+}
+
+// region:  --- Routes Hello
+fn routes_hello() -> Router {
+    Router::new()
+        .route("/hello", get(handler_hello))
+        .route("/hello2/:name", get(handler_hello2))
+}
+#[derive(Debug, Deserialize)]
+struct HelloParams {
+    name: Option<String>,
+}
+
+// e.g. `/hello?name=Jen`
+#[utoipa::path(
+    get,
+    path = "/hello",
+    tag = "misc",
+    security(()),
+    params(("name" = Option<String>, Query, description = "Default `World!`")),
+    responses((status = 200, content_type = "text/html", body = String)),
+)]
+async fn handler_hello(Query(params): Query<HelloParams>) -> impl IntoResponse {
+    debug!("{:<12} - handler_hello - {params:?}", "HANDLER");
+
+    let name = params.name.as_deref().unwrap_or("World!");
+    Html(format!("Hello <strong>{name}</strong>"))
+}
+
+// e.g., `/hello2/Mike
+#[utoipa::path(
+    get,
+    path = "/hello2/{name}",
+    tag = "misc",
+    security(()),
+    params(("name" = String, Path, description = "Who to greet")),
+    responses((status = 200, content_type = "text/html", body = String)),
+)]
+async fn handler_hello2(Path(name): Path<String>) -> impl IntoResponse {
+    debug!("{:<12} - handler_hello2 - {name:?}", "HANDLER");
+
+    Html(format!("Hello <strong>{name}</strong>"))
+}
+
+// endregion: --- Handler Hello
@@ -1,25 +1,35 @@
+mod sink;
+
+pub use self::sink::{flush_sink, init_sink};
+
 use crate::ctx::Ctx;
 use crate::error::ClientError;
-use crate::{Error, Result};
-use axum::http::{Method, Uri};
+use crate::web::mw_req_stamp::ReqStamp;
+use crate::Error;
+use axum::http::{Method, StatusCode, Uri};
 use serde::Serialize;
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
-use std::time::{SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
+use time::format_description::well_known::Rfc3339;
 
+/// Queue the request log line, dropped if the sink is behind (see `sink`).
 pub async fn log_request(
-    uuid: Uuid,
+    req_stamp: ReqStamp,
     req_method: Method,
     uri: Uri,
+    res_status: StatusCode,
     ctx: Option<Ctx>,
     service_error: Option<&Error>,
     client_error: Option<ClientError>,
-) -> Result<()> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
+) {
+    let ReqStamp {
+        uuid,
+        time_in,
+        instant_in,
+    } = req_stamp;
+
+    let timestamp = time_in.format(&Rfc3339).unwrap_or_default();
+    let duration_ms = instant_in.elapsed().as_secs_f64() * 1000.;
 
     let error_type = service_error.map(|se| se.as_ref().to_string());
     let error_data = serde_json::to_value(service_error)
@@ -29,31 +39,31 @@ pub async fn log_request(
     // Create the RequestLogLine
     let log_line = RequestLogLine {
         uuid: uuid.to_string(),
-        timestamp: timestamp.to_string(),
-        
+        timestamp,
+        duration_ms,
+
         req_path: uri.to_string(),
         req_method: req_method.to_string(),
-        
+        res_status: res_status.as_u16(),
+
         user_id: ctx.map(|c| c.user_id()),
         client_error_type: client_error.map(|e| e.as_ref().to_string()),
-        
+
         error_type,
         error_data,
     };
-    
-    println!("  ->> log_request: \n{}", json!(log_line));
-    
-    // Todo - Send to cloud-watch.
-    
-    Ok(())
+
+    // Queued for the background writer (see `sink`).
+    sink::send_line(json!(log_line).to_string());
 }
 
 #[skip_serializing_none]
 #[derive(Serialize)]
 struct RequestLogLine {
     uuid: String,      // uuid string formatted
-    timestamp: String, // (should be iso8601)
-    
+    timestamp: String, // iso8601 (rfc3339), request arrival time
+    duration_ms: f64,
+
     // -- User and context attributes.
     user_id: Option<u64>,
 
@@ -61,9 +71,11 @@ struct RequestLogLine {
     req_path: String,
     req_method: String,
 
+    // -- http response attributes.
+    res_status: u16,
+
     // -- Errors attributes.
     client_error_type: Option<String>,
     error_type: Option<String>,
     error_data: Option<Value>,
 }
-
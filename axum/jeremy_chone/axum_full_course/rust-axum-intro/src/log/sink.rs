@@ -0,0 +1,443 @@
+//! Request log sinks, written by a dedicated background thread.
+//!
+//! The handlers only push the lines into a bounded queue (never waiting),
+//! so a slow disk or collector can't slow down the requests.
+//! When the queue is full, lines are dropped (and counted).
+
+use crate::config::LogSinkConfig;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use time::{OffsetDateTime, UtcOffset};
+use tracing::warn;
+
+const QUEUE_CAPACITY: usize = 10_000;
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const TCP_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+const TCP_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const TCP_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+static WRITER: OnceLock<SyncSender<Msg>> = OnceLock::new();
+static DROPPED_LINES: AtomicU64 = AtomicU64::new(0);
+
+enum Msg {
+    Line(String),
+    /// Flush the sink, then reply (once the lines queued before are written).
+    Flush(Sender<()>),
+}
+
+/// Start the background writer for this sink.
+/// Only the first call has an effect, later ones are ignored.
+pub fn init_sink(sink_config: &LogSinkConfig) {
+    WRITER.get_or_init(|| {
+        let (tx, rx) = sync_channel(QUEUE_CAPACITY);
+        let sink = Sink::new(sink_config.clone());
+
+        thread::Builder::new()
+            .name("request-log-writer".to_string())
+            .spawn(move || run_writer(sink, rx))
+            .expect("FATAL - Cannot spawn the request log writer thread");
+
+        tx
+    });
+}
+
+/// Queue a line for the writer (never blocks).
+/// Before `init_sink`, lines go straight to stdout.
+pub fn send_line(line: String) {
+    let Some(writer) = WRITER.get() else {
+        println!("{line}");
+        return;
+    };
+
+    queue_line(writer, line);
+}
+
+fn queue_line(writer: &SyncSender<Msg>, line: String) {
+    match writer.try_send(Msg::Line(line)) {
+        Ok(()) => (),
+        Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+            DROPPED_LINES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Wait for the queued lines to be written and flushed, for up to `timeout`.
+/// Returns false on timeout.
+/// Note: Blocks the thread, so call it from a blocking task.
+pub fn flush_sink(timeout: Duration) -> bool {
+    let Some(writer) = WRITER.get() else {
+        return io::stdout().flush().is_ok();
+    };
+
+    let (tx, rx) = channel();
+    // Blocks while the queue is full.
+    if writer.send(Msg::Flush(tx)).is_err() {
+        return false;
+    }
+
+    rx.recv_timeout(timeout).is_ok()
+}
+
+fn run_writer(mut sink: Sink, rx: Receiver<Msg>) {
+    for msg in rx {
+        let dropped = DROPPED_LINES.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("{:<12} - {dropped} request log lines dropped", "LOG_SINK");
+        }
+
+        match msg {
+            Msg::Line(line) => {
+                if let Err(ex) = sink.write_line(&line) {
+                    warn!("{:<12} - write failed - {ex}", "LOG_SINK");
+                }
+            }
+            Msg::Flush(done) => {
+                if let Err(ex) = sink.flush() {
+                    warn!("{:<12} - flush failed - {ex}", "LOG_SINK");
+                }
+                // The flusher may have timed out, and be gone.
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+// region:    --- Sink
+
+enum Sink {
+    Stdout,
+    File(RotatingFile),
+    Udp {
+        socket: Option<UdpSocket>,
+        addr: String,
+    },
+    Tcp(TcpSink),
+}
+
+impl Sink {
+    fn new(sink_config: LogSinkConfig) -> Self {
+        match sink_config {
+            LogSinkConfig::Stdout => Sink::Stdout,
+            LogSinkConfig::File {
+                path,
+                max_bytes,
+                max_age,
+            } => Sink::File(RotatingFile {
+                path,
+                max_bytes,
+                max_age,
+                current: None,
+            }),
+            LogSinkConfig::Udp { addr } => Sink::Udp { socket: None, addr },
+            LogSinkConfig::Tcp { addr } => Sink::Tcp(TcpSink {
+                addr,
+                stream: None,
+                backoff: TCP_BACKOFF_MIN,
+                retry_at: None,
+            }),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Sink::Stdout => writeln!(io::stdout().lock(), "{line}"),
+            Sink::File(file) => file.write_line(line),
+            Sink::Udp { socket, addr } => {
+                if socket.is_none() {
+                    *socket = Some(UdpSocket::bind("0.0.0.0:0")?);
+                }
+                // Checked above.
+                let socket = socket.as_ref().unwrap();
+                socket.send_to(line.as_bytes(), addr.as_str()).map(|_| ())
+            }
+            Sink::Tcp(tcp) => tcp.write_line(line),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Stdout => io::stdout().flush(),
+            Sink::File(file) => file
+                .current
+                .as_mut()
+                .map_or(Ok(()), |current| current.file.sync_data()),
+            Sink::Udp { .. } => Ok(()),
+            Sink::Tcp(tcp) => tcp.stream.as_mut().map_or(Ok(()), |stream| stream.flush()),
+        }
+    }
+}
+
+// endregion: --- Sink
+
+// region:    --- RotatingFile
+
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_age: Duration,
+    current: Option<CurrentFile>,
+}
+
+struct CurrentFile {
+    file: File,
+    len: u64,
+    opened: Instant,
+}
+
+impl RotatingFile {
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let needs_rotation = self.current.as_ref().is_some_and(|current| {
+            current.len >= self.max_bytes || current.opened.elapsed() >= self.max_age
+        });
+        if needs_rotation {
+            self.rotate()?;
+        }
+
+        if self.current.is_none() {
+            self.current = Some(self.open()?);
+        }
+        let current = self.current.as_mut().unwrap();
+
+        current.file.write_all(format!("{line}\n").as_bytes())?;
+        current.len += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn open(&self) -> io::Result<CurrentFile> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let len = file.metadata()?.len();
+
+        Ok(CurrentFile {
+            file,
+            len,
+            opened: Instant::now(),
+        })
+    }
+
+    /// Move the current file to `[path].[YYYYMMDD]T[HHMMSS]Z`
+    /// (with a `-[n]` suffix when rotated more than once in the same second).
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.file.flush()?;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        // Unbounded, so always found.
+        let rotated = (0..)
+            .map(|n| self.rotated_path(now, n))
+            .find(|rotated| !rotated.exists())
+            .unwrap();
+
+        fs::rename(&self.path, rotated)
+    }
+
+    fn rotated_path(&self, now: OffsetDateTime, n: u32) -> PathBuf {
+        let now = now.to_offset(UtcOffset::UTC);
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(
+            ".{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+            now.year(),
+            u8::from(now.month()),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        ));
+        if n > 0 {
+            rotated.push(format!("-{n}"));
+        }
+
+        rotated.into()
+    }
+}
+
+// endregion: --- RotatingFile
+
+// region:    --- TcpSink
+
+struct TcpSink {
+    addr: String,
+    stream: Option<TcpStream>,
+    /// Wait before the next connect, doubled on each failure.
+    backoff: Duration,
+    retry_at: Option<Instant>,
+}
+
+impl TcpSink {
+    /// While the collector is down, the lines are dropped (and counted) until
+    /// the next connect attempt, instead of blocking on a connect per line.
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.stream.is_none() {
+            if self
+                .retry_at
+                .is_some_and(|retry_at| Instant::now() < retry_at)
+            {
+                DROPPED_LINES.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+            match self.connect() {
+                Ok(stream) => self.stream = Some(stream),
+                Err(ex) => {
+                    self.retry_later();
+                    return Err(ex);
+                }
+            }
+        }
+
+        // Connected above.
+        let stream = self.stream.as_mut().unwrap();
+        match stream.write_all(format!("{line}\n").as_bytes()) {
+            Ok(()) => {
+                self.backoff = TCP_BACKOFF_MIN;
+                self.retry_at = None;
+                Ok(())
+            }
+            Err(ex) => {
+                self.stream = None;
+                self.retry_later();
+                Err(ex)
+            }
+        }
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_ex = None;
+        for addr in self.addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, TCP_CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_write_timeout(Some(TCP_WRITE_TIMEOUT))?;
+                    return Ok(stream);
+                }
+                Err(ex) => last_ex = Some(ex),
+            }
+        }
+
+        Err(last_ex.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+        }))
+    }
+
+    fn retry_later(&mut self) {
+        self.retry_at = Some(Instant::now() + self.backoff);
+        self.backoff = (self.backoff * 2).min(TCP_BACKOFF_MAX);
+    }
+}
+
+// endregion: --- TcpSink
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn rotating_file(max_bytes: u64, max_age: Duration) -> RotatingFile {
+        let dir = std::env::temp_dir().join(format!("request-log-{}", Uuid::new_v4()));
+        RotatingFile {
+            path: dir.join("requests.jsonl"),
+            max_bytes,
+            max_age,
+            current: None,
+        }
+    }
+
+    /// The file names of the dir of `file`, sorted.
+    fn file_names(file: &RotatingFile) -> io::Result<Vec<String>> {
+        let mut names = fs::read_dir(file.path.parent().unwrap())?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort();
+
+        Ok(names)
+    }
+
+    #[test]
+    fn test_rotating_file_size() -> io::Result<()> {
+        let mut file = rotating_file(10, Duration::from_secs(3600));
+
+        // -- Each line is over `max_bytes`, so the next one rotates.
+        for line in ["line-00001", "line-00002", "line-00003"] {
+            file.write_line(line)?;
+        }
+        let names = file_names(&file)?;
+        assert_eq!(names.len(), 3, "{names:?}");
+        assert_eq!(names[0], "requests.jsonl");
+        assert_eq!(fs::read_to_string(&file.path)?, "line-00003\n");
+
+        fs::remove_dir_all(file.path.parent().unwrap())
+    }
+
+    #[test]
+    fn test_rotating_file_age() -> io::Result<()> {
+        let mut file = rotating_file(1024, Duration::from_millis(50));
+
+        file.write_line("line-00001")?;
+        file.write_line("line-00002")?;
+        assert_eq!(file_names(&file)?, ["requests.jsonl"]);
+
+        thread::sleep(Duration::from_millis(60));
+        file.write_line("line-00003")?;
+        let names = file_names(&file)?;
+        assert_eq!(names.len(), 2, "{names:?}");
+        let rotated = file.path.with_file_name(&names[1]);
+        assert_eq!(fs::read_to_string(rotated)?, "line-00001\nline-00002\n");
+        assert_eq!(fs::read_to_string(&file.path)?, "line-00003\n");
+
+        fs::remove_dir_all(file.path.parent().unwrap())
+    }
+
+    #[test]
+    fn test_rotating_file_rotated_path() {
+        let file = RotatingFile {
+            path: PathBuf::from("logs/requests.jsonl"),
+            max_bytes: 1024,
+            max_age: Duration::from_secs(3600),
+            current: None,
+        };
+        // 2024-01-01 12:00:05 UTC
+        let now = OffsetDateTime::from_unix_timestamp(1_704_110_405)
+            .unwrap()
+            .to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+
+        assert_eq!(
+            file.rotated_path(now, 0),
+            PathBuf::from("logs/requests.jsonl.20240101T120005Z")
+        );
+        assert_eq!(
+            file.rotated_path(now, 2),
+            PathBuf::from("logs/requests.jsonl.20240101T120005Z-2")
+        );
+    }
+
+    #[test]
+    fn test_queue_full_drops_line() {
+        let (tx, rx) = sync_channel(1);
+        let dropped = DROPPED_LINES.load(Ordering::Relaxed);
+
+        queue_line(&tx, "line-00001".to_string());
+        queue_line(&tx, "line-00002".to_string());
+
+        assert_eq!(DROPPED_LINES.load(Ordering::Relaxed), dropped + 1);
+        let Ok(Msg::Line(line)) = rx.try_recv() else {
+            panic!("should be the first line");
+        };
+        assert_eq!(line, "line-00001");
+    }
+}
+
+// endregion: --- Tests
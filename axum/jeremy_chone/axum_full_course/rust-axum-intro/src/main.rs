@@ -1,130 +1,72 @@
-#![allow(unused)]
-
-pub use self::error::{Error, Result};
-
-use crate::model::ModelController;
-use crate::web::mw_auth::mw_require_auth;
-use axum::extract::{Path, Query};
-use axum::handler::HandlerWithoutStateExt;
-use axum::response::{Html, IntoResponse, Response};
-use axum::routing::{get, get_service};
-use axum::{http, middleware, Json, Router};
-use http::StatusCode;
-use serde::Deserialize;
-use serde_json::json;
+use rust_axum_intro::config::init_config;
+use rust_axum_intro::model::{
+    spawn_idempotency_purge, spawn_trash_purge, spawn_webhook_delivery, ModelController,
+};
+use rust_axum_intro::shutdown::Shutdown;
+use rust_axum_intro::{app, log, trace, Result};
 use std::net::SocketAddr;
-use axum::http::{Method, Uri};
-use tower_cookies::CookieManagerLayer;
-use tower_http::services::ServeDir;
-use uuid::Uuid;
-use crate::ctx::Ctx;
-use crate::log::log_request;
-
-mod ctx;
-mod error;
-mod log;
-mod model;
-mod web;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize ModelController
-    let mc = ModelController::new().await?;
+    // Load and validate the config first, to fail early on a bad one.
+    let config = init_config()?;
 
-    let routes_apis =
-        web::routes_tickets::routes(mc.clone()).route_layer(middleware::from_fn(mw_require_auth));
+    trace::init_tracing(config);
 
-    let routes_all = Router::new()
-        .merge(routes_hello())
-        .merge(web::routes_login::routes())
-        .nest("/api", routes_apis)
-        .layer(middleware::map_response(main_response_mapper))
-        .layer(middleware::from_fn_with_state(
-            mc.clone(),
-            web::mw_auth::mw_ctx_resolver,
-        ))
-        .layer(CookieManagerLayer::new())
-        .fallback_service(routes_static());
+    // Start the request log writer.
+    log::init_sink(&config.log_sink);
+
+    // Initialize ModelController
+    let mc = ModelController::new(&config.store)
+        .await?
+        .with_login_lockout(config.login_max_failures, config.login_lockout)
+        .with_session_idle_timeout(config.session_idle_timeout);
+    if let Some(admin) = &config.admin_seed {
+        mc.seed_admin(&admin.username, admin.pwd.clone()).await?;
+    }
+    spawn_trash_purge(
+        mc.clone(),
+        config.trash_retention,
+        config.trash_purge_interval,
+    );
+    spawn_idempotency_purge(mc.clone(), config.idempotency_ttl);
+    spawn_webhook_delivery(mc.clone(), config.webhook);
+
+    let shutdown = Shutdown::default();
+    let routes_all = app(mc, shutdown.clone());
 
     // region:   --- Start Server
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
-    println!("->> LISTENING on {addr}\n");
-    axum::Server::bind(&addr)
-        .serve(routes_all.into_make_service())
-        .await
-        .unwrap();
+    let addr = config.bind_addr;
+    info!("{:<12} - {addr}", "LISTENING");
+    let server = axum::Server::bind(&addr)
+        .serve(routes_all.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().on_signal());
+
+    // Once draining, the in-flight requests have until the timeout to complete.
+    let drain_timeout = async {
+        shutdown.drain_started().await;
+        tokio::time::sleep(config.shutdown_timeout).await;
+    };
+    tokio::select! {
+        res = server => res.unwrap(),
+        _ = drain_timeout => warn!(
+            "{:<12} - drain timeout ({:?}), dropping the remaining requests",
+            "SHUTDOWN",
+            config.shutdown_timeout
+        ),
+    }
     // endregion --- Start Server
 
-    Ok(())
-}
-
-async fn main_response_mapper(
-    ctx: Option<Ctx>,
-    uri: Uri,
-    req_method: Method,
-    res: Response) -> Response {
-    println!("->> {:<12} - main_response_mapper", "RES_MAPPER");
-    let uuid = Uuid::new_v4();
-
-    // -- Get the eventual response error.
-    let service_error = res.extensions().get::<Error>();
-    let client_status_error = service_error.map(|se| se.client_status_and_error());
-
-    // -- If client error, build the new response.
-    let error_response = client_status_error
-        .as_ref()
-        .map(|(status_code, client_error)| {
-            let client_error_body = json!({
-                "error": {
-                    "type": client_error.as_ref(),
-                    "req_uuid": uuid.to_string(),
-                }
-            });
-
-            println!("  ->> client_error_body: {client_error_body}");
-
-            // Build the new response from the client_error_body
-            (*status_code, Json(client_error_body)).into_response()
-        });
-
-    //  Build and log the server log line.
-    let client_error = client_status_error.unzip().1;
-    log_request(uuid, req_method, uri, ctx, service_error, client_error).await;
-    
-    println!();
-    error_response.unwrap_or(res)
-}
-
-fn routes_static() -> Router {
-    Router::new().nest_service("/", get_service(ServeDir::new("./")))
-}
-
-// region:  --- Routes Hello
-fn routes_hello() -> Router {
-    Router::new()
-        .route("/hello", get(handler_hello))
-        .route("/hello2/:name", get(handler_hello2))
-}
-#[derive(Debug, Deserialize)]
-struct HelloParams {
-    name: Option<String>,
-}
-
-// e.g. `/hello?name=Jen`
-async fn handler_hello(Query(params): Query<HelloParams>) -> impl IntoResponse {
-    println!("->> {:<12} - handler_hello - {params:?}", "HANDLER");
-
-    let name = params.name.as_deref().unwrap_or("World!");
-    Html(format!("Hello <strong>{name}</strong>"))
-}
-
-// e.g., `/hello2/Mike
-async fn handler_hello2(Path(name): Path<String>) -> impl IntoResponse {
-    println!("->> {:<12} - handler_hello2 - {name:?}", "HANDLER");
+    // -- Flush the request log (the lines of the last requests).
+    let flush_timeout = config.shutdown_timeout;
+    let flushed = tokio::task::spawn_blocking(move || log::flush_sink(flush_timeout))
+        .await
+        .unwrap_or(false);
+    if !flushed {
+        warn!("{:<12} - request log not flushed", "SHUTDOWN");
+    }
+    info!("{:<12} - done", "SHUTDOWN");
 
-    Html(format!("Hello <strong>{name}</strong>"))
+    Ok(())
 }
-
-// endregion: --- Handler Hello
-
-
@@ -0,0 +1,142 @@
+//! Prometheus metrics, scraped from `GET /metrics` (text format).
+//!
+//! The request and error metrics are recorded by the `main_response_mapper`,
+//! the store gauges are refreshed at each scrape.
+
+use crate::error::ClientError;
+use crate::model::StoreStats;
+use crate::Error;
+use axum::http::{Method, StatusCode};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Route label of the requests which did not match any route
+/// (so unknown paths can't blow up the label cardinality).
+pub const ROUTE_UNMATCHED: &str = "unmatched";
+
+struct Metrics {
+    registry: Registry,
+
+    // -- Requests.
+    http_requests_total: IntCounterVec,
+    http_request_duration_seconds: HistogramVec,
+
+    // -- Errors.
+    service_errors_total: IntCounterVec,
+    client_errors_total: IntCounterVec,
+
+    // -- Store.
+    store_tickets: IntGauge,
+    store_users: IntGauge,
+}
+
+fn metrics() -> &'static Metrics {
+    static INSTANCE: OnceLock<Metrics> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        Metrics::new().unwrap_or_else(|ex| panic!("FATAL - WHILE CREATING METRICS - Cause: {ex:?}"))
+    })
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests."),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency, in seconds.",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let service_errors_total = IntCounterVec::new(
+            Opts::new(
+                "service_errors_total",
+                "Number of service errors, per variant.",
+            ),
+            &["error"],
+        )?;
+        let client_errors_total = IntCounterVec::new(
+            Opts::new(
+                "client_errors_total",
+                "Number of errors sent to the clients, per type.",
+            ),
+            &["client_error"],
+        )?;
+        let store_tickets = IntGauge::new("store_tickets", "Number of tickets in the store.")?;
+        let store_users = IntGauge::new("store_users", "Number of users in the store.")?;
+
+        registry.register(Box::new(http_requests_total.clone()))?;
+        registry.register(Box::new(http_request_duration_seconds.clone()))?;
+        registry.register(Box::new(service_errors_total.clone()))?;
+        registry.register(Box::new(client_errors_total.clone()))?;
+        registry.register(Box::new(store_tickets.clone()))?;
+        registry.register(Box::new(store_users.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            service_errors_total,
+            client_errors_total,
+            store_tickets,
+            store_users,
+        })
+    }
+}
+
+/// Record a served request, with its eventual errors.
+/// `route` is the matched route template (e.g., `/api/tickets/:id`), not the raw path.
+pub fn record_request(
+    req_method: &Method,
+    route: &str,
+    res_status: StatusCode,
+    duration: Duration,
+    service_error: Option<&Error>,
+    client_error: Option<&ClientError>,
+) {
+    let metrics = metrics();
+    let labels = [req_method.as_str(), route, res_status.as_str()];
+
+    metrics.http_requests_total.with_label_values(&labels).inc();
+    metrics
+        .http_request_duration_seconds
+        .with_label_values(&labels)
+        .observe(duration.as_secs_f64());
+
+    if let Some(service_error) = service_error {
+        metrics
+            .service_errors_total
+            .with_label_values(&[service_error.as_ref()])
+            .inc();
+    }
+    if let Some(client_error) = client_error {
+        metrics
+            .client_errors_total
+            .with_label_values(&[client_error.as_ref()])
+            .inc();
+    }
+}
+
+pub fn set_store_stats(stats: StoreStats) {
+    let metrics = metrics();
+
+    metrics.store_tickets.set(stats.tickets as i64);
+    metrics.store_users.set(stats.users as i64);
+}
+
+/// All the metrics, in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    // Only fails on io errors, which can't happen on a Vec.
+    let _ = TextEncoder::new().encode(&metrics().registry.gather(), &mut buffer);
+
+    String::from_utf8(buffer).unwrap_or_default()
+}
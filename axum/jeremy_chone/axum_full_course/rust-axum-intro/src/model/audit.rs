@@ -0,0 +1,179 @@
+//! Append-only audit trail of the ticket mutations.
+//!
+//! Each entry is written by the store in the same operation as the mutation
+//! (see `AuditForCreate`), so there is no mutation without its entry.
+//! Entries are never updated nor deleted.
+
+use crate::ctx::{Ctx, Role};
+use crate::model::{ListOptions, ModelController, Ticket};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+// region:    --- Audit Types
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: u64,
+    /// user_id of who did the mutation, `SYSTEM_ACTOR` for the background tasks.
+    pub actor: u64,
+    pub action: AuditAction,
+    pub ticket_id: u64,
+    /// Snapshots of the ticket, `None` before a create and after a delete.
+    pub before: Option<Ticket>,
+    pub after: Option<Ticket>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    /// The `uuid` of the request which did the mutation (see `ReqStamp`).
+    pub req_uuid: Option<Uuid>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    ToSchema,
+    strum_macros::AsRefStr,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditAction {
+    TicketCreate,
+    TicketUpdate,
+    TicketDelete,
+    TicketRestore,
+    TicketPurge,
+}
+
+/// Actor of the mutations not done by a user (user ids start at 1).
+pub const SYSTEM_ACTOR: u64 = 0;
+
+/// What the model knows of an entry before the mutation.
+/// The store completes it with the ticket id and snapshots.
+#[derive(Debug, Clone)]
+pub struct AuditForCreate {
+    pub actor: u64,
+    pub action: AuditAction,
+    pub ctime: OffsetDateTime,
+    pub req_uuid: Option<Uuid>,
+}
+
+impl AuditForCreate {
+    pub(super) fn new(ctx: &Ctx, action: AuditAction, now: OffsetDateTime) -> Self {
+        Self {
+            actor: ctx.user_id(),
+            action,
+            ctime: now,
+            req_uuid: ctx.req_uuid(),
+        }
+    }
+
+    pub(super) fn system(action: AuditAction, now: OffsetDateTime) -> Self {
+        Self {
+            actor: SYSTEM_ACTOR,
+            action,
+            ctime: now,
+            req_uuid: None,
+        }
+    }
+
+    pub(super) fn into_entry(
+        self,
+        id: u64,
+        ticket_id: u64,
+        before: Option<Ticket>,
+        after: Option<Ticket>,
+    ) -> AuditEntry {
+        AuditEntry {
+            id,
+            actor: self.actor,
+            action: self.action,
+            ticket_id,
+            before,
+            after,
+            ctime: self.ctime,
+            req_uuid: self.req_uuid,
+        }
+    }
+}
+
+// endregion: --- Audit Types
+
+// region:    --- Audit List Types
+
+/// Query params of `GET /api/audit`.
+/// `from` (inclusive) and `to` (exclusive) are rfc3339 times.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditListParams {
+    pub user_id: Option<u64>,
+    pub ticket_id: Option<u64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<u64>,
+    pub ticket_id: Option<u64>,
+    pub from: Option<OffsetDateTime>,
+    pub to: Option<OffsetDateTime>,
+}
+
+/// Entries are in the order they were written.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditPage {
+    pub data: Vec<AuditEntry>,
+    pub total: u64,
+    pub next_cursor: Option<String>,
+}
+
+// endregion: --- Audit List Types
+
+impl ModelController {
+    pub async fn list_audit(&self, ctx: Ctx, params: AuditListParams) -> Result<AuditPage> {
+        if ctx.role() != Role::Admin {
+            return Err(Error::AccessDenied {
+                action: "audit_list",
+            });
+        }
+
+        let filter = AuditFilter {
+            actor: params.user_id,
+            ticket_id: params.ticket_id,
+            from: parse_time(params.from)?,
+            to: parse_time(params.to)?,
+        };
+        let list_options = ListOptions::new(params.limit, params.offset, params.cursor)?;
+
+        let (data, total) = self.store.list_audit(&filter, list_options).await?;
+        let next_cursor = list_options.next_cursor(data.len() as u64, total);
+
+        Ok(AuditPage {
+            data,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+fn parse_time(time: Option<String>) -> Result<Option<OffsetDateTime>> {
+    time.map(|time| {
+        OffsetDateTime::parse(&time, &Rfc3339).map_err(|_| Error::AuditTimeInvalid { time })
+    })
+    .transpose()
+}
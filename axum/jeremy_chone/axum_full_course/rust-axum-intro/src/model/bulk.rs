@@ -0,0 +1,471 @@
+//! Bulk import and export of the tickets, as CSV or NDJSON (JSON Lines).
+//!
+//! The import validates each row as a create would, and reports the failed
+//! ones. The valid rows are then inserted together, in one store transaction
+//! (or none, with `all_or_nothing` and a failed row).
+//!
+//! The export pages through the ticket list (same filters and access), so a
+//! large export is never fully in memory.
+
+use crate::ctx::Ctx;
+use crate::error::ClientError;
+use crate::model::audit::{AuditAction, AuditForCreate};
+use crate::model::ticket::check_can_create;
+use crate::model::{ModelController, Ticket, TicketEventKind, TicketForCreate, TicketListParams};
+use crate::{Error, Result};
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+
+pub const IMPORT_MAX_ROWS: usize = 10_000;
+const EXPORT_PAGE_SIZE: u64 = 500;
+
+/// In the export order. The import reads the `TicketForCreate` ones (by header
+/// name, in any order), and ignores the others, so an export can be re-imported.
+const CSV_COLUMNS: [&str; 11] = [
+    "id",
+    "cid",
+    "title",
+    "description",
+    "status",
+    "priority",
+    "assignee",
+    "labels",
+    "ctime",
+    "mtime",
+    "version",
+];
+/// Between the label ids of the `labels` column, e.g., `2;5`.
+const CSV_LABELS_SEPARATOR: &str = ";";
+
+// region:    --- Bulk Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::AsRefStr, strum_macros::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum BulkFormat {
+    Csv,
+    Ndjson,
+}
+
+impl BulkFormat {
+    /// The `format` param if given, else from the request `Content-Type`.
+    pub fn resolve(format: Option<String>, content_type: Option<&str>) -> Result<Self> {
+        if let Some(format) = format {
+            return format
+                .parse()
+                .map_err(|_| Error::BulkFormatUnknown { format });
+        }
+
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase());
+        match mime.as_deref() {
+            Some("text/csv") => Ok(Self::Csv),
+            Some("application/x-ndjson" | "application/ndjson" | "application/jsonl") => {
+                Ok(Self::Ndjson)
+            }
+            _ => Err(Error::BulkFormatUnknown {
+                format: mime.unwrap_or_default(),
+            }),
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// e.g., `?format=csv&all_or_nothing=true`
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TicketImportParams {
+    /// `csv` or `ndjson`, by default from the `Content-Type`.
+    pub format: Option<String>,
+    /// Import nothing if any row fails.
+    #[serde(default)]
+    pub all_or_nothing: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TicketImportReport {
+    /// Number of rows read (without the CSV header).
+    pub total: u64,
+    /// Ids of the created tickets, in the rows order.
+    pub created: Vec<u64>,
+    pub failed: Vec<TicketImportRowError>,
+    /// False when nothing was imported, because of `all_or_nothing`.
+    pub committed: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TicketImportRowError {
+    /// 1-based, among the data rows (without the CSV header, nor the blank lines).
+    pub row: u64,
+    /// e.g., `{"type": "TicketStatusUnknown", "data": {"status": "done"}}`
+    #[schema(value_type = Object)]
+    pub error: Error,
+}
+
+/// The ticket list filters and order (see `TicketListParams`), and the format.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TicketExportParams {
+    /// `csv` (default) or `ndjson`.
+    pub format: Option<String>,
+
+    // -- Filters
+    pub cid: Option<u64>,
+    pub assignee: Option<u64>,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub title_contains: Option<String>,
+    /// A label id.
+    pub label: Option<u64>,
+
+    // -- Ordering
+    pub order_by: Option<String>,
+}
+
+impl TicketExportParams {
+    fn page(&self, offset: u64) -> TicketListParams {
+        TicketListParams {
+            cid: self.cid,
+            assignee: self.assignee,
+            status: self.status.clone(),
+            priority: self.priority.clone(),
+            title_contains: self.title_contains.clone(),
+            label: self.label,
+            order_by: self.order_by.clone(),
+            limit: Some(EXPORT_PAGE_SIZE),
+            offset: Some(offset),
+            cursor: None,
+        }
+    }
+}
+
+// endregion: --- Bulk Types
+
+// region:    --- Import & Export
+
+impl ModelController {
+    pub async fn import_tickets(
+        &self,
+        ctx: Ctx,
+        format: BulkFormat,
+        params: TicketImportParams,
+        body: &[u8],
+    ) -> Result<TicketImportReport> {
+        check_can_create(&ctx)?;
+
+        let rows = match format {
+            BulkFormat::Csv => parse_csv(body)?,
+            BulkFormat::Ndjson => parse_ndjson(body)?,
+        };
+        if rows.len() > IMPORT_MAX_ROWS {
+            return Err(Error::TicketImportTooManyRows {
+                max: IMPORT_MAX_ROWS,
+            });
+        }
+
+        // -- Validate all the rows.
+        let total = rows.len() as u64;
+        let now = OffsetDateTime::now_utc();
+        let mut tickets = Vec::new();
+        let mut failed = Vec::new();
+        for (idx, row) in rows.into_iter().enumerate() {
+            let ticket = match row {
+                Ok(ticket_fc) => self.new_ticket(&ctx, ticket_fc, now).await,
+                Err(ex) => Err(ex),
+            };
+            match ticket {
+                Ok(ticket) => {
+                    let audit = AuditForCreate::new(&ctx, AuditAction::TicketCreate, now);
+                    tickets.push((ticket, audit));
+                }
+                // Bad data, for the report. Other errors (e.g., store) fail the import.
+                Err(ex)
+                    if matches!(ex.client_status_and_error().1, ClientError::INVALID_PARAMS) =>
+                {
+                    failed.push(TicketImportRowError {
+                        row: idx as u64 + 1,
+                        error: ex,
+                    });
+                }
+                Err(ex) => return Err(ex),
+            }
+        }
+
+        // -- Insert the valid ones.
+        let committed = failed.is_empty() || !params.all_or_nothing;
+        let created = if committed && !tickets.is_empty() {
+            self.store.insert_tickets(tickets).await?
+        } else {
+            Vec::new()
+        };
+        for ticket in &created {
+            self.search_index.put(ticket);
+            self.publish_ticket_event(TicketEventKind::Created, ticket);
+        }
+
+        Ok(TicketImportReport {
+            total,
+            created: created.iter().map(|ticket| ticket.id).collect(),
+            failed,
+            committed,
+        })
+    }
+
+    /// The encoded chunks of the export (the CSV header first).
+    /// Fails early on invalid params, the later pages are read as streamed.
+    pub async fn export_tickets(
+        &self,
+        ctx: Ctx,
+        params: TicketExportParams,
+    ) -> Result<(
+        BulkFormat,
+        impl Stream<Item = Result<Vec<u8>>> + Send + 'static,
+    )> {
+        let format = match params.format.clone() {
+            Some(format) => BulkFormat::resolve(Some(format), None)?,
+            None => BulkFormat::Csv,
+        };
+        let first_page = self.list_tickets(ctx.clone(), params.page(0)).await?;
+
+        let header = match format {
+            BulkFormat::Csv => Some(encode_csv_row(CSV_COLUMNS.map(String::from))),
+            BulkFormat::Ndjson => None,
+        };
+
+        // Note: Paged by offset, so tickets created meanwhile are exported
+        //       (by the default id order), and deleted ones may shift the pages.
+        let mc = self.clone();
+        let pages = stream::unfold(Some((first_page, 0)), move |state| {
+            let (mc, ctx, params) = (mc.clone(), ctx.clone(), params.clone());
+            async move {
+                let (page, offset) = state?;
+                let chunk = encode_tickets(format, &page.data);
+
+                let offset = offset + page.data.len() as u64;
+                let next_state = match page.next_cursor {
+                    Some(_) => match mc.list_tickets(ctx, params.page(offset)).await {
+                        Ok(next_page) => Some((next_page, offset)),
+                        Err(ex) => return Some((Err(ex), None)),
+                    },
+                    None => None,
+                };
+
+                Some((chunk, next_state))
+            }
+        });
+
+        Ok((format, stream::iter(header).chain(pages)))
+    }
+}
+
+// endregion: --- Import & Export
+
+// region:    --- Decoding
+
+type Row = Result<TicketForCreate>;
+
+fn parse_ndjson(body: &[u8]) -> Result<Vec<Row>> {
+    let body = std::str::from_utf8(body).map_err(|_| Error::TicketImportInvalid {
+        reason: "not UTF-8".to_string(),
+    })?;
+
+    let rows = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|ex| Error::TicketImportRowInvalid {
+                cause: ex.to_string(),
+            })
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+fn parse_csv(body: &[u8]) -> Result<Vec<Row>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body);
+
+    let columns: HashMap<String, usize> = reader
+        .headers()
+        .map_err(|ex| Error::TicketImportInvalid {
+            reason: format!("header - {ex}"),
+        })?
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| (name.to_ascii_lowercase(), idx))
+        .collect();
+    if !columns.contains_key("title") {
+        return Err(Error::TicketImportInvalid {
+            reason: "no title column".to_string(),
+        });
+    }
+
+    let rows = reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|ex| Error::TicketImportRowInvalid {
+                cause: ex.to_string(),
+            })?;
+            // The non empty value of the column.
+            let get = |name: &str| {
+                columns
+                    .get(name)
+                    .and_then(|&idx| record.get(idx))
+                    .filter(|value| !value.is_empty())
+            };
+            let parse_id = |name: &str, value: &str| {
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| Error::TicketImportRowInvalid {
+                        cause: format!("{name} - not an id: '{value}'"),
+                    })
+            };
+
+            Ok(TicketForCreate {
+                title: get("title").unwrap_or_default().to_string(),
+                description: get("description").map(String::from),
+                status: get("status").map(String::from),
+                priority: get("priority").map(String::from),
+                assignee: get("assignee")
+                    .map(|value| parse_id("assignee", value))
+                    .transpose()?,
+                labels: get("labels")
+                    .map(|value| {
+                        value
+                            .split(CSV_LABELS_SEPARATOR)
+                            .filter(|id| !id.trim().is_empty())
+                            .map(|id| parse_id("labels", id))
+                            .collect::<Result<Vec<u64>>>()
+                    })
+                    .transpose()?,
+            })
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+// endregion: --- Decoding
+
+// region:    --- Encoding
+
+fn encode_tickets(format: BulkFormat, tickets: &[Ticket]) -> Result<Vec<u8>> {
+    let mut chunk = Vec::new();
+
+    for ticket in tickets {
+        match format {
+            BulkFormat::Csv => chunk.extend(encode_csv_row(csv_row(ticket)?)?),
+            BulkFormat::Ndjson => {
+                serde_json::to_writer(&mut chunk, ticket).map_err(|ex| Error::BulkEncodeFail {
+                    cause: ex.to_string(),
+                })?;
+                chunk.push(b'\n');
+            }
+        }
+    }
+
+    Ok(chunk)
+}
+
+/// The values of `CSV_COLUMNS`.
+fn csv_row(ticket: &Ticket) -> Result<[String; 11]> {
+    let time = |time: OffsetDateTime| {
+        time.format(&Rfc3339).map_err(|ex| Error::BulkEncodeFail {
+            cause: ex.to_string(),
+        })
+    };
+    let labels: Vec<String> = ticket.labels.iter().map(u64::to_string).collect();
+
+    Ok([
+        ticket.id.to_string(),
+        ticket.cid.to_string(),
+        ticket.title.clone(),
+        ticket.description.clone().unwrap_or_default(),
+        ticket.status.as_ref().to_string(),
+        ticket.priority.as_ref().to_string(),
+        ticket.assignee.map(|id| id.to_string()).unwrap_or_default(),
+        labels.join(CSV_LABELS_SEPARATOR),
+        time(ticket.ctime)?,
+        time(ticket.mtime)?,
+        ticket.version.to_string(),
+    ])
+}
+
+fn encode_csv_row(values: [String; 11]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(&values)
+        .map_err(|ex| Error::BulkEncodeFail {
+            cause: ex.to_string(),
+        })?;
+
+    writer.into_inner().map_err(|ex| Error::BulkEncodeFail {
+        cause: ex.to_string(),
+    })
+}
+
+// endregion: --- Encoding
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_by_header_name() -> Result<()> {
+        let body = "\
+id,Title,labels,assignee,status
+7,\"Crash, on login\",2;5,,in_progress
+8,  ,,x,
+";
+
+        let rows = parse_csv(body.as_bytes())?;
+
+        assert_eq!(rows.len(), 2);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.title, "Crash, on login");
+        assert_eq!(row.labels, Some(vec![2, 5]));
+        assert_eq!(row.assignee, None);
+        assert_eq!(row.status.as_deref(), Some("in_progress"));
+        assert!(matches!(rows[1], Err(Error::TicketImportRowInvalid { .. })));
+
+        let res = parse_csv(b"name,status\nx,open\n");
+        assert!(matches!(res, Err(Error::TicketImportInvalid { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_format_resolve() -> Result<()> {
+        let resolve = BulkFormat::resolve;
+
+        assert_eq!(
+            resolve(None, Some("text/csv; charset=utf-8"))?,
+            BulkFormat::Csv
+        );
+        assert_eq!(
+            resolve(Some("ndjson".to_string()), Some("text/csv"))?,
+            BulkFormat::Ndjson
+        );
+        assert!(resolve(None, Some("application/json")).is_err());
+        assert!(resolve(None, None).is_err());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,241 @@
+//! Ticket comments, threaded by their `parent_id`.
+//!
+//! Comments follow their ticket: hidden while it is in the trash, and removed
+//! with it by the purge.
+
+use crate::ctx::{Ctx, Role};
+use crate::model::ModelController;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+// region:    --- Comment Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Comment {
+    pub id: u64,
+    pub ticket_id: u64,
+    /// The comment replied to, `None` for a top level comment.
+    pub parent_id: Option<u64>,
+    pub author: u64, // user_id
+    pub body: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CommentForCreate {
+    pub body: String,
+    /// A comment of the same ticket, to reply to it.
+    pub parent_id: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CommentForUpdate {
+    pub body: String,
+}
+
+// endregion: --- Comment Types
+
+// region:    --- Comment CRUD
+
+// Comments are read by whoever can read their ticket (see `ticket`).
+// - Admin:  comment, edit their own comments, delete all.
+// - Member: comment, edit and delete their own comments.
+// - Viewer: read only.
+
+impl ModelController {
+    pub async fn create_comment(
+        &self,
+        ctx: Ctx,
+        ticket_id: u64,
+        comment_fc: CommentForCreate,
+    ) -> Result<Comment> {
+        self.get_ticket(ctx.clone(), ticket_id).await?;
+        if ctx.role() == Role::Viewer {
+            return Err(Error::AccessDenied {
+                action: "comment_create",
+            });
+        }
+        if let Some(parent_id) = comment_fc.parent_id {
+            self.get_ticket_comment(ticket_id, parent_id).await?;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let comment = Comment {
+            id: 0, // assigned by the store
+            ticket_id,
+            parent_id: comment_fc.parent_id,
+            author: ctx.user_id(),
+            body: validate_body(comment_fc.body)?,
+            ctime: now,
+            mtime: now,
+        };
+
+        self.store.insert_comment(comment).await
+    }
+
+    pub async fn get_comment(&self, ctx: Ctx, ticket_id: u64, id: u64) -> Result<Comment> {
+        self.get_ticket(ctx, ticket_id).await?;
+
+        self.get_ticket_comment(ticket_id, id).await
+    }
+
+    /// All the comments of the ticket, by id (so replies come after their parent).
+    pub async fn list_comments(&self, ctx: Ctx, ticket_id: u64) -> Result<Vec<Comment>> {
+        self.get_ticket(ctx, ticket_id).await?;
+
+        self.store.list_comments(ticket_id).await
+    }
+
+    pub async fn update_comment(
+        &self,
+        ctx: Ctx,
+        ticket_id: u64,
+        id: u64,
+        comment_fu: CommentForUpdate,
+    ) -> Result<Comment> {
+        let mut comment = self.get_comment(ctx.clone(), ticket_id, id).await?;
+        // Not even admins put words in someone else's mouth.
+        if comment.author != ctx.user_id() || ctx.role() == Role::Viewer {
+            return Err(Error::AccessDenied {
+                action: "comment_update",
+            });
+        }
+
+        comment.body = validate_body(comment_fu.body)?;
+        comment.mtime = OffsetDateTime::now_utc();
+
+        self.store
+            .update_comment(comment)
+            .await?
+            .ok_or(Error::CommentNotFound { id })
+    }
+
+    /// Deletes the comment with all its replies.
+    /// Returns the deleted comment.
+    pub async fn delete_comment(&self, ctx: Ctx, ticket_id: u64, id: u64) -> Result<Comment> {
+        let comment = self.get_comment(ctx.clone(), ticket_id, id).await?;
+        let allowed = match ctx.role() {
+            Role::Admin => true,
+            Role::Member => comment.author == ctx.user_id(),
+            Role::Viewer => false,
+        };
+        if !allowed {
+            return Err(Error::AccessDenied {
+                action: "comment_delete",
+            });
+        }
+
+        // Ordered by id, so the replies come after their parent.
+        let mut ids = vec![id];
+        for other in self.store.list_comments(ticket_id).await? {
+            if other
+                .parent_id
+                .is_some_and(|parent_id| ids.contains(&parent_id))
+            {
+                ids.push(other.id);
+            }
+        }
+        self.store.delete_comments(&ids).await?;
+
+        Ok(comment)
+    }
+
+    /// The comment, if it is one of the ticket.
+    async fn get_ticket_comment(&self, ticket_id: u64, id: u64) -> Result<Comment> {
+        self.store
+            .get_comment(id)
+            .await?
+            .filter(|c| c.ticket_id == ticket_id)
+            .ok_or(Error::CommentNotFound { id })
+    }
+}
+
+// endregion: --- Comment CRUD
+
+fn validate_body(body: String) -> Result<String> {
+    let body = body.trim();
+
+    if body.is_empty() {
+        return Err(Error::CommentBodyEmpty);
+    }
+
+    Ok(body.to_string())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use crate::model::{IfMatch, TicketForCreate};
+
+    #[tokio::test]
+    async fn test_comment_threads_and_cascade() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let member = Ctx::new(1, Role::Member);
+        let admin = Ctx::new(2, Role::Admin);
+        let new_comment = |body: &str, parent_id: Option<u64>| CommentForCreate {
+            body: body.to_string(),
+            parent_id,
+        };
+
+        let ticket = mc
+            .create_ticket(member.clone(), TicketForCreate::with_title("t1"))
+            .await?;
+        let c1 = mc
+            .create_comment(member.clone(), ticket.id, new_comment("c1", None))
+            .await?;
+        let c2 = mc
+            .create_comment(admin.clone(), ticket.id, new_comment("c2", Some(c1.id)))
+            .await?;
+        let c3 = mc
+            .create_comment(member.clone(), ticket.id, new_comment("c3", Some(c2.id)))
+            .await?;
+        let c4 = mc
+            .create_comment(member.clone(), ticket.id, new_comment("c4", None))
+            .await?;
+
+        // -- Only the author edits.
+        let res = mc
+            .update_comment(
+                admin.clone(),
+                ticket.id,
+                c1.id,
+                CommentForUpdate {
+                    body: "edited".to_string(),
+                },
+            )
+            .await;
+        assert!(matches!(res, Err(Error::AccessDenied { .. })));
+
+        // -- Deleting a comment deletes its replies.
+        mc.delete_comment(admin.clone(), ticket.id, c1.id).await?;
+        let ids: Vec<u64> = mc
+            .list_comments(member.clone(), ticket.id)
+            .await?
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec![c4.id]);
+        let res = mc.get_comment(member.clone(), ticket.id, c3.id).await;
+        assert!(matches!(res, Err(Error::CommentNotFound { .. })));
+
+        // -- Hidden with the ticket in the trash, removed by the purge.
+        mc.delete_ticket(member.clone(), ticket.id, IfMatch::Any)
+            .await?;
+        let res = mc.list_comments(member.clone(), ticket.id).await;
+        assert!(matches!(res, Err(Error::TicketNotFound { .. })));
+        mc.purge_trash(OffsetDateTime::now_utc()).await?;
+        assert!(mc.store.get_comment(c4.id).await?.is_none());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,273 @@
+//! Idempotency keys of the ticket creates.
+//!
+//! The first ticket created with a key is recorded (per user), and returned
+//! again, instead of creating a duplicate, for the repeats of the same create
+//! within the ttl. The same key with a different create is rejected.
+
+use crate::ctx::Ctx;
+use crate::model::{ModelController, Ticket, TicketForCreate};
+use crate::{Error, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::OwnedMutexGuard;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+const KEY_MAX_LEN: usize = 255;
+
+// region:    --- Idempotency Types
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub user_id: u64,
+    pub key: String,
+    /// Hash of the `TicketForCreate`, to detect a reuse of the key.
+    pub fingerprint: String,
+    /// The ticket as first created.
+    pub ticket: Ticket,
+    pub ctime: OffsetDateTime,
+}
+
+#[derive(Debug)]
+pub struct IdempotentTicket {
+    pub ticket: Ticket,
+    /// True when created by an earlier request with the same key.
+    pub replayed: bool,
+}
+
+// endregion: --- Idempotency Types
+
+impl ModelController {
+    /// Records expire after `ttl`, then the key can be used again.
+    pub async fn create_ticket_idempotent(
+        &self,
+        ctx: Ctx,
+        key: String,
+        ticket_fc: TicketForCreate,
+        ttl: Duration,
+    ) -> Result<IdempotentTicket> {
+        if key.is_empty() || key.len() > KEY_MAX_LEN || !key.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(Error::IdempotencyKeyInvalid { key });
+        }
+        let fingerprint = fingerprint(&ticket_fc)?;
+
+        // Held until the record is stored, so concurrent repeats wait for it.
+        let _guard = self.idempotency_locks.lock(ctx.user_id(), &key).await;
+
+        let now = OffsetDateTime::now_utc();
+        let record = self
+            .store
+            .get_idempotency(ctx.user_id(), &key)
+            .await?
+            .filter(|record| record.ctime + ttl > now);
+        if let Some(record) = record {
+            if record.fingerprint != fingerprint {
+                return Err(Error::IdempotencyKeyReused { key });
+            }
+            return Ok(IdempotentTicket {
+                ticket: record.ticket,
+                replayed: true,
+            });
+        }
+
+        let user_id = ctx.user_id();
+        let ticket = self.create_ticket(ctx, ticket_fc).await?;
+        self.store
+            .put_idempotency(IdempotencyRecord {
+                user_id,
+                key,
+                fingerprint,
+                ticket: ticket.clone(),
+                ctime: now,
+            })
+            .await?;
+
+        Ok(IdempotentTicket {
+            ticket,
+            replayed: false,
+        })
+    }
+
+    /// Remove the records created before `ctime_before`.
+    /// Returns the number of removed records.
+    pub async fn purge_idempotency_keys(&self, ctime_before: OffsetDateTime) -> Result<u64> {
+        self.store.purge_idempotency(ctime_before).await
+    }
+}
+
+// region:    --- Idempotency Locks
+
+/// One lock per `(user_id, key)`, so only the creates with the same key wait
+/// for each other. Kept only while held or waited for.
+#[derive(Default)]
+pub(super) struct IdempotencyLocks {
+    locks: Mutex<HashMap<(u64, String), KeyLock>>,
+}
+
+type KeyLock = Arc<tokio::sync::Mutex<()>>;
+
+struct IdempotencyGuard<'a> {
+    locks: &'a IdempotencyLocks,
+    lock_key: (u64, String),
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl IdempotencyLocks {
+    async fn lock(&self, user_id: u64, key: &str) -> IdempotencyGuard<'_> {
+        let lock_key = (user_id, key.to_string());
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(lock_key.clone())
+            .or_default()
+            .clone();
+
+        IdempotencyGuard {
+            locks: self,
+            lock_key,
+            _guard: lock.lock_owned().await,
+        }
+    }
+}
+
+impl Drop for IdempotencyGuard<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap();
+        // Only in the map and this guard, so no one is waiting for it.
+        // (The waiters clone it with the map locked.)
+        if locks
+            .get(&self.lock_key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 2)
+        {
+            locks.remove(&self.lock_key);
+        }
+    }
+}
+
+// endregion: --- Idempotency Locks
+
+fn fingerprint(ticket_fc: &TicketForCreate) -> Result<String> {
+    let json = serde_json::to_vec(ticket_fc).map_err(|ex| Error::Store(ex.to_string()))?;
+
+    Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(json)))
+}
+
+/// Purge the expired records every `ttl`.
+pub fn spawn_idempotency_purge(mc: ModelController, ttl: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(ttl);
+
+        loop {
+            ticker.tick().await;
+
+            let ctime_before = OffsetDateTime::now_utc() - ttl;
+            match mc.purge_idempotency_keys(ctime_before).await {
+                Ok(0) => (),
+                Ok(purged) => info!("{:<12} - {purged} keys purged", "IDEMP_PURGE"),
+                Err(ex) => warn!("{:<12} - failed - {ex:?}", "IDEMP_PURGE"),
+            }
+        }
+    })
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use crate::ctx::Role;
+    use crate::model::TicketListParams;
+
+    #[tokio::test]
+    async fn test_create_ticket_idempotent() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let ttl = Duration::from_secs(60);
+        let ctx_1 = Ctx::new(1, Role::Member);
+        let ctx_2 = Ctx::new(2, Role::Member);
+
+        let first = mc
+            .create_ticket_idempotent(
+                ctx_1.clone(),
+                "k1".into(),
+                TicketForCreate::with_title("t1"),
+                ttl,
+            )
+            .await?;
+        assert!(!first.replayed);
+
+        // -- Same key and create, replayed.
+        let again = mc
+            .create_ticket_idempotent(
+                ctx_1.clone(),
+                "k1".into(),
+                TicketForCreate::with_title("t1"),
+                ttl,
+            )
+            .await?;
+        assert!(again.replayed);
+        assert_eq!(again.ticket.id, first.ticket.id);
+
+        // -- Same key, other create, rejected.
+        let res = mc
+            .create_ticket_idempotent(
+                ctx_1.clone(),
+                "k1".into(),
+                TicketForCreate::with_title("t2"),
+                ttl,
+            )
+            .await;
+        assert!(matches!(res, Err(Error::IdempotencyKeyReused { .. })));
+
+        // -- Keys are per user.
+        let other = mc
+            .create_ticket_idempotent(ctx_2, "k1".into(), TicketForCreate::with_title("t1"), ttl)
+            .await?;
+        assert!(!other.replayed);
+
+        // -- Purged, the key creates again.
+        let purged = mc.purge_idempotency_keys(OffsetDateTime::now_utc()).await?;
+        assert_eq!(purged, 2);
+        let after_purge = mc
+            .create_ticket_idempotent(
+                ctx_1.clone(),
+                "k1".into(),
+                TicketForCreate::with_title("t1"),
+                ttl,
+            )
+            .await?;
+        assert!(!after_purge.replayed);
+
+        let page = mc.list_tickets(ctx_1, TicketListParams::default()).await?;
+        assert_eq!(page.total, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_locks_per_key() {
+        let locks = IdempotencyLocks::default();
+        let wait = Duration::from_millis(50);
+
+        let guard = locks.lock(1, "key-1").await;
+        // -- Other keys, or users, don't wait.
+        for (user_id, key) in [(1, "key-2"), (2, "key-1")] {
+            let res = tokio::time::timeout(wait, locks.lock(user_id, key)).await;
+            assert!(res.is_ok(), "({user_id}, {key}) should not wait");
+        }
+        // -- The same key does.
+        let res = tokio::time::timeout(wait, locks.lock(1, "key-1")).await;
+        assert!(res.is_err());
+
+        drop(guard);
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+}
+
+// endregion: --- Tests
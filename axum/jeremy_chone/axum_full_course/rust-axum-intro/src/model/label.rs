@@ -0,0 +1,283 @@
+//! Ticket labels.
+//!
+//! Labels are free-form names (unique, case insensitive), managed on their
+//! own, and set on the tickets by id (see `Ticket.labels`).
+
+use crate::ctx::{Ctx, Role};
+use crate::model::audit::{AuditAction, AuditForCreate};
+use crate::model::{ListOptions, ModelController, TicketEventKind, TicketFilter, TicketOrderBy};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+const NAME_MAX_LEN: usize = 50;
+
+// region:    --- Label Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Label {
+    pub id: u64,
+    pub name: String,
+    /// e.g., `#d73a4a`
+    pub color: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LabelForCreate {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+/// Only the given fields are changed, an explicit `null` color clears it.
+#[derive(Deserialize, Default, ToSchema)]
+pub struct LabelForUpdate {
+    pub name: Option<String>,
+    #[serde(default, with = "double_option")]
+    #[schema(value_type = Option<String>)]
+    pub color: Option<Option<String>>,
+}
+
+// endregion: --- Label Types
+
+// region:    --- Label CRUD
+
+// - Admin:  create, rename and delete the labels.
+// - Member: create labels.
+// - Viewer: list them only.
+
+impl ModelController {
+    pub async fn create_label(&self, ctx: Ctx, label_fc: LabelForCreate) -> Result<Label> {
+        if ctx.role() == Role::Viewer {
+            return Err(Error::AccessDenied {
+                action: "label_create",
+            });
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let label = Label {
+            id: 0, // assigned by the store
+            name: validate_name(label_fc.name)?,
+            color: validate_color(label_fc.color)?,
+            ctime: now,
+            mtime: now,
+        };
+
+        self.store.insert_label(label).await
+    }
+
+    /// All the labels, by name.
+    pub async fn list_labels(&self, _ctx: Ctx) -> Result<Vec<Label>> {
+        self.store.list_labels().await
+    }
+
+    pub async fn update_label(&self, ctx: Ctx, id: u64, label_fu: LabelForUpdate) -> Result<Label> {
+        check_admin(&ctx, "label_update")?;
+        let mut label = self
+            .store
+            .get_label(id)
+            .await?
+            .ok_or(Error::LabelNotFound { id })?;
+
+        if let Some(name) = label_fu.name {
+            label.name = validate_name(name)?;
+        }
+        if let Some(color) = label_fu.color {
+            label.color = validate_color(color)?;
+        }
+        label.mtime = OffsetDateTime::now_utc();
+
+        self.store
+            .update_label(label)
+            .await?
+            .ok_or(Error::LabelNotFound { id })
+    }
+
+    /// Also removes the label from the tickets (live or in the trash) having it,
+    /// as ticket updates by `ctx`.
+    pub async fn delete_label(&self, ctx: Ctx, id: u64) -> Result<Label> {
+        check_admin(&ctx, "label_delete")?;
+        let label = self
+            .store
+            .get_label(id)
+            .await?
+            .ok_or(Error::LabelNotFound { id })?;
+
+        for trashed in [false, true] {
+            let filter = TicketFilter {
+                label: Some(id),
+                trashed,
+                ..Default::default()
+            };
+            // Each page is updated out of the filter, so the next one starts over.
+            loop {
+                let (tickets, _) = self
+                    .store
+                    .list_tickets(
+                        &filter,
+                        TicketOrderBy::default(),
+                        ListOptions::new(None, None, None)?,
+                    )
+                    .await?;
+                if tickets.is_empty() {
+                    break;
+                }
+
+                for mut ticket in tickets {
+                    ticket.labels.retain(|label_id| *label_id != id);
+                    ticket.mtime = OffsetDateTime::now_utc();
+
+                    let audit = AuditForCreate::new(&ctx, AuditAction::TicketUpdate, ticket.mtime);
+                    if let Some(ticket) = self.store.update_ticket(ticket, audit).await? {
+                        self.search_index.put(&ticket);
+                        self.publish_ticket_event(TicketEventKind::Updated, &ticket);
+                    }
+                }
+            }
+        }
+
+        self.store.delete_label(id).await?;
+
+        Ok(label)
+    }
+
+    /// The existing label ids, ascending and without duplicates.
+    pub(super) async fn validate_labels(&self, labels: Option<Vec<u64>>) -> Result<Vec<u64>> {
+        let mut labels = labels.unwrap_or_default();
+        labels.sort_unstable();
+        labels.dedup();
+
+        for &label_id in &labels {
+            if self.store.get_label(label_id).await?.is_none() {
+                return Err(Error::TicketLabelNotFound { label_id });
+            }
+        }
+
+        Ok(labels)
+    }
+}
+
+// endregion: --- Label CRUD
+
+// region:    --- Validations
+
+fn validate_name(name: String) -> Result<String> {
+    let name = name.trim();
+
+    if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+        return Err(Error::LabelNameInvalid {
+            name: name.to_string(),
+        });
+    }
+
+    Ok(name.to_string())
+}
+
+/// A `#rrggbb` hex color.
+fn validate_color(color: Option<String>) -> Result<Option<String>> {
+    let Some(color) = color else {
+        return Ok(None);
+    };
+
+    let valid = color
+        .strip_prefix('#')
+        .is_some_and(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+    if !valid {
+        return Err(Error::LabelColorInvalid { color });
+    }
+
+    Ok(Some(color.to_lowercase()))
+}
+
+pub(super) fn check_admin(ctx: &Ctx, action: &'static str) -> Result<()> {
+    if ctx.role() == Role::Admin {
+        Ok(())
+    } else {
+        Err(Error::AccessDenied { action })
+    }
+}
+
+// endregion: --- Validations
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use crate::model::{IfMatch, TicketForCreate, TicketForUpdate, TicketListParams};
+
+    #[tokio::test]
+    async fn test_delete_label_removes_it_from_tickets() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let admin = Ctx::new(1, Role::Admin);
+        let new_label = |name: &str| LabelForCreate {
+            name: name.to_string(),
+            color: None,
+        };
+
+        let bug = mc.create_label(admin.clone(), new_label("bug")).await?;
+        let ui = mc.create_label(admin.clone(), new_label("ui")).await?;
+        let res = mc.create_label(admin.clone(), new_label(" BUG ")).await;
+        assert!(matches!(res, Err(Error::LabelNameTaken { .. })));
+
+        let ticket = mc
+            .create_ticket(
+                admin.clone(),
+                TicketForCreate {
+                    labels: Some(vec![ui.id, bug.id, ui.id]),
+                    ..TicketForCreate::with_title("t1")
+                },
+            )
+            .await?;
+        assert_eq!(ticket.labels, vec![bug.id, ui.id]);
+        let trashed = mc
+            .create_ticket(
+                admin.clone(),
+                TicketForCreate {
+                    labels: Some(vec![bug.id]),
+                    ..TicketForCreate::with_title("t2")
+                },
+            )
+            .await?;
+        mc.delete_ticket(admin.clone(), trashed.id, IfMatch::Any)
+            .await?;
+
+        // -- Unknown label ids are rejected.
+        let res = mc
+            .update_ticket(
+                admin.clone(),
+                ticket.id,
+                TicketForUpdate {
+                    labels: Some(vec![99]),
+                    ..Default::default()
+                },
+                IfMatch::Any,
+            )
+            .await;
+        assert!(matches!(
+            res,
+            Err(Error::TicketLabelNotFound { label_id: 99 })
+        ));
+
+        mc.delete_label(admin.clone(), bug.id).await?;
+
+        let ticket = mc.get_ticket(admin.clone(), ticket.id).await?;
+        assert_eq!(ticket.labels, vec![ui.id]);
+        let trash = mc
+            .list_trash(admin.clone(), TicketListParams::default())
+            .await?;
+        assert!(trash.data[0].labels.is_empty());
+        let labels = mc.list_labels(admin).await?;
+        assert_eq!(labels.len(), 1);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
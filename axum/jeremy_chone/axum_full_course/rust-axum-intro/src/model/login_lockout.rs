@@ -0,0 +1,115 @@
+//! Temporary lockout of the usernames with too many failed logins in a row.
+//!
+//! Counted per username, known or not, so a lockout does not tell
+//! whether the account exists. Kept in memory only.
+
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Above this many tracked usernames, the stale ones are dropped.
+const PRUNE_AT: usize = 10_000;
+
+pub(super) struct LoginLockout {
+    max_failures: u32,
+    lockout: Duration,
+    failures: Mutex<HashMap<String, Failures>>,
+}
+
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl LoginLockout {
+    pub(super) fn new(max_failures: u32, lockout: Duration) -> Self {
+        Self {
+            max_failures,
+            lockout,
+            failures: Mutex::default(),
+        }
+    }
+
+    /// Fails with `LoginLocked` while the username is locked.
+    pub(super) fn check(&self, username: &str) -> Result<()> {
+        let failures = self.failures.lock().unwrap();
+
+        let locked_until = failures.get(username).and_then(|f| f.locked_until);
+        match locked_until.map(|until| until.saturating_duration_since(Instant::now())) {
+            Some(left) if !left.is_zero() => Err(Error::LoginLocked {
+                retry_after_sec: left.as_secs().max(1),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Failures older than the lockout duration are forgotten,
+    /// so only a burst of them locks the username.
+    pub(super) fn record_failure(&self, username: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        let now = Instant::now();
+
+        if failures.len() >= PRUNE_AT {
+            let lockout = self.lockout;
+            failures.retain(|_, f| now.duration_since(f.last) < lockout);
+        }
+
+        let entry = failures.entry(username.to_string()).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        if now.duration_since(entry.last) >= self.lockout {
+            entry.count = 0;
+        }
+        entry.count += 1;
+        entry.last = now;
+        if entry.count >= self.max_failures {
+            entry.count = 0;
+            entry.locked_until = Some(now + self.lockout);
+        }
+    }
+
+    pub(super) fn record_success(&self, username: &str) {
+        self.failures.lock().unwrap().remove(username);
+    }
+}
+
+impl Default for LoginLockout {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(300))
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_lockout() -> Result<()> {
+        let lockout = LoginLockout::new(2, Duration::from_secs(60));
+
+        lockout.record_failure("demo1");
+        lockout.check("demo1")?;
+        lockout.record_failure("demo1");
+        assert!(matches!(
+            lockout.check("demo1"),
+            Err(Error::LoginLocked { .. })
+        ));
+        lockout.check("demo2")?;
+
+        // -- A success only clears the failures count (after the lockout).
+        lockout.record_failure("demo2");
+        lockout.record_success("demo2");
+        lockout.record_failure("demo2");
+        lockout.check("demo2")?;
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,156 @@
+//! Simplistic Model Layer
+//! (with a pluggable store layer, see `store`)
+
+mod audit;
+mod bulk;
+mod comment;
+mod idempotency;
+mod label;
+mod login_lockout;
+mod search;
+mod session;
+mod store;
+mod ticket;
+mod ticket_event;
+mod trash;
+pub mod user;
+mod webhook;
+
+pub use self::audit::{AuditAction, AuditEntry, AuditListParams, AuditPage};
+pub use self::bulk::{
+    BulkFormat, TicketExportParams, TicketImportParams, TicketImportReport, TicketImportRowError,
+    IMPORT_MAX_ROWS,
+};
+pub use self::comment::{Comment, CommentForCreate, CommentForUpdate};
+pub use self::idempotency::{spawn_idempotency_purge, IdempotentTicket};
+pub use self::label::{Label, LabelForCreate, LabelForUpdate};
+pub use self::search::{TicketSearchHit, TicketSearchPage, TicketSearchParams};
+pub use self::session::Session;
+pub use self::ticket::{
+    IfMatch, Ticket, TicketField, TicketFilter, TicketForCreate, TicketForReplace, TicketForUpdate,
+    TicketListParams, TicketOrderBy, TicketPage, TicketPriority, TicketStatus,
+};
+pub use self::ticket_event::{TicketEvent, TicketEventKind, TicketFeed, TicketFeedItem};
+pub use self::trash::spawn_trash_purge;
+pub use self::webhook::{
+    spawn_webhook_delivery, Webhook, WebhookAttempt, WebhookCreated, WebhookForCreate,
+    WebhookForUpdate, WebhookPayload,
+};
+
+use crate::config::StoreConfig;
+use crate::model::idempotency::IdempotencyLocks;
+use crate::model::login_lockout::LoginLockout;
+use crate::model::search::SearchIndex;
+use crate::model::session::SESSION_IDLE_TIMEOUT_DEFAULT;
+use crate::model::store::{new_store, Store};
+use crate::model::ticket_event::TICKET_EVENTS_CAPACITY;
+use crate::{Error, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+// region: --- Model Controller
+
+#[derive(Clone)]
+pub struct ModelController {
+    store: Arc<dyn Store>,
+    ticket_events: broadcast::Sender<TicketEvent>,
+    /// Serializes the idempotent creates of the same key (see `idempotency`).
+    idempotency_locks: Arc<IdempotencyLocks>,
+    login_lockout: Arc<LoginLockout>,
+    /// Full-text index of the live tickets (see `search`).
+    search_index: Arc<SearchIndex>,
+    /// Sessions without requests for this long are expired (see `session`).
+    session_idle_timeout: Duration,
+}
+
+// Constructor
+impl ModelController {
+    pub async fn new(store_config: &StoreConfig) -> Result<Self> {
+        let (ticket_events, _) = broadcast::channel(TICKET_EVENTS_CAPACITY);
+        let store = new_store(store_config)?;
+        let search_index = SearchIndex::build(store.as_ref()).await?;
+
+        Ok(Self {
+            store,
+            ticket_events,
+            idempotency_locks: Arc::default(),
+            login_lockout: Arc::default(),
+            search_index: Arc::new(search_index),
+            session_idle_timeout: SESSION_IDLE_TIMEOUT_DEFAULT,
+        })
+    }
+
+    /// Lock a username for `lockout` after `max_failures` failed logins in a row
+    /// (default 5 for 5 minutes).
+    pub fn with_login_lockout(mut self, max_failures: u32, lockout: Duration) -> Self {
+        self.login_lockout = Arc::new(LoginLockout::new(max_failures, lockout));
+        self
+    }
+
+    /// Expire the sessions after `idle_timeout` without requests (default 30 minutes).
+    pub fn with_session_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.session_idle_timeout = idle_timeout;
+        self
+    }
+}
+
+/// Sizes of the store, for the metrics gauges.
+#[derive(Debug, Clone, Copy)]
+pub struct StoreStats {
+    pub tickets: u64,
+    pub users: u64,
+}
+
+impl ModelController {
+    pub async fn store_stats(&self) -> Result<StoreStats> {
+        Ok(StoreStats {
+            tickets: self.store.count_tickets().await?,
+            users: self.store.count_users().await?,
+        })
+    }
+    /// Ok if the store serves queries, for the readiness probe.
+    pub async fn check_store(&self) -> Result<()> {
+        self.store.ping().await
+    }
+}
+
+// endregion: --- Model Controller
+
+// region:    --- List Options
+
+const LIST_LIMIT_DEFAULT: u64 = 100;
+const LIST_LIMIT_MAX: u64 = 1000;
+
+/// Paging of the list queries, resolved from the `limit`/`offset`/`cursor` params.
+#[derive(Debug, Clone, Copy)]
+pub struct ListOptions {
+    pub limit: u64,
+    pub offset: u64,
+}
+
+impl ListOptions {
+    pub fn new(limit: Option<u64>, offset: Option<u64>, cursor: Option<String>) -> Result<Self> {
+        let offset = match cursor.filter(|c| !c.is_empty()) {
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| Error::ListCursorInvalid { cursor })?,
+            None => offset.unwrap_or(0),
+        };
+        let limit = limit.unwrap_or(LIST_LIMIT_DEFAULT).min(LIST_LIMIT_MAX);
+
+        Ok(Self { limit, offset })
+    }
+
+    /// The cursor of the page after this one, if there is one.
+    ///
+    /// Note: For now, the cursor is the offset of the next page. Clients must
+    ///       treat it as opaque, so it can become a keyset cursor later.
+    pub fn next_cursor(&self, page_len: u64, total: u64) -> Option<String> {
+        let next_offset = self.offset + page_len;
+
+        (page_len > 0 && next_offset < total).then(|| next_offset.to_string())
+    }
+}
+
+// endregion: --- List Options
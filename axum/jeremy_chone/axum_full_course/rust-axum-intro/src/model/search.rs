@@ -0,0 +1,453 @@
+//! Full-text search of the live tickets.
+//!
+//! An in-memory inverted index of the ticket titles and descriptions, built
+//! from the store at startup, then kept up to date by the `ModelController` on
+//! each ticket change. Words are lowercased and stemmed (English), and the
+//! matches are ranked with BM25, a title term counting as two description ones.
+
+use crate::ctx::{Ctx, Role};
+use crate::model::store::Store;
+use crate::model::{ListOptions, ModelController, Ticket, TicketFilter, TicketOrderBy};
+use crate::{Error, Result};
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
+
+// BM25 parameters (the usual defaults).
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+const TITLE_WEIGHT: u32 = 2;
+
+/// Approximate length of the description snippets, in bytes.
+const SNIPPET_LEN: usize = 160;
+
+// region:    --- Search Types
+
+/// Query params of the ticket search, e.g., `?q=login+crash&limit=20`
+///
+/// `cursor` is the `next_cursor` of the previous page (takes precedence over `offset`).
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TicketSearchParams {
+    /// Words to search in the title and description, any of them can match.
+    pub q: String,
+
+    // -- Paging
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TicketSearchHit {
+    pub ticket: Ticket,
+    /// Relevance, the hits are by descending score.
+    pub score: f64,
+    /// The title, HTML escaped, with the matched words in `<mark>`.
+    pub title_html: String,
+    /// An excerpt of the description around the first match, as `title_html`.
+    pub snippet_html: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TicketSearchPage {
+    pub data: Vec<TicketSearchHit>,
+    /// Number of matching tickets, across all pages.
+    pub total: u64,
+    /// To pass as `cursor` to get the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+// endregion: --- Search Types
+
+impl ModelController {
+    /// Same visibility as `list_tickets`.
+    pub async fn search_tickets(
+        &self,
+        ctx: Ctx,
+        params: TicketSearchParams,
+    ) -> Result<TicketSearchPage> {
+        let terms = self.search_index.query_terms(&params.q);
+        if terms.is_empty() {
+            return Err(Error::SearchQueryEmpty { q: params.q });
+        }
+        let list_options = ListOptions::new(params.limit, params.offset, params.cursor)?;
+
+        // Members only see their own tickets.
+        let cid = (ctx.role() == Role::Member).then(|| ctx.user_id());
+        let ranked = self.search_index.search(&terms, cid);
+        let total = ranked.len() as u64;
+
+        let page: Vec<_> = ranked
+            .into_iter()
+            .skip(list_options.offset as usize)
+            .take(list_options.limit as usize)
+            .collect();
+        // The cursor moves by the hits taken from the ranking, even the dropped ones.
+        let page_len = page.len() as u64;
+
+        let mut data = Vec::new();
+        for (id, score) in page {
+            // Could have been deleted, or moved to the trash, since.
+            let Some(ticket) = self
+                .store
+                .get_ticket(id)
+                .await?
+                .filter(|ticket| ticket.deleted.is_none())
+            else {
+                continue;
+            };
+            let title_html = self.search_index.highlight(&ticket.title, &terms, None);
+            let snippet_html = ticket.description.as_deref().map(|description| {
+                self.search_index
+                    .highlight(description, &terms, Some(SNIPPET_LEN))
+            });
+
+            data.push(TicketSearchHit {
+                ticket,
+                score,
+                title_html,
+                snippet_html,
+            });
+        }
+        let next_cursor = list_options.next_cursor(page_len, total);
+
+        Ok(TicketSearchPage {
+            data,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+// region:    --- Search Index
+
+pub(super) struct SearchIndex {
+    stemmer: Stemmer,
+    inner: RwLock<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Term -> ticket id -> weighted term frequency.
+    postings: HashMap<String, HashMap<u64, u32>>,
+    docs: HashMap<u64, Doc>,
+    /// Sum of the docs `len`.
+    total_len: u64,
+}
+
+struct Doc {
+    cid: u64,
+    /// Weighted number of terms.
+    len: u32,
+    /// Distinct terms, to remove the doc from their postings.
+    terms: Vec<String>,
+}
+
+/// A word of a text, as its term, and its byte range in the text.
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self {
+            stemmer: Stemmer::create(Algorithm::English),
+            inner: RwLock::default(),
+        }
+    }
+}
+
+impl SearchIndex {
+    /// Index all the live tickets of the store.
+    pub(super) async fn build(store: &dyn Store) -> Result<Self> {
+        let index = Self::default();
+        let filter = TicketFilter::default();
+
+        let mut offset = 0;
+        loop {
+            let list_options = ListOptions::new(Some(u64::MAX), Some(offset), None)?;
+            let (tickets, total) = store
+                .list_tickets(&filter, TicketOrderBy::default(), list_options)
+                .await?;
+            offset += tickets.len() as u64;
+            for ticket in &tickets {
+                index.put(ticket);
+            }
+            if tickets.is_empty() || offset >= total {
+                break;
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Index the ticket as it now is (or remove it if in the trash).
+    pub(super) fn put(&self, ticket: &Ticket) {
+        let mut tfs: HashMap<String, u32> = HashMap::new();
+        if ticket.deleted.is_none() {
+            for token in self.tokenize(&ticket.title) {
+                *tfs.entry(token.term).or_default() += TITLE_WEIGHT;
+            }
+            for token in self.tokenize(ticket.description.as_deref().unwrap_or_default()) {
+                *tfs.entry(token.term).or_default() += 1;
+            }
+        }
+
+        let mut inner = self.inner.write().unwrap();
+        inner.remove(ticket.id);
+        if ticket.deleted.is_some() {
+            return;
+        }
+
+        let len = tfs.values().sum();
+        for (term, tf) in &tfs {
+            inner
+                .postings
+                .entry(term.clone())
+                .or_default()
+                .insert(ticket.id, *tf);
+        }
+        inner.total_len += u64::from(len);
+        inner.docs.insert(
+            ticket.id,
+            Doc {
+                cid: ticket.cid,
+                len,
+                terms: tfs.into_keys().collect(),
+            },
+        );
+    }
+
+    /// The distinct terms of a query.
+    pub(super) fn query_terms(&self, q: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+
+        self.tokenize(q)
+            .into_iter()
+            .map(|token| token.term)
+            .filter(|term| seen.insert(term.clone()))
+            .collect()
+    }
+
+    /// The ids of the tickets (of creator `cid` if given) matching any of the
+    /// terms, with their score, by descending score then ascending id.
+    pub(super) fn search(&self, terms: &[String], cid: Option<u64>) -> Vec<(u64, f64)> {
+        let inner = self.inner.read().unwrap();
+
+        let doc_count = inner.docs.len() as f64;
+        let avg_len = (inner.total_len as f64 / doc_count).max(1.);
+
+        let mut scores: HashMap<u64, f64> = HashMap::new();
+        for term in terms {
+            let Some(postings) = inner.postings.get(term) else {
+                continue;
+            };
+            let df = postings.len() as f64;
+            let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.).ln();
+
+            for (id, tf) in postings {
+                let doc = &inner.docs[id];
+                if cid.is_some_and(|cid| doc.cid != cid) {
+                    continue;
+                }
+                let tf = f64::from(*tf);
+                let norm = 1. - BM25_B + BM25_B * f64::from(doc.len) / avg_len;
+                *scores.entry(*id).or_default() +=
+                    idf * tf * (BM25_K1 + 1.) / (tf + BM25_K1 * norm);
+            }
+        }
+
+        let mut ranked: Vec<(u64, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        ranked
+    }
+
+    /// The text HTML escaped, with the words of the terms in `<mark>`.
+    /// With `max_len`, only an excerpt around the first match (or the start).
+    pub(super) fn highlight(&self, text: &str, terms: &[String], max_len: Option<usize>) -> String {
+        let tokens = self.tokenize(text);
+        let is_match = |token: &Token| terms.contains(&token.term);
+
+        let (mut start, mut end) = (0, text.len());
+        if let Some(max_len) = max_len.filter(|max_len| text.len() > *max_len) {
+            // Start a few words before the first match, and cut at a word end.
+            let first = tokens.iter().position(is_match).unwrap_or(0);
+            let first_start = tokens.get(first).map_or(0, |t| t.start);
+            start = tokens[..first]
+                .iter()
+                .find(|t| t.start + max_len / 4 >= first_start)
+                .map_or(first_start, |t| t.start);
+            end = tokens
+                .iter()
+                .rev()
+                .find(|t| t.start >= start && t.end <= start + max_len)
+                .map_or(text.len().min(start + max_len), |t| t.end);
+            // Not cut in a multi-byte char.
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+        }
+
+        let mut html = String::new();
+        if start > 0 {
+            html.push('…');
+        }
+        let mut pos = start;
+        for token in tokens.iter().filter(|t| t.start >= start && t.end <= end) {
+            if is_match(token) {
+                html.push_str(&escape_html(&text[pos..token.start]));
+                html.push_str("<mark>");
+                html.push_str(&escape_html(&text[token.start..token.end]));
+                html.push_str("</mark>");
+                pos = token.end;
+            }
+        }
+        html.push_str(&escape_html(&text[pos..end]));
+        if end < text.len() {
+            html.push('…');
+        }
+
+        html
+    }
+
+    /// The alphanumeric words, lowercased and stemmed.
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut word_start = None;
+
+        for (idx, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (c.is_alphanumeric(), word_start) {
+                (true, None) => word_start = Some(idx),
+                (false, Some(start)) => {
+                    let word = text[start..idx].to_lowercase();
+                    tokens.push(Token {
+                        term: self.stemmer.stem(&word).into_owned(),
+                        start,
+                        end: idx,
+                    });
+                    word_start = None;
+                }
+                _ => (),
+            }
+        }
+
+        tokens
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, id: u64) {
+        let Some(doc) = self.docs.remove(&id) else {
+            return;
+        };
+
+        self.total_len -= u64::from(doc.len);
+        for term in doc.terms {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                postings.remove(&id);
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+// endregion: --- Search Index
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::store::tests::new_ticket;
+    use crate::model::{TicketPriority, TicketStatus};
+    use time::OffsetDateTime;
+
+    fn ticket(id: u64, title: &str, description: Option<&str>) -> Ticket {
+        Ticket {
+            id,
+            description: description.map(str::to_string),
+            ..new_ticket(id, title, TicketStatus::Open, TicketPriority::Medium)
+        }
+    }
+
+    #[test]
+    fn test_search_index_stemmed_ranked_incremental() {
+        let index = SearchIndex::default();
+        index.put(&ticket(
+            1,
+            "Login crashes",
+            Some("The app crashed on login."),
+        ));
+        index.put(&ticket(
+            2,
+            "Slow search",
+            Some("Crashing is not the issue."),
+        ));
+        index.put(&ticket(3, "Dark mode", None));
+
+        // -- Stemmed, a title match ranks first.
+        let terms = index.query_terms("CRASH crash");
+        assert_eq!(terms, vec!["crash"]);
+        let ids: Vec<u64> = index.search(&terms, None).iter().map(|h| h.0).collect();
+        assert_eq!(ids, vec![1, 2]);
+        let ids: Vec<u64> = index.search(&terms, Some(2)).iter().map(|h| h.0).collect();
+        assert_eq!(ids, vec![2]);
+
+        // -- Updated, and removed once in the trash.
+        index.put(&ticket(2, "Slow search", None));
+        let mut deleted = ticket(1, "Login crashes", None);
+        deleted.deleted = Some(OffsetDateTime::now_utc());
+        index.put(&deleted);
+        assert!(index.search(&terms, None).is_empty());
+    }
+
+    #[test]
+    fn test_search_highlight() {
+        let index = SearchIndex::default();
+        let terms = index.query_terms("crash");
+
+        let html = index.highlight("<b>Crashes</b> & crash", &terms, None);
+        assert_eq!(
+            html,
+            "&lt;b&gt;<mark>Crashes</mark>&lt;/b&gt; &amp; <mark>crash</mark>"
+        );
+
+        // -- Excerpt around the first match.
+        let text = format!(
+            "{} the crash happens {}",
+            "word ".repeat(50),
+            "after ".repeat(50)
+        );
+        let html = index.highlight(&text, &terms, Some(40));
+        assert!(html.starts_with('…') && html.ends_with('…'), "{html}");
+        assert!(html.contains("<mark>crash</mark>"), "{html}");
+        assert!(html.len() < 80, "{html}");
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,204 @@
+//! Server side login sessions, referenced by the auth token cookie.
+//!
+//! A session is created on login, and removed on logout or revoke, so the
+//! cookie stops working right away (e.g., when leaked).
+//! It also expires after `idle_timeout` without requests (see `resolve_session`).
+
+use crate::ctx::{Ctx, Role};
+use crate::model::ModelController;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Default of `ModelController::with_session_idle_timeout`.
+pub(super) const SESSION_IDLE_TIMEOUT_DEFAULT: Duration = Duration::from_secs(1800);
+/// The `last_seen` is only written when older than this, not on each request.
+const LAST_SEEN_RESOLUTION: Duration = Duration::from_secs(60);
+const USER_AGENT_MAX_LEN: usize = 256;
+
+// region:    --- Session Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: u64,
+    /// Of the login request.
+    pub user_agent: Option<String>,
+    /// Client IP of the login request.
+    pub ip: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    /// Last request, within a minute.
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_seen: OffsetDateTime,
+    /// The session of the request listing them (not stored).
+    #[serde(default)]
+    pub current: bool,
+}
+
+// endregion: --- Session Types
+
+// region:    --- Session Lifecycle
+
+impl ModelController {
+    /// Start a session for the logged in user.
+    pub async fn create_session(
+        &self,
+        user_id: u64,
+        user_agent: Option<String>,
+        ip: Option<String>,
+    ) -> Result<Session> {
+        let now = OffsetDateTime::now_utc();
+
+        // Also clear the expired ones, of the users who never log out.
+        self.store
+            .purge_sessions(now - self.session_idle_timeout)
+            .await?;
+
+        let session = Session {
+            id: Uuid::new_v4(),
+            user_id,
+            user_agent: user_agent.map(|ua| ua.chars().take(USER_AGENT_MAX_LEN).collect()),
+            ip,
+            ctime: now,
+            last_seen: now,
+            current: false,
+        };
+
+        self.store.insert_session(session).await
+    }
+
+    /// The live session of the auth token, with its `last_seen` refreshed.
+    /// Fails if it was revoked (or logged out), or idled out.
+    pub async fn resolve_session(&self, id: Uuid) -> Result<Session> {
+        let mut session = self
+            .store
+            .get_session(id)
+            .await?
+            .ok_or(Error::AuthFailSessionNotFound)?;
+
+        let now = OffsetDateTime::now_utc();
+        let idle = now - session.last_seen;
+        if idle >= self.session_idle_timeout {
+            self.store.delete_session(id).await?;
+            return Err(Error::AuthFailSessionExpired);
+        }
+        if idle >= LAST_SEEN_RESOLUTION {
+            self.store.touch_session(id, now).await?;
+            session.last_seen = now;
+        }
+
+        Ok(session)
+    }
+
+    /// Log out, removing the session of the `ctx` (if any).
+    pub async fn end_session(&self, ctx: &Ctx) -> Result<()> {
+        if let Some(id) = ctx.session_id() {
+            self.store.delete_session(id).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// endregion: --- Session Lifecycle
+
+// region:    --- Session Management
+
+// - Users:  list and revoke their own sessions.
+// - Admin:  can also revoke the sessions of the other users (e.g., leaked cookie).
+
+impl ModelController {
+    /// The live sessions of the ctx user, last seen first.
+    pub async fn list_sessions(&self, ctx: Ctx) -> Result<Vec<Session>> {
+        let idle_since = OffsetDateTime::now_utc() - self.session_idle_timeout;
+
+        let sessions = self
+            .store
+            .list_sessions(ctx.user_id())
+            .await?
+            .into_iter()
+            .filter(|session| session.last_seen > idle_since)
+            .map(|session| Session {
+                current: ctx.session_id() == Some(session.id),
+                ..session
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Its next request with this session fails with `NO_AUTH`.
+    pub async fn revoke_session(&self, ctx: Ctx, id: Uuid) -> Result<Session> {
+        let session = self
+            .store
+            .get_session(id)
+            .await?
+            // Other users' sessions are not found, rather than denied.
+            .filter(|session| session.user_id == ctx.user_id() || ctx.role() == Role::Admin)
+            .ok_or(Error::SessionNotFound { id })?;
+
+        self.store.delete_session(id).await?;
+
+        Ok(Session {
+            current: ctx.session_id() == Some(session.id),
+            ..session
+        })
+    }
+
+    /// Log out everywhere, the current session included.
+    /// Returns the number of revoked sessions.
+    pub async fn revoke_all_sessions(&self, ctx: Ctx) -> Result<u64> {
+        self.store.delete_user_sessions(ctx.user_id()).await
+    }
+}
+
+// endregion: --- Session Management
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+
+    #[tokio::test]
+    async fn test_session_revoked_and_idle_expired() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let s1 = mc.create_session(1, Some("ua-1".into()), None).await?;
+        let s2 = mc.create_session(1, None, None).await?;
+        let ctx = Ctx::new(1, Role::Member).with_session_id(s1.id);
+
+        let sessions = mc.list_sessions(ctx.clone()).await?;
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().any(|s| s.id == s1.id && s.current));
+
+        // -- Revoked.
+        mc.revoke_session(ctx.clone(), s2.id).await?;
+        assert!(matches!(
+            mc.resolve_session(s2.id).await,
+            Err(Error::AuthFailSessionNotFound)
+        ));
+        let other_ctx = Ctx::new(2, Role::Member);
+        assert!(matches!(
+            mc.revoke_session(other_ctx, s1.id).await,
+            Err(Error::SessionNotFound { .. })
+        ));
+        assert_eq!(mc.resolve_session(s1.id).await?.user_id, 1);
+
+        // -- Idle expired.
+        let mc = mc.with_session_idle_timeout(Duration::ZERO);
+        assert!(matches!(
+            mc.resolve_session(s1.id).await,
+            Err(Error::AuthFailSessionExpired)
+        ));
+        assert!(mc.list_sessions(ctx).await?.is_empty());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
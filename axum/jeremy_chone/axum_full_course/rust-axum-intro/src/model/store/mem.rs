@@ -0,0 +1,682 @@
+//! In-memory store (nothing survives a restart).
+
+use crate::ctx::Role;
+use crate::model::audit::{AuditEntry, AuditFilter, AuditForCreate};
+use crate::model::comment::Comment;
+use crate::model::idempotency::IdempotencyRecord;
+use crate::model::label::Label;
+use crate::model::session::Session;
+use crate::model::store::Store;
+use crate::model::user::User;
+use crate::model::webhook::{Webhook, WebhookAttempt};
+use crate::model::{ListOptions, Ticket, TicketField, TicketFilter, TicketOrderBy};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Default)]
+pub struct MemStore {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    // Last ids handed out. Ids are never reused, even after a delete.
+    last_ticket_id: u64,
+    last_user_id: u64,
+    last_label_id: u64,
+    last_comment_id: u64,
+    last_webhook_id: u64,
+    last_webhook_attempt_id: u64,
+
+    tickets: BTreeMap<u64, Ticket>,
+    users: BTreeMap<u64, User>,
+    labels: BTreeMap<u64, Label>,
+    comments: BTreeMap<u64, Comment>,
+    webhooks: BTreeMap<u64, Webhook>,
+    webhook_attempts: BTreeMap<u64, WebhookAttempt>,
+    sessions: BTreeMap<Uuid, Session>,
+    /// Append only, the entry id is its index + 1.
+    audit: Vec<AuditEntry>,
+    /// By (user_id, key).
+    idempotency: BTreeMap<(u64, String), IdempotencyRecord>,
+}
+
+impl MemStore {
+    fn lock(&self) -> Result<MutexGuard<'_, Inner>> {
+        // Poisoned by a panic while holding it.
+        self.inner
+            .lock()
+            .map_err(|_| Error::Store("mem store lock poisoned".to_string()))
+    }
+}
+
+impl Inner {
+    fn append_audit(
+        &mut self,
+        audit: AuditForCreate,
+        ticket_id: u64,
+        before: Option<Ticket>,
+        after: Option<Ticket>,
+    ) {
+        let id = self.audit.len() as u64 + 1;
+        self.audit
+            .push(audit.into_entry(id, ticket_id, before, after));
+    }
+
+    /// Same as the SQLite `COLLATE NOCASE` of the label names.
+    fn label_name_taken(&self, label: &Label) -> bool {
+        self.labels
+            .values()
+            .any(|l| l.id != label.id && l.name.eq_ignore_ascii_case(&label.name))
+    }
+}
+
+#[async_trait]
+impl Store for MemStore {
+    async fn insert_ticket(&self, mut ticket: Ticket, audit: AuditForCreate) -> Result<Ticket> {
+        let mut inner = self.lock()?;
+
+        inner.last_ticket_id += 1;
+        ticket.id = inner.last_ticket_id;
+
+        inner.tickets.insert(ticket.id, ticket.clone());
+        inner.append_audit(audit, ticket.id, None, Some(ticket.clone()));
+
+        Ok(ticket)
+    }
+
+    async fn insert_tickets(&self, tickets: Vec<(Ticket, AuditForCreate)>) -> Result<Vec<Ticket>> {
+        let mut inner = self.lock()?;
+
+        let tickets = tickets
+            .into_iter()
+            .map(|(mut ticket, audit)| {
+                inner.last_ticket_id += 1;
+                ticket.id = inner.last_ticket_id;
+
+                inner.tickets.insert(ticket.id, ticket.clone());
+                inner.append_audit(audit, ticket.id, None, Some(ticket.clone()));
+
+                ticket
+            })
+            .collect();
+
+        Ok(tickets)
+    }
+
+    async fn get_ticket(&self, id: u64) -> Result<Option<Ticket>> {
+        let inner = self.lock()?;
+
+        Ok(inner.tickets.get(&id).cloned())
+    }
+
+    async fn list_tickets(
+        &self,
+        filter: &TicketFilter,
+        order_by: TicketOrderBy,
+        list_options: ListOptions,
+    ) -> Result<(Vec<Ticket>, u64)> {
+        let inner = self.lock()?;
+
+        let mut tickets: Vec<&Ticket> = inner
+            .tickets
+            .values()
+            .filter(|t| ticket_matches(t, filter))
+            .collect();
+        let total = tickets.len() as u64;
+
+        // Stable sort, and the map iterates by id, so ties stay by ascending id.
+        tickets.sort_by(|a, b| {
+            let ord = cmp_ticket_field(a, b, order_by.field);
+            if order_by.desc {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+
+        let tickets = tickets
+            .into_iter()
+            .skip(list_options.offset as usize)
+            .take(list_options.limit as usize)
+            .cloned()
+            .collect();
+
+        Ok((tickets, total))
+    }
+
+    async fn update_ticket(
+        &self,
+        mut ticket: Ticket,
+        audit: AuditForCreate,
+    ) -> Result<Option<Ticket>> {
+        let mut inner = self.lock()?;
+
+        let Some(stored) = inner.tickets.get_mut(&ticket.id) else {
+            return Ok(None);
+        };
+        if stored.version != ticket.version {
+            return Err(Error::TicketPreconditionFailed {
+                id: ticket.id,
+                version: stored.version,
+            });
+        }
+        ticket.version += 1;
+        let before = std::mem::replace(stored, ticket.clone());
+        inner.append_audit(audit, ticket.id, Some(before), Some(ticket.clone()));
+
+        Ok(Some(ticket))
+    }
+
+    async fn purge_tickets(
+        &self,
+        deleted_before: OffsetDateTime,
+        audit: AuditForCreate,
+    ) -> Result<Vec<Ticket>> {
+        let mut inner = self.lock()?;
+
+        let ids: Vec<u64> = inner
+            .tickets
+            .values()
+            .filter(|t| t.deleted.is_some_and(|deleted| deleted < deleted_before))
+            .map(|t| t.id)
+            .collect();
+
+        let mut purged = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(ticket) = inner.tickets.remove(&id) {
+                inner.append_audit(audit.clone(), id, Some(ticket.clone()), None);
+                purged.push(ticket);
+            }
+        }
+        inner
+            .comments
+            .retain(|_, comment| !purged.iter().any(|t| t.id == comment.ticket_id));
+
+        Ok(purged)
+    }
+
+    async fn count_tickets(&self) -> Result<u64> {
+        let inner = self.lock()?;
+
+        Ok(inner.tickets.len() as u64)
+    }
+
+    async fn list_audit(
+        &self,
+        filter: &AuditFilter,
+        list_options: ListOptions,
+    ) -> Result<(Vec<AuditEntry>, u64)> {
+        let inner = self.lock()?;
+
+        let entries: Vec<&AuditEntry> = inner
+            .audit
+            .iter()
+            .filter(|e| audit_matches(e, filter))
+            .collect();
+        let total = entries.len() as u64;
+
+        let entries = entries
+            .into_iter()
+            .skip(list_options.offset as usize)
+            .take(list_options.limit as usize)
+            .cloned()
+            .collect();
+
+        Ok((entries, total))
+    }
+
+    async fn insert_label(&self, mut label: Label) -> Result<Label> {
+        let mut inner = self.lock()?;
+
+        if inner.label_name_taken(&label) {
+            return Err(Error::LabelNameTaken { name: label.name });
+        }
+
+        inner.last_label_id += 1;
+        label.id = inner.last_label_id;
+        inner.labels.insert(label.id, label.clone());
+
+        Ok(label)
+    }
+
+    async fn get_label(&self, id: u64) -> Result<Option<Label>> {
+        let inner = self.lock()?;
+
+        Ok(inner.labels.get(&id).cloned())
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>> {
+        let inner = self.lock()?;
+
+        let mut labels: Vec<Label> = inner.labels.values().cloned().collect();
+        labels.sort_by_key(|l| l.name.to_ascii_lowercase());
+
+        Ok(labels)
+    }
+
+    async fn update_label(&self, label: Label) -> Result<Option<Label>> {
+        let mut inner = self.lock()?;
+
+        if inner.label_name_taken(&label) {
+            return Err(Error::LabelNameTaken { name: label.name });
+        }
+        let Some(stored) = inner.labels.get_mut(&label.id) else {
+            return Ok(None);
+        };
+        *stored = label.clone();
+
+        Ok(Some(label))
+    }
+
+    async fn delete_label(&self, id: u64) -> Result<()> {
+        let mut inner = self.lock()?;
+
+        inner.labels.remove(&id);
+
+        Ok(())
+    }
+
+    async fn insert_comment(&self, mut comment: Comment) -> Result<Comment> {
+        let mut inner = self.lock()?;
+
+        inner.last_comment_id += 1;
+        comment.id = inner.last_comment_id;
+        inner.comments.insert(comment.id, comment.clone());
+
+        Ok(comment)
+    }
+
+    async fn get_comment(&self, id: u64) -> Result<Option<Comment>> {
+        let inner = self.lock()?;
+
+        Ok(inner.comments.get(&id).cloned())
+    }
+
+    async fn list_comments(&self, ticket_id: u64) -> Result<Vec<Comment>> {
+        let inner = self.lock()?;
+
+        Ok(inner
+            .comments
+            .values()
+            .filter(|c| c.ticket_id == ticket_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn update_comment(&self, comment: Comment) -> Result<Option<Comment>> {
+        let mut inner = self.lock()?;
+
+        let Some(stored) = inner.comments.get_mut(&comment.id) else {
+            return Ok(None);
+        };
+        *stored = comment.clone();
+
+        Ok(Some(comment))
+    }
+
+    async fn delete_comments(&self, ids: &[u64]) -> Result<u64> {
+        let mut inner = self.lock()?;
+
+        let count = inner.comments.len();
+        inner.comments.retain(|id, _| !ids.contains(id));
+
+        Ok((count - inner.comments.len()) as u64)
+    }
+
+    async fn insert_webhook(&self, mut webhook: Webhook) -> Result<Webhook> {
+        let mut inner = self.lock()?;
+
+        inner.last_webhook_id += 1;
+        webhook.id = inner.last_webhook_id;
+        inner.webhooks.insert(webhook.id, webhook.clone());
+
+        Ok(webhook)
+    }
+
+    async fn get_webhook(&self, id: u64) -> Result<Option<Webhook>> {
+        let inner = self.lock()?;
+
+        Ok(inner.webhooks.get(&id).cloned())
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let inner = self.lock()?;
+
+        Ok(inner.webhooks.values().cloned().collect())
+    }
+
+    async fn update_webhook(&self, webhook: Webhook) -> Result<Option<Webhook>> {
+        let mut inner = self.lock()?;
+
+        let Some(stored) = inner.webhooks.get_mut(&webhook.id) else {
+            return Ok(None);
+        };
+        *stored = webhook.clone();
+
+        Ok(Some(webhook))
+    }
+
+    async fn delete_webhook(&self, id: u64) -> Result<()> {
+        let mut inner = self.lock()?;
+
+        inner.webhooks.remove(&id);
+        inner
+            .webhook_attempts
+            .retain(|_, attempt| attempt.webhook_id != id);
+
+        Ok(())
+    }
+
+    async fn record_webhook_delivery(
+        &self,
+        id: u64,
+        delivered: bool,
+        disable_after: u32,
+    ) -> Result<Option<Webhook>> {
+        let mut inner = self.lock()?;
+
+        let webhook = inner.webhooks.get_mut(&id).map(|webhook| {
+            if delivered {
+                webhook.failures = 0;
+            } else {
+                webhook.failures += 1;
+                if webhook.failures >= disable_after {
+                    webhook.active = false;
+                }
+            }
+            webhook.clone()
+        });
+
+        Ok(webhook)
+    }
+
+    async fn insert_webhook_attempt(&self, mut attempt: WebhookAttempt) -> Result<WebhookAttempt> {
+        let mut inner = self.lock()?;
+
+        inner.last_webhook_attempt_id += 1;
+        attempt.id = inner.last_webhook_attempt_id;
+        inner.webhook_attempts.insert(attempt.id, attempt.clone());
+
+        Ok(attempt)
+    }
+
+    async fn list_webhook_attempts(
+        &self,
+        webhook_id: u64,
+        limit: u64,
+    ) -> Result<Vec<WebhookAttempt>> {
+        let inner = self.lock()?;
+
+        let attempts = inner
+            .webhook_attempts
+            .values()
+            .rev()
+            .filter(|attempt| attempt.webhook_id == webhook_id)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+
+        Ok(attempts)
+    }
+
+    async fn get_idempotency(&self, user_id: u64, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let inner = self.lock()?;
+
+        Ok(inner.idempotency.get(&(user_id, key.to_string())).cloned())
+    }
+
+    async fn put_idempotency(&self, record: IdempotencyRecord) -> Result<()> {
+        let mut inner = self.lock()?;
+
+        inner
+            .idempotency
+            .insert((record.user_id, record.key.clone()), record);
+
+        Ok(())
+    }
+
+    async fn purge_idempotency(&self, ctime_before: OffsetDateTime) -> Result<u64> {
+        let mut inner = self.lock()?;
+
+        let count = inner.idempotency.len();
+        inner
+            .idempotency
+            .retain(|_, record| record.ctime >= ctime_before);
+
+        Ok((count - inner.idempotency.len()) as u64)
+    }
+
+    async fn insert_user(
+        &self,
+        username: String,
+        pwd: String,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<User> {
+        let mut inner = self.lock()?;
+
+        if inner.users.values().any(|u| u.username == username) {
+            return Err(Error::RegisterFailUsernameExists { username });
+        }
+
+        inner.last_user_id += 1;
+        let id = inner.last_user_id;
+
+        let user = User {
+            id,
+            username,
+            pwd,
+            role,
+            ctime: now,
+            mtime: now,
+        };
+        inner.users.insert(id, user.clone());
+
+        Ok(user)
+    }
+
+    async fn get_user(&self, id: u64) -> Result<Option<User>> {
+        let inner = self.lock()?;
+
+        Ok(inner.users.get(&id).cloned())
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        let inner = self.lock()?;
+
+        Ok(inner
+            .users
+            .values()
+            .find(|u| u.username == username)
+            .cloned())
+    }
+
+    async fn count_users(&self) -> Result<u64> {
+        let inner = self.lock()?;
+
+        Ok(inner.users.len() as u64)
+    }
+
+    async fn update_user_pwd(&self, id: u64, pwd: String, now: OffsetDateTime) -> Result<()> {
+        let mut inner = self.lock()?;
+
+        if let Some(user) = inner.users.get_mut(&id) {
+            user.pwd = pwd;
+            user.mtime = now;
+        }
+
+        Ok(())
+    }
+
+    async fn update_user_role(
+        &self,
+        id: u64,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<Option<User>> {
+        let mut inner = self.lock()?;
+
+        let user = inner.users.get_mut(&id).map(|user| {
+            user.role = role;
+            user.mtime = now;
+            user.clone()
+        });
+
+        Ok(user)
+    }
+
+    async fn insert_session(&self, session: Session) -> Result<Session> {
+        let mut inner = self.lock()?;
+
+        inner.sessions.insert(session.id, session.clone());
+
+        Ok(session)
+    }
+
+    async fn get_session(&self, id: Uuid) -> Result<Option<Session>> {
+        let inner = self.lock()?;
+
+        Ok(inner.sessions.get(&id).cloned())
+    }
+
+    async fn list_sessions(&self, user_id: u64) -> Result<Vec<Session>> {
+        let inner = self.lock()?;
+
+        let mut sessions: Vec<Session> = inner
+            .sessions
+            .values()
+            .filter(|session| session.user_id == user_id)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen));
+
+        Ok(sessions)
+    }
+
+    async fn touch_session(&self, id: Uuid, last_seen: OffsetDateTime) -> Result<()> {
+        let mut inner = self.lock()?;
+
+        if let Some(session) = inner.sessions.get_mut(&id) {
+            session.last_seen = last_seen;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_session(&self, id: Uuid) -> Result<Option<Session>> {
+        let mut inner = self.lock()?;
+
+        Ok(inner.sessions.remove(&id))
+    }
+
+    async fn delete_user_sessions(&self, user_id: u64) -> Result<u64> {
+        let mut inner = self.lock()?;
+
+        let count = inner.sessions.len();
+        inner
+            .sessions
+            .retain(|_, session| session.user_id != user_id);
+
+        Ok((count - inner.sessions.len()) as u64)
+    }
+
+    async fn purge_sessions(&self, last_seen_before: OffsetDateTime) -> Result<u64> {
+        let mut inner = self.lock()?;
+
+        let count = inner.sessions.len();
+        inner
+            .sessions
+            .retain(|_, session| session.last_seen >= last_seen_before);
+
+        Ok((count - inner.sessions.len()) as u64)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn ticket_matches(ticket: &Ticket, filter: &TicketFilter) -> bool {
+    let TicketFilter {
+        cid,
+        assignee,
+        status,
+        priority,
+        title_contains,
+        label,
+        trashed,
+    } = filter;
+
+    ticket.deleted.is_some() == *trashed
+        && cid.is_none_or(|cid| ticket.cid == cid)
+        && assignee.is_none_or(|assignee| ticket.assignee == Some(assignee))
+        && status.is_none_or(|status| ticket.status == status)
+        && priority.is_none_or(|priority| ticket.priority == priority)
+        && title_contains
+            .as_ref()
+            .is_none_or(|part| ticket.title.to_lowercase().contains(&part.to_lowercase()))
+        && label.is_none_or(|label| ticket.labels.contains(&label))
+}
+
+fn audit_matches(entry: &AuditEntry, filter: &AuditFilter) -> bool {
+    let AuditFilter {
+        actor,
+        ticket_id,
+        from,
+        to,
+    } = filter;
+
+    actor.is_none_or(|actor| entry.actor == actor)
+        && ticket_id.is_none_or(|ticket_id| entry.ticket_id == ticket_id)
+        && from.is_none_or(|from| entry.ctime >= from)
+        && to.is_none_or(|to| entry.ctime < to)
+}
+
+// Note: `None` sorts first, like the SQL NULLs.
+fn cmp_ticket_field(a: &Ticket, b: &Ticket, field: TicketField) -> Ordering {
+    match field {
+        TicketField::Id => a.id.cmp(&b.id),
+        TicketField::Cid => a.cid.cmp(&b.cid),
+        TicketField::Title => a.title.cmp(&b.title),
+        TicketField::Description => a.description.cmp(&b.description),
+        TicketField::Status => a.status.cmp(&b.status),
+        TicketField::Priority => a.priority.cmp(&b.priority),
+        TicketField::Assignee => a.assignee.cmp(&b.assignee),
+        TicketField::Ctime => a.ctime.cmp(&b.ctime),
+        TicketField::Mtime => a.mtime.cmp(&b.mtime),
+        TicketField::Deleted => a.deleted.cmp(&b.deleted),
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mem_poisoned_lock_is_store_error() {
+        let store = MemStore::default();
+
+        std::thread::scope(|scope| {
+            let res = scope
+                .spawn(|| {
+                    let _inner = store.inner.lock().unwrap();
+                    panic!("update panicked");
+                })
+                .join();
+            assert!(res.is_err());
+        });
+
+        // The next queries fail, rather than panic.
+        assert!(matches!(store.count_tickets().await, Err(Error::Store(_))));
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,430 @@
+//! Storage backends for the `ModelController`.
+//!
+//! The model layer only talks to a `dyn Store`, so the backend
+//! (in-memory or SQLite) is chosen at startup from the config.
+
+mod mem;
+mod sqlite;
+
+pub use self::mem::MemStore;
+pub use self::sqlite::SqliteStore;
+
+use crate::config::StoreConfig;
+use crate::ctx::Role;
+use crate::model::audit::{AuditEntry, AuditFilter, AuditForCreate};
+use crate::model::comment::Comment;
+use crate::model::idempotency::IdempotencyRecord;
+use crate::model::label::Label;
+use crate::model::session::Session;
+use crate::model::user::User;
+use crate::model::webhook::{Webhook, WebhookAttempt};
+use crate::model::{ListOptions, Ticket, TicketFilter, TicketOrderBy};
+use crate::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait Store: Send + Sync {
+    // -- Tickets
+    // Note: Each ticket mutation also appends its audit entry, atomically.
+
+    /// The `ticket.id` is ignored, a new one is assigned by the store.
+    async fn insert_ticket(&self, ticket: Ticket, audit: AuditForCreate) -> Result<Ticket>;
+
+    /// Insert all the tickets, or none (on error).
+    /// Returns the stored tickets, in the same order.
+    async fn insert_tickets(&self, tickets: Vec<(Ticket, AuditForCreate)>) -> Result<Vec<Ticket>>;
+
+    async fn get_ticket(&self, id: u64) -> Result<Option<Ticket>>;
+
+    /// Returns the requested page of tickets matching the filter,
+    /// and the total number of matching tickets.
+    /// Ties in `order_by` are broken by ascending id, so paging is stable.
+    async fn list_tickets(
+        &self,
+        filter: &TicketFilter,
+        order_by: TicketOrderBy,
+        list_options: ListOptions,
+    ) -> Result<(Vec<Ticket>, u64)>;
+
+    /// Replace the ticket with the same id, bumping its `version`.
+    /// `ticket.version` is the version the change was made from, if the stored
+    /// one is no longer it, fails with `TicketPreconditionFailed`.
+    /// Returns the stored ticket, or `None` if no ticket has this id.
+    async fn update_ticket(&self, ticket: Ticket, audit: AuditForCreate) -> Result<Option<Ticket>>;
+
+    /// Permanently remove the tickets in the trash since before `deleted_before`
+    /// (one audit entry each), with their comments. Returns the removed tickets.
+    async fn purge_tickets(
+        &self,
+        deleted_before: OffsetDateTime,
+        audit: AuditForCreate,
+    ) -> Result<Vec<Ticket>>;
+
+    async fn count_tickets(&self) -> Result<u64>;
+
+    // -- Audit
+    /// Returns the requested page of entries matching the filter (by ascending id),
+    /// and the total number of matching entries.
+    async fn list_audit(
+        &self,
+        filter: &AuditFilter,
+        list_options: ListOptions,
+    ) -> Result<(Vec<AuditEntry>, u64)>;
+
+    // -- Labels
+    /// The `label.id` is ignored, a new one is assigned by the store.
+    /// Fails with `LabelNameTaken` if the name is taken (ASCII case insensitive).
+    async fn insert_label(&self, label: Label) -> Result<Label>;
+
+    async fn get_label(&self, id: u64) -> Result<Option<Label>>;
+
+    /// All the labels, by name.
+    async fn list_labels(&self) -> Result<Vec<Label>>;
+
+    /// Fails with `LabelNameTaken` as `insert_label`.
+    /// Returns the stored label, or `None` if no label has this id.
+    async fn update_label(&self, label: Label) -> Result<Option<Label>>;
+
+    /// Only the label, the tickets having it are not changed.
+    async fn delete_label(&self, id: u64) -> Result<()>;
+
+    // -- Comments
+    /// The `comment.id` is ignored, a new one is assigned by the store.
+    async fn insert_comment(&self, comment: Comment) -> Result<Comment>;
+
+    async fn get_comment(&self, id: u64) -> Result<Option<Comment>>;
+
+    /// The comments of the ticket, by id.
+    async fn list_comments(&self, ticket_id: u64) -> Result<Vec<Comment>>;
+
+    /// Returns the stored comment, or `None` if no comment has this id.
+    async fn update_comment(&self, comment: Comment) -> Result<Option<Comment>>;
+
+    /// Returns the number of removed comments.
+    async fn delete_comments(&self, ids: &[u64]) -> Result<u64>;
+
+    // -- Webhooks
+    /// The `webhook.id` is ignored, a new one is assigned by the store.
+    async fn insert_webhook(&self, webhook: Webhook) -> Result<Webhook>;
+
+    async fn get_webhook(&self, id: u64) -> Result<Option<Webhook>>;
+
+    /// All the webhooks, by id.
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>>;
+
+    /// Returns the stored webhook, or `None` if no webhook has this id.
+    async fn update_webhook(&self, webhook: Webhook) -> Result<Option<Webhook>>;
+
+    /// With its attempts.
+    async fn delete_webhook(&self, id: u64) -> Result<()>;
+
+    /// Atomically, resets the `failures` when `delivered`, or increments them
+    /// and disables the webhook when they reach `disable_after`.
+    /// Returns the stored webhook, or `None` if no webhook has this id.
+    async fn record_webhook_delivery(
+        &self,
+        id: u64,
+        delivered: bool,
+        disable_after: u32,
+    ) -> Result<Option<Webhook>>;
+
+    /// The `attempt.id` is ignored, a new one is assigned by the store.
+    async fn insert_webhook_attempt(&self, attempt: WebhookAttempt) -> Result<WebhookAttempt>;
+
+    /// The `limit` latest attempts of the webhook, latest first.
+    async fn list_webhook_attempts(
+        &self,
+        webhook_id: u64,
+        limit: u64,
+    ) -> Result<Vec<WebhookAttempt>>;
+
+    // -- Idempotency
+    async fn get_idempotency(&self, user_id: u64, key: &str) -> Result<Option<IdempotencyRecord>>;
+
+    /// Replaces the record with the same user and key, if any.
+    async fn put_idempotency(&self, record: IdempotencyRecord) -> Result<()>;
+
+    /// Remove the records created before `ctime_before`, returns how many.
+    async fn purge_idempotency(&self, ctime_before: OffsetDateTime) -> Result<u64>;
+
+    // -- Users
+    /// Fails with `RegisterFailUsernameExists` if the username is taken.
+    async fn insert_user(
+        &self,
+        username: String,
+        pwd: String,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<User>;
+
+    async fn get_user(&self, id: u64) -> Result<Option<User>>;
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>>;
+
+    async fn count_users(&self) -> Result<u64>;
+
+    async fn update_user_pwd(&self, id: u64, pwd: String, now: OffsetDateTime) -> Result<()>;
+
+    /// Returns the updated user, or `None` if no user has this id.
+    async fn update_user_role(
+        &self,
+        id: u64,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<Option<User>>;
+
+    // -- Sessions
+    async fn insert_session(&self, session: Session) -> Result<Session>;
+
+    async fn get_session(&self, id: Uuid) -> Result<Option<Session>>;
+
+    /// The sessions of the user, last seen first.
+    async fn list_sessions(&self, user_id: u64) -> Result<Vec<Session>>;
+
+    async fn touch_session(&self, id: Uuid, last_seen: OffsetDateTime) -> Result<()>;
+
+    /// Returns the removed session, or `None` if no session has this id.
+    async fn delete_session(&self, id: Uuid) -> Result<Option<Session>>;
+
+    /// Remove all the sessions of the user, returns how many.
+    async fn delete_user_sessions(&self, user_id: u64) -> Result<u64>;
+
+    /// Remove the sessions last seen before `last_seen_before`, returns how many.
+    async fn purge_sessions(&self, last_seen_before: OffsetDateTime) -> Result<u64>;
+
+    // -- Health
+
+    /// Fails if the store can't serve queries (e.g., db file gone or locked).
+    async fn ping(&self) -> Result<()>;
+}
+
+/// Open (and migrate if needed) the store described by the config.
+pub fn new_store(store_config: &StoreConfig) -> Result<Arc<dyn Store>> {
+    let store: Arc<dyn Store> = match store_config {
+        StoreConfig::Memory => Arc::new(MemStore::default()),
+        StoreConfig::Sqlite { path } => Arc::new(SqliteStore::open(path)?),
+    };
+
+    Ok(store)
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::model::TicketStatus::{self, *};
+    use crate::model::{AuditAction, TicketField, TicketPriority};
+
+    pub(in crate::model) fn new_ticket(
+        cid: u64,
+        title: &str,
+        status: TicketStatus,
+        priority: TicketPriority,
+    ) -> Ticket {
+        let now = OffsetDateTime::now_utc();
+        Ticket {
+            id: 0,
+            cid,
+            title: title.to_string(),
+            description: None,
+            status,
+            priority,
+            assignee: None,
+            ctime: now,
+            mtime: now,
+            deleted: None,
+            version: 1,
+            labels: Vec::new(),
+        }
+    }
+
+    fn audit_fc(actor: u64) -> AuditForCreate {
+        AuditForCreate {
+            actor,
+            action: AuditAction::TicketCreate,
+            ctime: OffsetDateTime::now_utc(),
+            req_uuid: None,
+        }
+    }
+
+    /// The same list queries must give the same results on all the backends.
+    #[tokio::test]
+    async fn test_list_tickets_same_on_all_stores() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+        let stores: Vec<Arc<dyn Store>> = vec![
+            new_store(&StoreConfig::Memory)?,
+            new_store(&StoreConfig::Sqlite { path: path.clone() })?,
+        ];
+
+        for store in stores {
+            for (cid, title, status, priority) in [
+                (1, "Fix the Bug", Open, TicketPriority::High),
+                (2, "bug report", Closed, TicketPriority::Low),
+                (1, "Feature", InProgress, TicketPriority::Low),
+                (1, "another BUG", Open, TicketPriority::Medium),
+                (2, "ÉCRAN noir", Open, TicketPriority::Low),
+            ] {
+                store
+                    .insert_ticket(new_ticket(cid, title, status, priority), audit_fc(cid))
+                    .await?;
+            }
+
+            // -- Filter, case insensitive title.
+            let filter = TicketFilter {
+                cid: Some(1),
+                title_contains: Some("bug".to_string()),
+                ..Default::default()
+            };
+            let (tickets, total) = store
+                .list_tickets(
+                    &filter,
+                    TicketOrderBy::default(),
+                    ListOptions::new(None, None, None)?,
+                )
+                .await?;
+            let ids: Vec<u64> = tickets.iter().map(|t| t.id).collect();
+            assert_eq!((ids, total), (vec![1, 4], 2));
+
+            // -- Case insensitive beyond ASCII too.
+            let filter = TicketFilter {
+                title_contains: Some("écran".to_string()),
+                ..Default::default()
+            };
+            let (tickets, _) = store
+                .list_tickets(
+                    &filter,
+                    TicketOrderBy::default(),
+                    ListOptions::new(None, None, None)?,
+                )
+                .await?;
+            let ids: Vec<u64> = tickets.iter().map(|t| t.id).collect();
+            assert_eq!(ids, vec![5]);
+
+            // -- Order by priority rank (not name), desc, paged.
+            let order_by = TicketOrderBy {
+                field: TicketField::Priority,
+                desc: true,
+            };
+            let (tickets, total) = store
+                .list_tickets(
+                    &TicketFilter::default(),
+                    order_by,
+                    ListOptions::new(Some(2), Some(1), None)?,
+                )
+                .await?;
+            let ids: Vec<u64> = tickets.iter().map(|t| t.id).collect();
+            assert_eq!((ids, total), (vec![4, 2], 5));
+
+            // -- Filter on a label id (in the labels json of SQLite).
+            let mut ticket = store.get_ticket(3).await?.unwrap();
+            ticket.labels = vec![2, 12];
+            store.update_ticket(ticket, audit_fc(1)).await?;
+            let filter = TicketFilter {
+                label: Some(12),
+                ..Default::default()
+            };
+            let (tickets, total) = store
+                .list_tickets(
+                    &filter,
+                    TicketOrderBy::default(),
+                    ListOptions::new(None, None, None)?,
+                )
+                .await?;
+            let ids: Vec<u64> = tickets.iter().map(|t| t.id).collect();
+            assert_eq!((ids, total), (vec![3], 1));
+        }
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_ticket_version_on_all_stores() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+        let stores: Vec<Arc<dyn Store>> = vec![
+            new_store(&StoreConfig::Memory)?,
+            new_store(&StoreConfig::Sqlite { path: path.clone() })?,
+        ];
+
+        for store in stores {
+            let ticket = store
+                .insert_ticket(new_ticket(1, "t1", Open, TicketPriority::Low), audit_fc(1))
+                .await?;
+            assert_eq!(ticket.version, 1);
+
+            let updated = store
+                .update_ticket(ticket.clone(), audit_fc(1))
+                .await?
+                .unwrap();
+            assert_eq!(updated.version, 2);
+
+            // -- A change made from the old version is rejected.
+            let res = store.update_ticket(ticket, audit_fc(1)).await;
+            assert!(matches!(
+                res,
+                Err(crate::Error::TicketPreconditionFailed { version: 2, .. })
+            ));
+        }
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sessions_on_all_stores() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+        let stores: Vec<Arc<dyn Store>> = vec![
+            new_store(&StoreConfig::Memory)?,
+            new_store(&StoreConfig::Sqlite { path: path.clone() })?,
+        ];
+        let new_session = |user_id: u64, last_seen: OffsetDateTime| Session {
+            id: Uuid::new_v4(),
+            user_id,
+            user_agent: Some("test".to_string()),
+            ip: None,
+            ctime: last_seen,
+            last_seen,
+            current: false,
+        };
+        let now = OffsetDateTime::now_utc();
+
+        for store in stores {
+            let old = store
+                .insert_session(new_session(1, now - time::Duration::hours(2)))
+                .await?;
+            let s1 = store.insert_session(new_session(1, now)).await?;
+            store.insert_session(new_session(2, now)).await?;
+
+            // -- Last seen first.
+            store
+                .touch_session(old.id, now + time::Duration::minutes(1))
+                .await?;
+            let ids: Vec<Uuid> = store.list_sessions(1).await?.iter().map(|s| s.id).collect();
+            assert_eq!(ids, [old.id, s1.id]);
+
+            assert_eq!(
+                store.purge_sessions(now - time::Duration::hours(1)).await?,
+                0
+            );
+            assert_eq!(
+                store.delete_session(old.id).await?.map(|s| s.id),
+                Some(old.id)
+            );
+            assert!(store.get_session(old.id).await?.is_none());
+            assert_eq!(store.delete_user_sessions(1).await?, 1);
+            assert_eq!(
+                store.purge_sessions(now + time::Duration::hours(1)).await?,
+                1
+            );
+        }
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
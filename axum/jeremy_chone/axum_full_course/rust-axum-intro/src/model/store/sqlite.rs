@@ -0,0 +1,1580 @@
+//! SQLite store, with the schema migrations applied on open.
+
+use crate::ctx::Role;
+use crate::model::audit::{AuditAction, AuditEntry, AuditFilter, AuditForCreate};
+use crate::model::comment::Comment;
+use crate::model::idempotency::IdempotencyRecord;
+use crate::model::label::Label;
+use crate::model::session::Session;
+use crate::model::store::Store;
+use crate::model::user::User;
+use crate::model::webhook::{Webhook, WebhookAttempt};
+use crate::model::{
+    ListOptions, Ticket, TicketEventKind, TicketField, TicketFilter, TicketOrderBy, TicketPriority,
+    TicketStatus,
+};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, ErrorCode, OptionalExtension, Row, ToSql};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use time::{OffsetDateTime, UtcOffset};
+use tracing::debug;
+use uuid::Uuid;
+
+// region:    --- Migrations
+
+/// `UPDATE` of the time columns of the table to the `SqlTime` format, from the
+/// `OffsetDateTime` one (`.[1 to 9 digits]Z` fraction) or the `datetime()` one (no fraction).
+macro_rules! fixed_times {
+    ($table:literal, $($col:literal),+) => {
+        concat!(
+            "UPDATE ", $table, " SET rowid = rowid",
+            $(
+                ", ", $col, " = substr(", $col, ", 1, 19) || '.' || substr(",
+                "CASE WHEN substr(", $col, ", 20, 1) = '.' THEN rtrim(substr(", $col, ", 21), 'Z') ELSE '' END",
+                " || '000000000', 1, 9) || 'Z'",
+            )+
+            ";"
+        )
+    };
+}
+
+/// Schema migrations, applied in order.
+/// The index + 1 of the last applied one is kept in `PRAGMA user_version`.
+/// Only append to this list, never edit an already released migration.
+const MIGRATIONS: &[&str] = &[
+    // 1 - Tickets.
+    "CREATE TABLE ticket (
+        id    INTEGER PRIMARY KEY AUTOINCREMENT,
+        cid   INTEGER NOT NULL,
+        title TEXT    NOT NULL
+    );",
+    // 2 - Users.
+    "CREATE TABLE user (
+        id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        username TEXT    NOT NULL UNIQUE,
+        pwd      TEXT    NOT NULL,
+        ctime    TEXT    NOT NULL,
+        mtime    TEXT    NOT NULL
+    );",
+    // 3 - User roles (existing accounts become members).
+    "ALTER TABLE user ADD COLUMN role TEXT NOT NULL DEFAULT 'member';",
+    // 4 - Ticket details (existing tickets get the migration time as ctime/mtime).
+    "ALTER TABLE ticket ADD COLUMN description TEXT;
+     ALTER TABLE ticket ADD COLUMN status   TEXT NOT NULL DEFAULT 'open';
+     ALTER TABLE ticket ADD COLUMN priority TEXT NOT NULL DEFAULT 'medium';
+     ALTER TABLE ticket ADD COLUMN assignee INTEGER REFERENCES user(id);
+     ALTER TABLE ticket ADD COLUMN ctime    TEXT NOT NULL DEFAULT '';
+     ALTER TABLE ticket ADD COLUMN mtime    TEXT NOT NULL DEFAULT '';
+     UPDATE ticket SET ctime = datetime('now'), mtime = datetime('now');",
+    // 5 - Audit trail (append only, `before`/`after` are the ticket json).
+    "CREATE TABLE audit (
+        id        INTEGER PRIMARY KEY AUTOINCREMENT,
+        actor     INTEGER NOT NULL,
+        action    TEXT    NOT NULL,
+        ticket_id INTEGER NOT NULL,
+        before    TEXT,
+        after     TEXT,
+        ctime     TEXT    NOT NULL,
+        req_uuid  TEXT
+     );
+     CREATE INDEX audit_actor ON audit(actor);
+     CREATE INDEX audit_ticket_id ON audit(ticket_id);
+     CREATE TRIGGER audit_no_update BEFORE UPDATE ON audit
+        BEGIN SELECT RAISE(ABORT, 'audit is append only'); END;
+     CREATE TRIGGER audit_no_delete BEFORE DELETE ON audit
+        BEGIN SELECT RAISE(ABORT, 'audit is append only'); END;",
+    // 6 - Ticket trash (soft delete time).
+    "ALTER TABLE ticket ADD COLUMN deleted TEXT;
+     CREATE INDEX ticket_deleted ON ticket(deleted);",
+    // 7 - Ticket version (optimistic concurrency).
+    "ALTER TABLE ticket ADD COLUMN version INTEGER NOT NULL DEFAULT 1;",
+    // 8 - Idempotency keys (`ticket` is the ticket json).
+    "CREATE TABLE idempotency (
+        user_id     INTEGER NOT NULL,
+        key         TEXT    NOT NULL,
+        fingerprint TEXT    NOT NULL,
+        ticket      TEXT    NOT NULL,
+        ctime       TEXT    NOT NULL,
+        PRIMARY KEY (user_id, key)
+     );
+     CREATE INDEX idempotency_ctime ON idempotency(ctime);",
+    // 9 - Labels, and the ticket labels (`labels` is the json array of label ids).
+    "CREATE TABLE label (
+        id    INTEGER PRIMARY KEY AUTOINCREMENT,
+        name  TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+        color TEXT,
+        ctime TEXT    NOT NULL,
+        mtime TEXT    NOT NULL
+     );
+     ALTER TABLE ticket ADD COLUMN labels TEXT NOT NULL DEFAULT '[]';",
+    // 10 - Ticket comments.
+    "CREATE TABLE comment (
+        id        INTEGER PRIMARY KEY AUTOINCREMENT,
+        ticket_id INTEGER NOT NULL,
+        parent_id INTEGER,
+        author    INTEGER NOT NULL,
+        body      TEXT    NOT NULL,
+        ctime     TEXT    NOT NULL,
+        mtime     TEXT    NOT NULL
+     );
+     CREATE INDEX comment_ticket_id ON comment(ticket_id);",
+    // 11 - Outgoing webhooks (`events` is the json array of event kinds), and their attempts.
+    "CREATE TABLE webhook (
+        id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        url      TEXT    NOT NULL,
+        events   TEXT    NOT NULL DEFAULT '[]',
+        secret   TEXT    NOT NULL,
+        active   INTEGER NOT NULL,
+        failures INTEGER NOT NULL DEFAULT 0,
+        ctime    TEXT    NOT NULL,
+        mtime    TEXT    NOT NULL
+     );
+     CREATE TABLE webhook_attempt (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        webhook_id  INTEGER NOT NULL,
+        delivery_id TEXT    NOT NULL,
+        event       TEXT    NOT NULL,
+        ticket_id   INTEGER NOT NULL,
+        attempt     INTEGER NOT NULL,
+        status      INTEGER,
+        error       TEXT,
+        duration_ms INTEGER NOT NULL,
+        ctime       TEXT    NOT NULL
+     );
+     CREATE INDEX webhook_attempt_webhook_id ON webhook_attempt(webhook_id, id);",
+    // 12 - Login sessions (`id` is the uuid in the auth token).
+    "CREATE TABLE session (
+        id         TEXT    PRIMARY KEY,
+        user_id    INTEGER NOT NULL,
+        user_agent TEXT,
+        ip         TEXT,
+        ctime      TEXT    NOT NULL,
+        last_seen  TEXT    NOT NULL
+     );
+     CREATE INDEX session_user_id ON session(user_id);",
+    // 13 - Times to the fixed width format of `SqlTime` (the audit trigger is set back after).
+    concat!(
+        "DROP TRIGGER audit_no_update;",
+        fixed_times!("ticket", "ctime", "mtime", "deleted"),
+        fixed_times!("user", "ctime", "mtime"),
+        fixed_times!("audit", "ctime"),
+        fixed_times!("idempotency", "ctime"),
+        fixed_times!("label", "ctime", "mtime"),
+        fixed_times!("comment", "ctime", "mtime"),
+        fixed_times!("webhook", "ctime", "mtime"),
+        fixed_times!("webhook_attempt", "ctime"),
+        fixed_times!("session", "ctime", "last_seen"),
+        "CREATE TRIGGER audit_no_update BEFORE UPDATE ON audit
+            BEGIN SELECT RAISE(ABORT, 'audit is append only'); END;"
+    ),
+];
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if version > MIGRATIONS.len() {
+        return Err(Error::StoreSchemaTooNew {
+            version,
+            supported: MIGRATIONS.len(),
+        });
+    }
+
+    for (idx, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        debug!("{:<12} - migration {}", "STORE", idx + 1);
+
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", idx + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+// endregion: --- Migrations
+
+/// The SQLite `lower()` only folds ASCII, so the title search uses `rust_lower()`,
+/// the `str::to_lowercase` of the `MemStore`.
+fn add_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "rust_lower",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            Ok(ctx
+                .get::<Option<String>>(0)?
+                .map(|text| text.to_lowercase()))
+        },
+    )?;
+
+    Ok(())
+}
+
+/// The connection is used from the blocking thread pool (see `with_conn`),
+/// so the sqlite calls do not block the async runtime workers.
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+        add_functions(&conn)?;
+        migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` with the connection, on the blocking thread pool.
+    async fn with_conn<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    ) -> Result<T>
+    where
+        T: Send + 'static,
+    {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            // Poisoned by a panic in a previous `f`.
+            let mut conn = conn
+                .lock()
+                .map_err(|_| Error::Store("sqlite connection lock poisoned".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|ex| Error::Store(ex.to_string()))?
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn insert_ticket(&self, ticket: Ticket, audit: AuditForCreate) -> Result<Ticket> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let ticket = insert_ticket_row(&tx, ticket, audit)?;
+            tx.commit()?;
+
+            Ok(ticket)
+        })
+        .await
+    }
+
+    async fn insert_tickets(&self, tickets: Vec<(Ticket, AuditForCreate)>) -> Result<Vec<Ticket>> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let tickets = tickets
+                .into_iter()
+                .map(|(ticket, audit)| insert_ticket_row(&tx, ticket, audit))
+                .collect::<Result<Vec<_>>>()?;
+            tx.commit()?;
+
+            Ok(tickets)
+        })
+        .await
+    }
+
+    async fn get_ticket(&self, id: u64) -> Result<Option<Ticket>> {
+        self.with_conn(move |conn| select_ticket(conn, id)).await
+    }
+
+    async fn list_tickets(
+        &self,
+        filter: &TicketFilter,
+        order_by: TicketOrderBy,
+        list_options: ListOptions,
+    ) -> Result<(Vec<Ticket>, u64)> {
+        let filter = filter.clone();
+
+        self.with_conn(move |conn| {
+            let (where_sql, where_params) = ticket_where(&filter);
+
+            let total: u64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM ticket {where_sql}"),
+                params_from_iter(&where_params),
+                |row| row.get(0),
+            )?;
+
+            let order_sql = ticket_order_by(order_by);
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {TICKET_COLUMNS} FROM ticket {where_sql} {order_sql} LIMIT {} OFFSET {}",
+                list_options.limit, list_options.offset
+            ))?;
+            let tickets = stmt
+                .query_map(params_from_iter(&where_params), ticket_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok((tickets, total))
+        })
+        .await
+    }
+
+    async fn update_ticket(&self, ticket: Ticket, audit: AuditForCreate) -> Result<Option<Ticket>> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let Some(before) = select_ticket(&tx, ticket.id)? else {
+                return Ok(None);
+            };
+            if before.version != ticket.version {
+                return Err(Error::TicketPreconditionFailed {
+                    id: ticket.id,
+                    version: before.version,
+                });
+            }
+            let ticket = tx.query_row(
+                &format!(
+                    "UPDATE ticket SET title = ?2, description = ?3, status = ?4, priority = ?5,
+                                           assignee = ?6, mtime = ?7, deleted = ?8, labels = ?9,
+                                           version = version + 1
+                         WHERE id = ?1
+                         RETURNING {TICKET_COLUMNS}"
+                ),
+                params![
+                    ticket.id,
+                    ticket.title,
+                    ticket.description,
+                    ticket.status,
+                    ticket.priority,
+                    ticket.assignee,
+                    SqlTime(ticket.mtime),
+                    ticket.deleted.map(SqlTime),
+                    labels_json(&ticket.labels)?,
+                ],
+                ticket_from_row,
+            )?;
+            insert_audit(&tx, audit, ticket.id, Some(&before), Some(&ticket))?;
+            tx.commit()?;
+
+            Ok(Some(ticket))
+        })
+        .await
+    }
+
+    async fn purge_tickets(
+        &self,
+        deleted_before: OffsetDateTime,
+        audit: AuditForCreate,
+    ) -> Result<Vec<Ticket>> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let purged = tx
+                .prepare(&format!(
+                    "DELETE FROM ticket WHERE deleted IS NOT NULL AND deleted < ?1
+                     RETURNING {TICKET_COLUMNS}"
+                ))?
+                .query_map([SqlTime(deleted_before)], ticket_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for ticket in &purged {
+                insert_audit(&tx, audit.clone(), ticket.id, Some(ticket), None)?;
+                tx.execute("DELETE FROM comment WHERE ticket_id = ?1", [ticket.id])?;
+            }
+            tx.commit()?;
+
+            Ok(purged)
+        })
+        .await
+    }
+
+    async fn count_tickets(&self) -> Result<u64> {
+        self.with_conn(move |conn| {
+            let count = conn.query_row("SELECT COUNT(*) FROM ticket", [], |row| row.get(0))?;
+
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn list_audit(
+        &self,
+        filter: &AuditFilter,
+        list_options: ListOptions,
+    ) -> Result<(Vec<AuditEntry>, u64)> {
+        let filter = filter.clone();
+
+        self.with_conn(move |conn| {
+            let (where_sql, where_params) = audit_where(&filter);
+
+            let total: u64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM audit {where_sql}"),
+                params_from_iter(&where_params),
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {AUDIT_COLUMNS} FROM audit {where_sql} ORDER BY id LIMIT {} OFFSET {}",
+                list_options.limit, list_options.offset
+            ))?;
+            let entries = stmt
+                .query_map(params_from_iter(&where_params), audit_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok((entries, total))
+        })
+        .await
+    }
+
+    async fn insert_label(&self, label: Label) -> Result<Label> {
+        self.with_conn(move |conn| {
+            let res = conn.query_row(
+                &format!(
+                    "INSERT INTO label (name, color, ctime, mtime) VALUES (?1, ?2, ?3, ?4)
+                     RETURNING {LABEL_COLUMNS}"
+                ),
+                params![
+                    label.name,
+                    label.color,
+                    SqlTime(label.ctime),
+                    SqlTime(label.mtime)
+                ],
+                label_from_row,
+            );
+
+            label_name_checked(res, label.name)
+        })
+        .await
+    }
+
+    async fn get_label(&self, id: u64) -> Result<Option<Label>> {
+        self.with_conn(move |conn| {
+            let label = conn
+                .query_row(
+                    &format!("SELECT {LABEL_COLUMNS} FROM label WHERE id = ?1"),
+                    [id],
+                    label_from_row,
+                )
+                .optional()?;
+
+            Ok(label)
+        })
+        .await
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>> {
+        self.with_conn(move |conn| {
+            let labels = conn
+                .prepare(&format!(
+                    "SELECT {LABEL_COLUMNS} FROM label ORDER BY name, id"
+                ))?
+                .query_map([], label_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(labels)
+        })
+        .await
+    }
+
+    async fn update_label(&self, label: Label) -> Result<Option<Label>> {
+        self.with_conn(move |conn| {
+            let res = conn
+                .query_row(
+                    &format!(
+                        "UPDATE label SET name = ?2, color = ?3, mtime = ?4 WHERE id = ?1
+                         RETURNING {LABEL_COLUMNS}"
+                    ),
+                    params![label.id, label.name, label.color, SqlTime(label.mtime)],
+                    label_from_row,
+                )
+                .optional();
+
+            label_name_checked(res, label.name)
+        })
+        .await
+    }
+
+    async fn delete_label(&self, id: u64) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM label WHERE id = ?1", [id])?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn insert_comment(&self, comment: Comment) -> Result<Comment> {
+        self.with_conn(move |conn| {
+            let comment = conn.query_row(
+                &format!(
+                    "INSERT INTO comment (ticket_id, parent_id, author, body, ctime, mtime)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     RETURNING {COMMENT_COLUMNS}"
+                ),
+                params![
+                    comment.ticket_id,
+                    comment.parent_id,
+                    comment.author,
+                    comment.body,
+                    SqlTime(comment.ctime),
+                    SqlTime(comment.mtime),
+                ],
+                comment_from_row,
+            )?;
+
+            Ok(comment)
+        })
+        .await
+    }
+
+    async fn get_comment(&self, id: u64) -> Result<Option<Comment>> {
+        self.with_conn(move |conn| {
+            let comment = conn
+                .query_row(
+                    &format!("SELECT {COMMENT_COLUMNS} FROM comment WHERE id = ?1"),
+                    [id],
+                    comment_from_row,
+                )
+                .optional()?;
+
+            Ok(comment)
+        })
+        .await
+    }
+
+    async fn list_comments(&self, ticket_id: u64) -> Result<Vec<Comment>> {
+        self.with_conn(move |conn| {
+            let comments = conn
+                .prepare(&format!(
+                    "SELECT {COMMENT_COLUMNS} FROM comment WHERE ticket_id = ?1 ORDER BY id"
+                ))?
+                .query_map([ticket_id], comment_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(comments)
+        })
+        .await
+    }
+
+    async fn update_comment(&self, comment: Comment) -> Result<Option<Comment>> {
+        self.with_conn(move |conn| {
+            let comment = conn
+                .query_row(
+                    &format!(
+                        "UPDATE comment SET body = ?2, mtime = ?3 WHERE id = ?1
+                         RETURNING {COMMENT_COLUMNS}"
+                    ),
+                    params![comment.id, comment.body, SqlTime(comment.mtime)],
+                    comment_from_row,
+                )
+                .optional()?;
+
+            Ok(comment)
+        })
+        .await
+    }
+
+    async fn delete_comments(&self, ids: &[u64]) -> Result<u64> {
+        let ids = ids.to_vec();
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            let mut count = 0;
+            for id in ids {
+                count += tx.execute("DELETE FROM comment WHERE id = ?1", [id])? as u64;
+            }
+            tx.commit()?;
+
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn insert_webhook(&self, webhook: Webhook) -> Result<Webhook> {
+        self.with_conn(move |conn| {
+            let webhook = conn.query_row(
+                &format!(
+                    "INSERT INTO webhook (url, events, secret, active, failures, ctime, mtime)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     RETURNING {WEBHOOK_COLUMNS}"
+                ),
+                params![
+                    webhook.url,
+                    events_json(&webhook.events)?,
+                    webhook.secret,
+                    webhook.active,
+                    webhook.failures,
+                    SqlTime(webhook.ctime),
+                    SqlTime(webhook.mtime),
+                ],
+                webhook_from_row,
+            )?;
+
+            Ok(webhook)
+        })
+        .await
+    }
+
+    async fn get_webhook(&self, id: u64) -> Result<Option<Webhook>> {
+        self.with_conn(move |conn| {
+            let webhook = conn
+                .query_row(
+                    &format!("SELECT {WEBHOOK_COLUMNS} FROM webhook WHERE id = ?1"),
+                    [id],
+                    webhook_from_row,
+                )
+                .optional()?;
+
+            Ok(webhook)
+        })
+        .await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.with_conn(move |conn| {
+            let webhooks = conn
+                .prepare(&format!(
+                    "SELECT {WEBHOOK_COLUMNS} FROM webhook ORDER BY id"
+                ))?
+                .query_map([], webhook_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(webhooks)
+        })
+        .await
+    }
+
+    async fn update_webhook(&self, webhook: Webhook) -> Result<Option<Webhook>> {
+        self.with_conn(move |conn| {
+            let webhook = conn
+                .query_row(
+                    &format!(
+                        "UPDATE webhook SET url = ?2, events = ?3, active = ?4, failures = ?5, mtime = ?6
+                         WHERE id = ?1
+                         RETURNING {WEBHOOK_COLUMNS}"
+                    ),
+                    params![
+                        webhook.id,
+                        webhook.url,
+                        events_json(&webhook.events)?,
+                        webhook.active,
+                        webhook.failures,
+                        SqlTime(webhook.mtime),
+                    ],
+                    webhook_from_row,
+                )
+                .optional()?;
+
+            Ok(webhook)
+        })
+        .await
+    }
+
+    async fn delete_webhook(&self, id: u64) -> Result<()> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            tx.execute("DELETE FROM webhook_attempt WHERE webhook_id = ?1", [id])?;
+            tx.execute("DELETE FROM webhook WHERE id = ?1", [id])?;
+            tx.commit()?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_webhook_delivery(
+        &self,
+        id: u64,
+        delivered: bool,
+        disable_after: u32,
+    ) -> Result<Option<Webhook>> {
+        self.with_conn(move |conn| {
+            let webhook = conn
+                .query_row(
+                    &format!(
+                        "UPDATE webhook SET
+                            failures = CASE WHEN ?2 THEN 0 ELSE failures + 1 END,
+                            active = CASE WHEN NOT ?2 AND failures + 1 >= ?3 THEN 0 ELSE active END
+                         WHERE id = ?1
+                         RETURNING {WEBHOOK_COLUMNS}"
+                    ),
+                    params![id, delivered, disable_after],
+                    webhook_from_row,
+                )
+                .optional()?;
+
+            Ok(webhook)
+        })
+        .await
+    }
+
+    async fn insert_webhook_attempt(&self, attempt: WebhookAttempt) -> Result<WebhookAttempt> {
+        self.with_conn(move |conn| {
+            let attempt = conn.query_row(
+                &format!(
+                    "INSERT INTO webhook_attempt
+                        (webhook_id, delivery_id, event, ticket_id, attempt, status, error, duration_ms, ctime)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                     RETURNING {WEBHOOK_ATTEMPT_COLUMNS}"
+                ),
+                params![
+                    attempt.webhook_id,
+                    attempt.delivery_id.to_string(),
+                    attempt.event,
+                    attempt.ticket_id,
+                    attempt.attempt,
+                    attempt.status,
+                    attempt.error,
+                    attempt.duration_ms,
+                    SqlTime(attempt.ctime),
+                ],
+                webhook_attempt_from_row,
+            )?;
+
+            Ok(attempt)
+        })
+        .await
+    }
+
+    async fn list_webhook_attempts(
+        &self,
+        webhook_id: u64,
+        limit: u64,
+    ) -> Result<Vec<WebhookAttempt>> {
+        self.with_conn(move |conn| {
+            let attempts = conn
+                .prepare(&format!(
+                    "SELECT {WEBHOOK_ATTEMPT_COLUMNS} FROM webhook_attempt
+                     WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2"
+                ))?
+                .query_map(params![webhook_id, limit], webhook_attempt_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(attempts)
+        })
+        .await
+    }
+
+    async fn get_idempotency(&self, user_id: u64, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let key = key.to_string();
+
+        self.with_conn(move |conn| {
+            let record = conn
+                .query_row(
+                    "SELECT user_id, key, fingerprint, ticket, ctime FROM idempotency
+                     WHERE user_id = ?1 AND key = ?2",
+                    params![user_id, key],
+                    idempotency_from_row,
+                )
+                .optional()?;
+
+            Ok(record)
+        })
+        .await
+    }
+
+    async fn put_idempotency(&self, record: IdempotencyRecord) -> Result<()> {
+        self.with_conn(move |conn| {
+            let ticket =
+                serde_json::to_string(&record.ticket).map_err(|ex| Error::Store(ex.to_string()))?;
+            conn.execute(
+                "INSERT OR REPLACE INTO idempotency (user_id, key, fingerprint, ticket, ctime)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    record.user_id,
+                    record.key,
+                    record.fingerprint,
+                    ticket,
+                    SqlTime(record.ctime),
+                ],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn purge_idempotency(&self, ctime_before: OffsetDateTime) -> Result<u64> {
+        self.with_conn(move |conn| {
+            let count = conn.execute(
+                "DELETE FROM idempotency WHERE ctime < ?1",
+                [SqlTime(ctime_before)],
+            )?;
+
+            Ok(count as u64)
+        })
+        .await
+    }
+
+    async fn insert_user(
+        &self,
+        username: String,
+        pwd: String,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<User> {
+        self.with_conn(move |conn| {
+            let res = conn.query_row(
+                &format!(
+                    "INSERT INTO user (username, pwd, role, ctime, mtime) VALUES (?1, ?2, ?3, ?4, ?4)
+                     RETURNING {USER_COLUMNS}"
+                ),
+                params![username, pwd, role, SqlTime(now)],
+                user_from_row,
+            );
+
+            match res {
+                Err(rusqlite::Error::SqliteFailure(ex, _))
+                    if ex.code == ErrorCode::ConstraintViolation =>
+                {
+                    Err(Error::RegisterFailUsernameExists { username })
+                }
+                res => Ok(res?),
+            }
+        })
+        .await
+    }
+
+    async fn get_user(&self, id: u64) -> Result<Option<User>> {
+        self.with_conn(move |conn| {
+            let user = conn
+                .query_row(
+                    &format!("SELECT {USER_COLUMNS} FROM user WHERE id = ?1"),
+                    [id],
+                    user_from_row,
+                )
+                .optional()?;
+
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        let username = username.to_string();
+
+        self.with_conn(move |conn| {
+            let user = conn
+                .query_row(
+                    &format!("SELECT {USER_COLUMNS} FROM user WHERE username = ?1"),
+                    [username],
+                    user_from_row,
+                )
+                .optional()?;
+
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn count_users(&self) -> Result<u64> {
+        self.with_conn(move |conn| {
+            let count = conn.query_row("SELECT COUNT(*) FROM user", [], |row| row.get(0))?;
+
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn update_user_pwd(&self, id: u64, pwd: String, now: OffsetDateTime) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "UPDATE user SET pwd = ?2, mtime = ?3 WHERE id = ?1",
+                params![id, pwd, SqlTime(now)],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_user_role(
+        &self,
+        id: u64,
+        role: Role,
+        now: OffsetDateTime,
+    ) -> Result<Option<User>> {
+        self.with_conn(move |conn| {
+            let user = conn
+                .query_row(
+                    &format!(
+                        "UPDATE user SET role = ?2, mtime = ?3 WHERE id = ?1 RETURNING {USER_COLUMNS}"
+                    ),
+                    params![id, role, SqlTime(now)],
+                    user_from_row,
+                )
+                .optional()?;
+
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn insert_session(&self, session: Session) -> Result<Session> {
+        self.with_conn(move |conn| {
+            let session = conn.query_row(
+                &format!(
+                    "INSERT INTO session (id, user_id, user_agent, ip, ctime, last_seen)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     RETURNING {SESSION_COLUMNS}"
+                ),
+                params![
+                    session.id.to_string(),
+                    session.user_id,
+                    session.user_agent,
+                    session.ip,
+                    SqlTime(session.ctime),
+                    SqlTime(session.last_seen),
+                ],
+                session_from_row,
+            )?;
+
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn get_session(&self, id: Uuid) -> Result<Option<Session>> {
+        self.with_conn(move |conn| {
+            let session = conn
+                .query_row(
+                    &format!("SELECT {SESSION_COLUMNS} FROM session WHERE id = ?1"),
+                    [id.to_string()],
+                    session_from_row,
+                )
+                .optional()?;
+
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn list_sessions(&self, user_id: u64) -> Result<Vec<Session>> {
+        self.with_conn(move |conn| {
+            let sessions = conn
+                .prepare(&format!(
+                    "SELECT {SESSION_COLUMNS} FROM session
+                     WHERE user_id = ?1 ORDER BY last_seen DESC"
+                ))?
+                .query_map([user_id], session_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn touch_session(&self, id: Uuid, last_seen: OffsetDateTime) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "UPDATE session SET last_seen = ?2 WHERE id = ?1",
+                params![id.to_string(), SqlTime(last_seen)],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_session(&self, id: Uuid) -> Result<Option<Session>> {
+        self.with_conn(move |conn| {
+            let session = conn
+                .query_row(
+                    &format!("DELETE FROM session WHERE id = ?1 RETURNING {SESSION_COLUMNS}"),
+                    [id.to_string()],
+                    session_from_row,
+                )
+                .optional()?;
+
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn delete_user_sessions(&self, user_id: u64) -> Result<u64> {
+        self.with_conn(move |conn| {
+            let count = conn.execute("DELETE FROM session WHERE user_id = ?1", [user_id])?;
+
+            Ok(count as u64)
+        })
+        .await
+    }
+
+    async fn purge_sessions(&self, last_seen_before: OffsetDateTime) -> Result<u64> {
+        self.with_conn(move |conn| {
+            let count = conn.execute(
+                "DELETE FROM session WHERE last_seen < ?1",
+                [SqlTime(last_seen_before)],
+            )?;
+
+            Ok(count as u64)
+        })
+        .await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.query_row("SELECT COUNT(*) FROM user", [], |row| row.get::<_, u64>(0))?;
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+// region:    --- Ticket Query Builders
+
+fn ticket_where(filter: &TicketFilter) -> (String, Vec<Box<dyn ToSql>>) {
+    let mut conds: Vec<&str> = Vec::new();
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if filter.trashed {
+        conds.push("deleted IS NOT NULL");
+    } else {
+        conds.push("deleted IS NULL");
+    }
+    if let Some(cid) = filter.cid {
+        conds.push("cid = ?");
+        params.push(Box::new(cid));
+    }
+    if let Some(assignee) = filter.assignee {
+        conds.push("assignee = ?");
+        params.push(Box::new(assignee));
+    }
+    if let Some(status) = filter.status {
+        conds.push("status = ?");
+        params.push(Box::new(status));
+    }
+    if let Some(priority) = filter.priority {
+        conds.push("priority = ?");
+        params.push(Box::new(priority));
+    }
+    if let Some(title_contains) = &filter.title_contains {
+        conds.push("instr(rust_lower(title), rust_lower(?)) > 0");
+        params.push(Box::new(title_contains.clone()));
+    }
+    if let Some(label) = filter.label {
+        conds.push("EXISTS (SELECT 1 FROM json_each(labels) WHERE value = ?)");
+        params.push(Box::new(label));
+    }
+
+    if conds.is_empty() {
+        (String::new(), params)
+    } else {
+        (format!("WHERE {}", conds.join(" AND ")), params)
+    }
+}
+
+fn ticket_order_by(order_by: TicketOrderBy) -> String {
+    // Status and priority sort by rank (as their Rust enum), not by name.
+    let expr = match order_by.field {
+        TicketField::Status => {
+            "CASE status WHEN 'open' THEN 0 WHEN 'in_progress' THEN 1 ELSE 2 END".to_string()
+        }
+        TicketField::Priority => {
+            "CASE priority WHEN 'low' THEN 0 WHEN 'medium' THEN 1 ELSE 2 END".to_string()
+        }
+        // Other fields are named as their column.
+        field => field.as_ref().to_string(),
+    };
+    let dir = if order_by.desc { "DESC" } else { "ASC" };
+
+    format!("ORDER BY {expr} {dir}, id ASC")
+}
+
+// endregion: --- Ticket Query Builders
+
+// region:    --- Row Mappings
+
+const TICKET_COLUMNS: &str =
+    "id, cid, title, description, status, priority, assignee, ctime, mtime, deleted, version, labels";
+
+fn select_ticket(conn: &Connection, id: u64) -> Result<Option<Ticket>> {
+    let ticket = conn
+        .query_row(
+            &format!("SELECT {TICKET_COLUMNS} FROM ticket WHERE id = ?1"),
+            [id],
+            ticket_from_row,
+        )
+        .optional()?;
+
+    Ok(ticket)
+}
+
+/// Insert the ticket (with a new id) and its audit entry.
+fn insert_ticket_row(conn: &Connection, ticket: Ticket, audit: AuditForCreate) -> Result<Ticket> {
+    let ticket = conn.query_row(
+        &format!(
+            "INSERT INTO ticket (cid, title, description, status, priority, assignee, ctime, mtime, deleted, version, labels)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             RETURNING {TICKET_COLUMNS}"
+        ),
+        params![
+            ticket.cid,
+            ticket.title,
+            ticket.description,
+            ticket.status,
+            ticket.priority,
+            ticket.assignee,
+            SqlTime(ticket.ctime),
+            SqlTime(ticket.mtime),
+            ticket.deleted.map(SqlTime),
+            ticket.version,
+            labels_json(&ticket.labels)?,
+        ],
+        ticket_from_row,
+    )?;
+    insert_audit(conn, audit, ticket.id, None, Some(&ticket))?;
+
+    Ok(ticket)
+}
+
+const AUDIT_COLUMNS: &str = "id, actor, action, ticket_id, before, after, ctime, req_uuid";
+
+fn insert_audit(
+    conn: &Connection,
+    audit: AuditForCreate,
+    ticket_id: u64,
+    before: Option<&Ticket>,
+    after: Option<&Ticket>,
+) -> Result<()> {
+    let to_json = |ticket: Option<&Ticket>| {
+        ticket
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|ex| Error::Store(ex.to_string()))
+    };
+
+    conn.execute(
+        "INSERT INTO audit (actor, action, ticket_id, before, after, ctime, req_uuid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            audit.actor,
+            audit.action,
+            ticket_id,
+            to_json(before)?,
+            to_json(after)?,
+            SqlTime(audit.ctime),
+            audit.req_uuid.map(|uuid| uuid.to_string()),
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn audit_from_row(row: &Row) -> rusqlite::Result<AuditEntry> {
+    let from_json = |idx: &str| -> rusqlite::Result<Option<Ticket>> {
+        row.get::<_, Option<String>>(idx)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|ex| FromSqlError::Other(Box::new(ex)).into())
+    };
+    let req_uuid = row
+        .get::<_, Option<String>>("req_uuid")?
+        .map(|uuid| Uuid::parse_str(&uuid))
+        .transpose()
+        .map_err(|ex| rusqlite::Error::from(FromSqlError::Other(Box::new(ex))))?;
+
+    Ok(AuditEntry {
+        id: row.get("id")?,
+        actor: row.get("actor")?,
+        action: row.get("action")?,
+        ticket_id: row.get("ticket_id")?,
+        before: from_json("before")?,
+        after: from_json("after")?,
+        ctime: row.get("ctime")?,
+        req_uuid,
+    })
+}
+
+fn idempotency_from_row(row: &Row) -> rusqlite::Result<IdempotencyRecord> {
+    let ticket = serde_json::from_str(&row.get::<_, String>("ticket")?)
+        .map_err(|ex| FromSqlError::Other(Box::new(ex)))?;
+
+    Ok(IdempotencyRecord {
+        user_id: row.get("user_id")?,
+        key: row.get("key")?,
+        fingerprint: row.get("fingerprint")?,
+        ticket,
+        ctime: row.get("ctime")?,
+    })
+}
+
+fn audit_where(filter: &AuditFilter) -> (String, Vec<Box<dyn ToSql>>) {
+    let mut conds: Vec<&str> = Vec::new();
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if let Some(actor) = filter.actor {
+        conds.push("actor = ?");
+        params.push(Box::new(actor));
+    }
+    if let Some(ticket_id) = filter.ticket_id {
+        conds.push("ticket_id = ?");
+        params.push(Box::new(ticket_id));
+    }
+    if let Some(from) = filter.from {
+        conds.push("ctime >= ?");
+        params.push(Box::new(SqlTime(from)));
+    }
+    if let Some(to) = filter.to {
+        conds.push("ctime < ?");
+        params.push(Box::new(SqlTime(to)));
+    }
+
+    if conds.is_empty() {
+        (String::new(), params)
+    } else {
+        (format!("WHERE {}", conds.join(" AND ")), params)
+    }
+}
+
+const USER_COLUMNS: &str = "id, username, pwd, role, ctime, mtime";
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get("id")?,
+        username: row.get("username")?,
+        pwd: row.get("pwd")?,
+        role: row.get("role")?,
+        ctime: row.get("ctime")?,
+        mtime: row.get("mtime")?,
+    })
+}
+
+fn ticket_from_row(row: &Row) -> rusqlite::Result<Ticket> {
+    Ok(Ticket {
+        id: row.get("id")?,
+        cid: row.get("cid")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        status: row.get("status")?,
+        priority: row.get("priority")?,
+        assignee: row.get("assignee")?,
+        ctime: row.get("ctime")?,
+        mtime: row.get("mtime")?,
+        deleted: row.get("deleted")?,
+        version: row.get("version")?,
+        labels: serde_json::from_str(&row.get::<_, String>("labels")?)
+            .map_err(|ex| FromSqlError::Other(Box::new(ex)))?,
+    })
+}
+
+fn labels_json(labels: &[u64]) -> Result<String> {
+    serde_json::to_string(labels).map_err(|ex| Error::Store(ex.to_string()))
+}
+
+const LABEL_COLUMNS: &str = "id, name, color, ctime, mtime";
+
+fn label_from_row(row: &Row) -> rusqlite::Result<Label> {
+    Ok(Label {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        color: row.get("color")?,
+        ctime: row.get("ctime")?,
+        mtime: row.get("mtime")?,
+    })
+}
+
+/// Maps the unique name violation to `LabelNameTaken`.
+fn label_name_checked<T>(res: rusqlite::Result<T>, name: String) -> Result<T> {
+    match res {
+        Err(rusqlite::Error::SqliteFailure(ex, _)) if ex.code == ErrorCode::ConstraintViolation => {
+            Err(Error::LabelNameTaken { name })
+        }
+        res => Ok(res?),
+    }
+}
+
+const COMMENT_COLUMNS: &str = "id, ticket_id, parent_id, author, body, ctime, mtime";
+
+fn comment_from_row(row: &Row) -> rusqlite::Result<Comment> {
+    Ok(Comment {
+        id: row.get("id")?,
+        ticket_id: row.get("ticket_id")?,
+        parent_id: row.get("parent_id")?,
+        author: row.get("author")?,
+        body: row.get("body")?,
+        ctime: row.get("ctime")?,
+        mtime: row.get("mtime")?,
+    })
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, events, secret, active, failures, ctime, mtime";
+
+fn webhook_from_row(row: &Row) -> rusqlite::Result<Webhook> {
+    Ok(Webhook {
+        id: row.get("id")?,
+        url: row.get("url")?,
+        events: serde_json::from_str(&row.get::<_, String>("events")?)
+            .map_err(|ex| FromSqlError::Other(Box::new(ex)))?,
+        secret: row.get("secret")?,
+        active: row.get("active")?,
+        failures: row.get("failures")?,
+        ctime: row.get("ctime")?,
+        mtime: row.get("mtime")?,
+    })
+}
+
+fn events_json(events: &[TicketEventKind]) -> Result<String> {
+    serde_json::to_string(events).map_err(|ex| Error::Store(ex.to_string()))
+}
+
+const WEBHOOK_ATTEMPT_COLUMNS: &str =
+    "id, webhook_id, delivery_id, event, ticket_id, attempt, status, error, duration_ms, ctime";
+
+fn webhook_attempt_from_row(row: &Row) -> rusqlite::Result<WebhookAttempt> {
+    let delivery_id = Uuid::parse_str(&row.get::<_, String>("delivery_id")?)
+        .map_err(|ex| FromSqlError::Other(Box::new(ex)))?;
+
+    Ok(WebhookAttempt {
+        id: row.get("id")?,
+        webhook_id: row.get("webhook_id")?,
+        delivery_id,
+        event: row.get("event")?,
+        ticket_id: row.get("ticket_id")?,
+        attempt: row.get("attempt")?,
+        status: row.get("status")?,
+        error: row.get("error")?,
+        duration_ms: row.get("duration_ms")?,
+        ctime: row.get("ctime")?,
+    })
+}
+
+const SESSION_COLUMNS: &str = "id, user_id, user_agent, ip, ctime, last_seen";
+
+fn session_from_row(row: &Row) -> rusqlite::Result<Session> {
+    let id = Uuid::parse_str(&row.get::<_, String>("id")?)
+        .map_err(|ex| FromSqlError::Other(Box::new(ex)))?;
+
+    Ok(Session {
+        id,
+        user_id: row.get("user_id")?,
+        user_agent: row.get("user_agent")?,
+        ip: row.get("ip")?,
+        ctime: row.get("ctime")?,
+        last_seen: row.get("last_seen")?,
+        current: false,
+    })
+}
+
+/// A time bound as `YYYY-MM-DD HH:MM:SS.nnnnnnnnnZ`, always in UTC with 9 fraction digits.
+///
+/// Being fixed width, the stored times compare (and sort) as text, e.g., in the purges,
+/// the audit filter, and the ticket order.
+/// Note: Not the `ToSql` of `OffsetDateTime`, with a fraction of variable length
+///       (`.0Z` > `.0123Z` as text). Both are read back with its `FromSql`.
+struct SqlTime(OffsetDateTime);
+
+impl ToSql for SqlTime {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let time = self.0.to_offset(UtcOffset::UTC);
+
+        Ok(ToSqlOutput::from(format!(
+            "{} {:02}:{:02}:{:02}.{:09}Z",
+            time.date(),
+            time.hour(),
+            time.minute(),
+            time.second(),
+            time.nanosecond()
+        )))
+    }
+}
+
+/// Enums stored as their snake_case name (see their `strum` derives).
+macro_rules! impl_sql_for_str_enum {
+    ($($enum:ty),+) => {
+        $(
+            impl ToSql for $enum {
+                fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                    Ok(ToSqlOutput::from(self.as_ref()))
+                }
+            }
+
+            impl FromSql for $enum {
+                fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                    value
+                        .as_str()?
+                        .parse()
+                        .map_err(|ex| FromSqlError::Other(Box::new(ex)))
+                }
+            }
+        )+
+    };
+}
+
+impl_sql_for_str_enum!(
+    Role,
+    TicketStatus,
+    TicketPriority,
+    AuditAction,
+    TicketEventKind
+);
+
+// endregion: --- Row Mappings
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::store::tests::new_ticket;
+    use crate::model::TicketPriority::Medium;
+    use crate::model::TicketStatus::Open;
+
+    fn audit_fc(action: AuditAction) -> AuditForCreate {
+        AuditForCreate {
+            actor: 1,
+            action,
+            ctime: OffsetDateTime::now_utc(),
+            req_uuid: Some(Uuid::new_v4()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_tickets_survive_reopen() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+
+        let store = SqliteStore::open(&path)?;
+        let create = || audit_fc(AuditAction::TicketCreate);
+        let t1 = store
+            .insert_ticket(new_ticket(1, "t1", Open, Medium), create())
+            .await?;
+        let t2 = store
+            .insert_ticket(new_ticket(1, "t2", Open, Medium), create())
+            .await?;
+        let mut t1_deleted = t1.clone();
+        t1_deleted.deleted = Some(OffsetDateTime::now_utc());
+        store
+            .update_ticket(t1_deleted, audit_fc(AuditAction::TicketDelete))
+            .await?;
+        let purged = store
+            .purge_tickets(
+                OffsetDateTime::now_utc(),
+                audit_fc(AuditAction::TicketPurge),
+            )
+            .await?;
+        assert_eq!(purged.len(), 1);
+        drop(store);
+
+        // Reopening must not re-run the migrations nor lose data.
+        let store = SqliteStore::open(&path)?;
+        let (tickets, _) = store
+            .list_tickets(
+                &TicketFilter::default(),
+                TicketOrderBy::default(),
+                ListOptions::new(None, None, None)?,
+            )
+            .await?;
+        assert_eq!(tickets.len(), 1);
+        assert_eq!(tickets[0].id, t2.id);
+
+        // Deleted ids are never handed out again.
+        let t3 = store
+            .insert_ticket(new_ticket(1, "t3", Open, Medium), create())
+            .await?;
+        assert!(t3.id > t2.id);
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_audit_append_only() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteStore::open(&path)?;
+
+        let audit = audit_fc(AuditAction::TicketCreate);
+        let req_uuid = audit.req_uuid;
+        let ticket = store
+            .insert_ticket(new_ticket(1, "t1", Open, Medium), audit)
+            .await?;
+        let mut ticket_update = ticket.clone();
+        ticket_update.title = "t1 renamed".to_string();
+        store
+            .update_ticket(ticket_update, audit_fc(AuditAction::TicketUpdate))
+            .await?;
+
+        let (entries, total) = store
+            .list_audit(
+                &AuditFilter {
+                    ticket_id: Some(ticket.id),
+                    ..Default::default()
+                },
+                ListOptions::new(None, None, None)?,
+            )
+            .await?;
+        assert_eq!(total, 2);
+        assert_eq!(entries[0].req_uuid, req_uuid);
+        assert!(entries[0].before.is_none());
+        let before = entries[1].before.as_ref().map(|t| t.title.as_str());
+        let after = entries[1].after.as_ref().map(|t| t.title.as_str());
+        assert_eq!((before, after), (Some("t1"), Some("t1 renamed")));
+
+        // -- Entries can't be changed, even directly in the db.
+        let conn = store.conn.lock().unwrap();
+        assert!(conn.execute("DELETE FROM audit", []).is_err());
+        assert!(conn.execute("UPDATE audit SET actor = 2", []).is_err());
+        drop(conn);
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqlite_times_fixed_width() -> Result<()> {
+        // -- A db at migration 12, with the times of `OffsetDateTime` and of `datetime()`.
+        let mut conn = Connection::open_in_memory()?;
+        for sql in &MIGRATIONS[..12] {
+            conn.execute_batch(sql)?;
+        }
+        conn.pragma_update(None, "user_version", 12)?;
+        conn.execute(
+            "INSERT INTO ticket (cid, title, ctime, mtime, deleted)
+             VALUES (1, 't1', '2024-01-01 12:00:00', '2024-01-01 12:00:00.0Z', '2024-01-01 12:00:00.0123Z')",
+            [],
+        )?;
+
+        migrate(&mut conn)?;
+        let times: [String; 3] =
+            conn.query_row("SELECT ctime, mtime, deleted FROM ticket", [], |row| {
+                Ok([row.get(0)?, row.get(1)?, row.get(2)?])
+            })?;
+        assert_eq!(
+            times,
+            [
+                "2024-01-01 12:00:00.000000000Z",
+                "2024-01-01 12:00:00.000000000Z",
+                "2024-01-01 12:00:00.012300000Z",
+            ]
+        );
+
+        // -- Written the same way, and read back.
+        let time = OffsetDateTime::from_unix_timestamp_nanos(1_704_110_400_012_300_000).unwrap();
+        let (text, read): (String, OffsetDateTime) =
+            conn.query_row("SELECT ?1, ?1", [SqlTime(time)], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+        assert_eq!(text, times[2]);
+        assert_eq!(read, time);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_poisoned_lock_is_store_error() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("tickets-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteStore::open(&path)?;
+
+        let res = store
+            .with_conn(|_| -> Result<()> { panic!("query panicked") })
+            .await;
+        assert!(matches!(res, Err(Error::Store(_))));
+
+        // The next queries fail, rather than panic.
+        assert!(matches!(store.count_tickets().await, Err(Error::Store(_))));
+
+        std::fs::remove_file(&path).ok();
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,574 @@
+//! Tickets CRUD, with the per role access control.
+
+use crate::ctx::{Ctx, Role};
+use crate::model::audit::{AuditAction, AuditForCreate};
+use crate::model::{ListOptions, ModelController, TicketEventKind};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+
+// region: --- Ticket Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Ticket {
+    pub id: u64,
+    pub cid: u64, // creator user_id
+    pub title: String,
+    pub description: Option<String>,
+    pub status: TicketStatus,
+    pub priority: TicketPriority,
+    pub assignee: Option<u64>, // assigned user_id
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub mtime: OffsetDateTime,
+    /// Set when in the trash (see `trash`), hidden from the list and get.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted: Option<OffsetDateTime>,
+    /// Starts at 1, bumped by the store on every change (see `IfMatch`).
+    #[serde(default = "first_version")]
+    pub version: u64,
+    /// Ids of its labels (see `label`), ascending.
+    #[serde(default)]
+    pub labels: Vec<u64>,
+}
+
+fn first_version() -> u64 {
+    1
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    strum_macros::AsRefStr,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TicketStatus {
+    #[default]
+    Open,
+    InProgress,
+    Closed,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    strum_macros::AsRefStr,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TicketPriority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+// Note: status and priority are taken as strings and parsed by the model,
+//       so unknown values are reported as our own `Error` variants.
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TicketForCreate {
+    pub title: String,
+    pub description: Option<String>,
+    #[schema(value_type = Option<TicketStatus>)]
+    pub status: Option<String>,
+    #[schema(value_type = Option<TicketPriority>)]
+    pub priority: Option<String>,
+    pub assignee: Option<u64>,
+    /// Label ids.
+    pub labels: Option<Vec<u64>>,
+}
+
+#[cfg(test)]
+impl TicketForCreate {
+    /// Test fixture, only the title is set.
+    pub fn with_title(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            description: None,
+            status: None,
+            priority: None,
+            assignee: None,
+            labels: None,
+        }
+    }
+}
+
+/// All the fields are set, the omitted optional ones are reset to their default.
+#[derive(Deserialize, ToSchema)]
+pub struct TicketForReplace {
+    pub title: String,
+    pub description: Option<String>,
+    #[schema(value_type = Option<TicketStatus>)]
+    pub status: Option<String>,
+    #[schema(value_type = Option<TicketPriority>)]
+    pub priority: Option<String>,
+    pub assignee: Option<u64>,
+    /// Label ids.
+    pub labels: Option<Vec<u64>>,
+}
+
+/// Only the given fields are changed.
+/// For `description` and `assignee`, an explicit `null` clears the value.
+#[derive(Deserialize, Default, ToSchema)]
+pub struct TicketForUpdate {
+    pub title: Option<String>,
+    #[serde(default, with = "double_option")]
+    #[schema(value_type = Option<String>)]
+    pub description: Option<Option<String>>,
+    #[schema(value_type = Option<TicketStatus>)]
+    pub status: Option<String>,
+    #[schema(value_type = Option<TicketPriority>)]
+    pub priority: Option<String>,
+    #[serde(default, with = "double_option")]
+    #[schema(value_type = Option<u64>)]
+    pub assignee: Option<Option<u64>>,
+    /// Label ids, replacing the current ones.
+    pub labels: Option<Vec<u64>>,
+}
+
+/// Condition of a change on the current ticket version (from `If-Match`).
+#[derive(Debug, Clone, Default)]
+pub enum IfMatch {
+    #[default]
+    Any,
+    /// One of these versions (none when no tag could be a version).
+    Versions(Vec<u64>),
+}
+
+impl IfMatch {
+    fn check(&self, ticket: &Ticket) -> Result<()> {
+        match self {
+            IfMatch::Versions(versions) if !versions.contains(&ticket.version) => {
+                Err(Error::TicketPreconditionFailed {
+                    id: ticket.id,
+                    version: ticket.version,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// endregion: --- Ticket Types
+
+// region:    --- Ticket List Types
+
+/// Query params of the ticket list, e.g.,
+/// `?status=open&title_contains=bug&order_by=-priority&limit=20`
+///
+/// `order_by` takes a ticket field name, prefixed by `-` for descending order.
+/// `cursor` is the `next_cursor` of the previous page (takes precedence over `offset`).
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TicketListParams {
+    // -- Filters
+    pub cid: Option<u64>,
+    pub assignee: Option<u64>,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub title_contains: Option<String>,
+    /// A label id.
+    pub label: Option<u64>,
+
+    // -- Ordering & Paging
+    pub order_by: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    pub cursor: Option<String>,
+}
+
+/// Filters pushed down to the store (all given ones must match).
+#[derive(Debug, Default, Clone)]
+pub struct TicketFilter {
+    pub cid: Option<u64>,
+    pub assignee: Option<u64>,
+    pub status: Option<TicketStatus>,
+    pub priority: Option<TicketPriority>,
+    /// Case insensitive.
+    pub title_contains: Option<String>,
+    /// Has this label id.
+    pub label: Option<u64>,
+    /// The tickets in the trash, instead of the live ones.
+    pub trashed: bool,
+}
+
+/// Ticket fields usable in `order_by` (named as the `Ticket` properties).
+#[derive(Debug, Clone, Copy, Default, strum_macros::AsRefStr, strum_macros::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum TicketField {
+    #[default]
+    Id,
+    Cid,
+    Title,
+    Description,
+    Status,
+    Priority,
+    Assignee,
+    Ctime,
+    Mtime,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TicketOrderBy {
+    pub field: TicketField,
+    pub desc: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TicketPage {
+    pub data: Vec<Ticket>,
+    /// Number of tickets matching the filters, across all pages.
+    pub total: u64,
+    /// To pass as `cursor` to get the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+// endregion: --- Ticket List Types
+
+// region: --- Ticket CRUD
+
+impl ModelController {
+    pub async fn create_ticket(&self, ctx: Ctx, ticket_fc: TicketForCreate) -> Result<Ticket> {
+        check_can_create(&ctx)?;
+
+        let now = OffsetDateTime::now_utc();
+        let ticket = self.new_ticket(&ctx, ticket_fc, now).await?;
+
+        let audit = AuditForCreate::new(&ctx, AuditAction::TicketCreate, now);
+        let ticket = self.store.insert_ticket(ticket, audit).await?;
+        self.search_index.put(&ticket);
+        self.publish_ticket_event(TicketEventKind::Created, &ticket);
+
+        Ok(ticket)
+    }
+
+    pub async fn get_ticket(&self, ctx: Ctx, id: u64) -> Result<Ticket> {
+        let ticket = self
+            .get_live_ticket(id)
+            .await?
+            .ok_or(Error::TicketNotFound { id })?;
+        check_can_read(&ctx, &ticket)?;
+
+        Ok(ticket)
+    }
+
+    pub async fn list_tickets(&self, ctx: Ctx, params: TicketListParams) -> Result<TicketPage> {
+        self.list_tickets_in(ctx, params, false).await
+    }
+
+    /// List the live tickets, or the `trashed` ones.
+    pub(super) async fn list_tickets_in(
+        &self,
+        ctx: Ctx,
+        params: TicketListParams,
+        trashed: bool,
+    ) -> Result<TicketPage> {
+        let mut filter = TicketFilter {
+            cid: params.cid,
+            assignee: params.assignee,
+            status: parse_status(params.status)?,
+            priority: parse_priority(params.priority)?,
+            title_contains: params.title_contains.filter(|t| !t.is_empty()),
+            label: params.label,
+            trashed,
+        };
+        let order_by = parse_order_by(params.order_by)?;
+        let list_options = ListOptions::new(params.limit, params.offset, params.cursor)?;
+
+        // Members only see their own tickets, whatever cid they asked for.
+        if ctx.role() == Role::Member {
+            if filter.cid.is_some_and(|cid| cid != ctx.user_id()) {
+                return Ok(TicketPage {
+                    data: Vec::new(),
+                    total: 0,
+                    next_cursor: None,
+                });
+            }
+            filter.cid = Some(ctx.user_id());
+        }
+
+        let (data, total) = self
+            .store
+            .list_tickets(&filter, order_by, list_options)
+            .await?;
+        let next_cursor = list_options.next_cursor(data.len() as u64, total);
+
+        Ok(TicketPage {
+            data,
+            total,
+            next_cursor,
+        })
+    }
+
+    pub async fn replace_ticket(
+        &self,
+        ctx: Ctx,
+        id: u64,
+        ticket_fr: TicketForReplace,
+        if_match: IfMatch,
+    ) -> Result<Ticket> {
+        let mut ticket = self
+            .get_live_ticket(id)
+            .await?
+            .ok_or(Error::TicketNotFound { id })?;
+        check_can_modify(&ctx, &ticket, "ticket_update")?;
+        if_match.check(&ticket)?;
+
+        ticket.title = validate_title(ticket_fr.title)?;
+        ticket.description = ticket_fr.description;
+        ticket.status = parse_status(ticket_fr.status)?.unwrap_or_default();
+        ticket.priority = parse_priority(ticket_fr.priority)?.unwrap_or_default();
+        ticket.assignee = self.validate_assignee(ticket_fr.assignee).await?;
+        ticket.labels = self.validate_labels(ticket_fr.labels).await?;
+        ticket.mtime = OffsetDateTime::now_utc();
+
+        let audit = AuditForCreate::new(&ctx, AuditAction::TicketUpdate, ticket.mtime);
+        let ticket = self
+            .store
+            .update_ticket(ticket, audit)
+            .await?
+            .ok_or(Error::TicketNotFound { id })?;
+        self.search_index.put(&ticket);
+        self.publish_ticket_event(TicketEventKind::Updated, &ticket);
+
+        Ok(ticket)
+    }
+
+    pub async fn update_ticket(
+        &self,
+        ctx: Ctx,
+        id: u64,
+        ticket_fu: TicketForUpdate,
+        if_match: IfMatch,
+    ) -> Result<Ticket> {
+        let mut ticket = self
+            .get_live_ticket(id)
+            .await?
+            .ok_or(Error::TicketNotFound { id })?;
+        check_can_modify(&ctx, &ticket, "ticket_update")?;
+        if_match.check(&ticket)?;
+
+        if let Some(title) = ticket_fu.title {
+            ticket.title = validate_title(title)?;
+        }
+        if let Some(description) = ticket_fu.description {
+            ticket.description = description;
+        }
+        if let Some(status) = parse_status(ticket_fu.status)? {
+            ticket.status = status;
+        }
+        if let Some(priority) = parse_priority(ticket_fu.priority)? {
+            ticket.priority = priority;
+        }
+        if let Some(assignee) = ticket_fu.assignee {
+            ticket.assignee = self.validate_assignee(assignee).await?;
+        }
+        if let Some(labels) = ticket_fu.labels {
+            ticket.labels = self.validate_labels(Some(labels)).await?;
+        }
+        ticket.mtime = OffsetDateTime::now_utc();
+
+        let audit = AuditForCreate::new(&ctx, AuditAction::TicketUpdate, ticket.mtime);
+        let ticket = self
+            .store
+            .update_ticket(ticket, audit)
+            .await?
+            .ok_or(Error::TicketNotFound { id })?;
+        self.search_index.put(&ticket);
+        self.publish_ticket_event(TicketEventKind::Updated, &ticket);
+
+        Ok(ticket)
+    }
+
+    /// Moves the ticket to the trash (see `trash` to restore it).
+    pub async fn delete_ticket(&self, ctx: Ctx, id: u64, if_match: IfMatch) -> Result<Ticket> {
+        let mut ticket = self
+            .get_live_ticket(id)
+            .await?
+            .ok_or(Error::TicketDeleteFailIdNotFound { id })?;
+        check_can_modify(&ctx, &ticket, "ticket_delete")?;
+        if_match.check(&ticket)?;
+
+        let now = OffsetDateTime::now_utc();
+        ticket.deleted = Some(now);
+
+        let audit = AuditForCreate::new(&ctx, AuditAction::TicketDelete, now);
+        let ticket = self
+            .store
+            .update_ticket(ticket, audit)
+            .await?
+            .ok_or(Error::TicketDeleteFailIdNotFound { id })?;
+        self.search_index.put(&ticket);
+        self.publish_ticket_event(TicketEventKind::Deleted, &ticket);
+
+        Ok(ticket)
+    }
+
+    /// The ticket, unless it does not exist or is in the trash.
+    async fn get_live_ticket(&self, id: u64) -> Result<Option<Ticket>> {
+        let ticket = self.store.get_ticket(id).await?;
+
+        Ok(ticket.filter(|t| t.deleted.is_none()))
+    }
+}
+
+// endregion: --- Ticket CRUD
+
+// region:    --- Validations
+
+fn validate_title(title: String) -> Result<String> {
+    let title = title.trim();
+
+    if title.is_empty() {
+        return Err(Error::TicketTitleEmpty);
+    }
+
+    Ok(title.to_string())
+}
+
+fn parse_status(status: Option<String>) -> Result<Option<TicketStatus>> {
+    status
+        .map(|status| {
+            status
+                .parse()
+                .map_err(|_| Error::TicketStatusUnknown { status })
+        })
+        .transpose()
+}
+
+fn parse_priority(priority: Option<String>) -> Result<Option<TicketPriority>> {
+    priority
+        .map(|priority| {
+            priority
+                .parse()
+                .map_err(|_| Error::TicketPriorityUnknown { priority })
+        })
+        .transpose()
+}
+
+fn parse_order_by(order_by: Option<String>) -> Result<TicketOrderBy> {
+    let Some(order_by) = order_by.filter(|o| !o.is_empty()) else {
+        return Ok(TicketOrderBy::default());
+    };
+
+    let (name, desc) = match order_by.strip_prefix('-') {
+        Some(name) => (name, true),
+        None => (order_by.as_str(), false),
+    };
+    let field = name.parse().map_err(|_| Error::ListOrderByUnknown {
+        order_by: order_by.clone(),
+    })?;
+
+    Ok(TicketOrderBy { field, desc })
+}
+
+impl ModelController {
+    /// The validated ticket to insert, created by `ctx`.
+    pub(super) async fn new_ticket(
+        &self,
+        ctx: &Ctx,
+        ticket_fc: TicketForCreate,
+        now: OffsetDateTime,
+    ) -> Result<Ticket> {
+        Ok(Ticket {
+            id: 0, // assigned by the store
+            cid: ctx.user_id(),
+            title: validate_title(ticket_fc.title)?,
+            description: ticket_fc.description,
+            status: parse_status(ticket_fc.status)?.unwrap_or_default(),
+            priority: parse_priority(ticket_fc.priority)?.unwrap_or_default(),
+            assignee: self.validate_assignee(ticket_fc.assignee).await?,
+            ctime: now,
+            mtime: now,
+            deleted: None,
+            version: first_version(),
+            labels: self.validate_labels(ticket_fc.labels).await?,
+        })
+    }
+
+    async fn validate_assignee(&self, assignee: Option<u64>) -> Result<Option<u64>> {
+        if let Some(assignee) = assignee {
+            if self.store.get_user(assignee).await?.is_none() {
+                return Err(Error::TicketAssigneeNotFound { assignee });
+            }
+        }
+
+        Ok(assignee)
+    }
+}
+
+// endregion: --- Validations
+
+// region:    --- Access Control
+
+// - Admin:  read and change all tickets.
+// - Member: create tickets, read and change their own.
+// - Viewer: read all tickets, change nothing.
+
+pub(super) fn can_read(ctx: &Ctx, ticket: &Ticket) -> bool {
+    match ctx.role() {
+        Role::Admin | Role::Viewer => true,
+        Role::Member => ticket.cid == ctx.user_id(),
+    }
+}
+
+fn check_can_read(ctx: &Ctx, ticket: &Ticket) -> Result<()> {
+    if can_read(ctx, ticket) {
+        Ok(())
+    } else {
+        Err(Error::AccessDenied {
+            action: "ticket_read",
+        })
+    }
+}
+
+pub(super) fn check_can_create(ctx: &Ctx) -> Result<()> {
+    match ctx.role() {
+        Role::Admin | Role::Member => Ok(()),
+        Role::Viewer => Err(Error::AccessDenied {
+            action: "ticket_create",
+        }),
+    }
+}
+
+pub(super) fn check_can_modify(ctx: &Ctx, ticket: &Ticket, action: &'static str) -> Result<()> {
+    let allowed = match ctx.role() {
+        Role::Admin => true,
+        Role::Member => ticket.cid == ctx.user_id(),
+        Role::Viewer => false,
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(Error::AccessDenied { action })
+    }
+}
+
+// endregion: --- Access Control
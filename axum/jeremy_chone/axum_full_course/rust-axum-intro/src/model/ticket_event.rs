@@ -0,0 +1,167 @@
+//! Ticket change feed.
+//!
+//! The `ModelController` publishes each ticket create/update/delete on a
+//! broadcast channel. Publishing never waits: a subscriber too slow to keep up
+//! gets a single `Lagged` item, and its feed ends.
+
+use crate::ctx::Ctx;
+use crate::model::ticket::can_read;
+use crate::model::{ModelController, Ticket};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::ToSchema;
+
+/// Events kept for the subscribers before the slowest ones lag.
+pub(super) const TICKET_EVENTS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TicketEvent {
+    pub kind: TicketEventKind,
+    /// For `Deleted`, the ticket as it was before the delete.
+    pub ticket: Ticket,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    strum_macros::AsRefStr,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TicketEventKind {
+    Created,
+    Updated,
+    /// Moved to the trash.
+    Deleted,
+    /// Back from the trash.
+    Restored,
+}
+
+#[derive(Debug, Clone)]
+pub enum TicketFeedItem {
+    Event(TicketEvent),
+    /// The subscriber fell behind, and `skipped` events were lost.
+    /// Always the last item of the feed.
+    Lagged {
+        skipped: u64,
+    },
+}
+
+/// The events of the tickets visible to the `ctx` (see `can_read`).
+pub struct TicketFeed {
+    ctx: Ctx,
+    rx: broadcast::Receiver<TicketEvent>,
+    ended: bool,
+}
+
+impl TicketFeed {
+    /// Next item, or `None` once the feed ended.
+    pub async fn recv(&mut self) -> Option<TicketFeedItem> {
+        if self.ended {
+            return None;
+        }
+
+        loop {
+            match self.rx.recv().await {
+                Ok(event) if can_read(&self.ctx, &event.ticket) => {
+                    return Some(TicketFeedItem::Event(event));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    self.ended = true;
+                    return Some(TicketFeedItem::Lagged { skipped });
+                }
+                Err(RecvError::Closed) => {
+                    self.ended = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl ModelController {
+    pub fn subscribe_ticket_events(&self, ctx: Ctx) -> TicketFeed {
+        TicketFeed {
+            ctx,
+            rx: self.ticket_events.subscribe(),
+            ended: false,
+        }
+    }
+
+    pub(super) fn publish_ticket_event(&self, kind: TicketEventKind, ticket: &Ticket) {
+        // Only fails when there are no subscribers.
+        let _ = self.ticket_events.send(TicketEvent {
+            kind,
+            ticket: ticket.clone(),
+        });
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use crate::ctx::Role;
+    use crate::model::TicketForCreate;
+    use crate::Result;
+
+    #[tokio::test]
+    async fn test_ticket_feed_filtered_by_visibility() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let member_1 = Ctx::new(1, Role::Member);
+        let member_2 = Ctx::new(2, Role::Member);
+        let mut feed_2 = mc.subscribe_ticket_events(member_2.clone());
+
+        mc.create_ticket(member_1, TicketForCreate::with_title("From member 1"))
+            .await?;
+        let ticket = mc
+            .create_ticket(member_2, TicketForCreate::with_title("From member 2"))
+            .await?;
+
+        let Some(TicketFeedItem::Event(event)) = feed_2.recv().await else {
+            panic!("should be an event");
+        };
+        assert_eq!(event.kind, TicketEventKind::Created);
+        assert_eq!(event.ticket.id, ticket.id);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ticket_feed_lagged_ends() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let admin = Ctx::new(1, Role::Admin);
+        let mut feed = mc.subscribe_ticket_events(admin.clone());
+
+        for i in 0..TICKET_EVENTS_CAPACITY + 10 {
+            mc.create_ticket(
+                admin.clone(),
+                TicketForCreate::with_title(&format!("Ticket {i}")),
+            )
+            .await?;
+        }
+
+        assert!(matches!(
+            feed.recv().await,
+            Some(TicketFeedItem::Lagged { skipped: 10 })
+        ));
+        assert!(feed.recv().await.is_none());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
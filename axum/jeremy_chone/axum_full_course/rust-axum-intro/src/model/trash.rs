@@ -0,0 +1,131 @@
+//! Ticket trash.
+//!
+//! `delete_ticket` only sets `Ticket.deleted`. From the trash, tickets can be
+//! listed and restored, until the purge task removes them for good once they
+//! have been deleted for longer than the retention.
+
+use crate::ctx::Ctx;
+use crate::model::audit::{AuditAction, AuditForCreate};
+use crate::model::ticket::check_can_modify;
+use crate::model::{ModelController, Ticket, TicketEventKind, TicketListParams, TicketPage};
+use crate::{Error, Result};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+impl ModelController {
+    /// Same params and visibility as `list_tickets`.
+    pub async fn list_trash(&self, ctx: Ctx, params: TicketListParams) -> Result<TicketPage> {
+        self.list_tickets_in(ctx, params, true).await
+    }
+
+    pub async fn restore_ticket(&self, ctx: Ctx, id: u64) -> Result<Ticket> {
+        let mut ticket = self
+            .store
+            .get_ticket(id)
+            .await?
+            .filter(|t| t.deleted.is_some())
+            .ok_or(Error::TicketNotInTrash { id })?;
+        check_can_modify(&ctx, &ticket, "ticket_restore")?;
+
+        ticket.deleted = None;
+
+        let audit =
+            AuditForCreate::new(&ctx, AuditAction::TicketRestore, OffsetDateTime::now_utc());
+        let ticket = self
+            .store
+            .update_ticket(ticket, audit)
+            .await?
+            .ok_or(Error::TicketNotInTrash { id })?;
+        self.search_index.put(&ticket);
+        self.publish_ticket_event(TicketEventKind::Restored, &ticket);
+
+        Ok(ticket)
+    }
+
+    /// Permanently remove the tickets deleted before `deleted_before`.
+    /// Returns the number of purged tickets.
+    pub async fn purge_trash(&self, deleted_before: OffsetDateTime) -> Result<u64> {
+        let audit = AuditForCreate::system(AuditAction::TicketPurge, OffsetDateTime::now_utc());
+        let purged = self.store.purge_tickets(deleted_before, audit).await?;
+
+        Ok(purged.len() as u64)
+    }
+}
+
+/// Purge the trash every `interval`, of the tickets deleted for longer than `retention`.
+pub fn spawn_trash_purge(
+    mc: ModelController,
+    retention: Duration,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            let deleted_before = OffsetDateTime::now_utc() - retention;
+            match mc.purge_trash(deleted_before).await {
+                Ok(0) => (),
+                Ok(purged) => info!("{:<12} - {purged} tickets purged", "TRASH_PURGE"),
+                Err(ex) => warn!("{:<12} - failed - {ex:?}", "TRASH_PURGE"),
+            }
+        }
+    })
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use crate::ctx::Role;
+    use crate::model::{IfMatch, TicketForCreate};
+
+    #[tokio::test]
+    async fn test_trash_restore_and_purge() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let ctx = Ctx::new(1, Role::Admin);
+        let t1 = mc
+            .create_ticket(ctx.clone(), TicketForCreate::with_title("t1"))
+            .await?;
+        let t2 = mc
+            .create_ticket(ctx.clone(), TicketForCreate::with_title("t2"))
+            .await?;
+
+        mc.delete_ticket(ctx.clone(), t1.id, IfMatch::Any).await?;
+        mc.delete_ticket(ctx.clone(), t2.id, IfMatch::Any).await?;
+        assert!(mc.get_ticket(ctx.clone(), t1.id).await.is_err());
+
+        // -- Restore.
+        let t1 = mc.restore_ticket(ctx.clone(), t1.id).await?;
+        assert!(t1.deleted.is_none());
+        mc.get_ticket(ctx.clone(), t1.id).await?;
+        assert!(matches!(
+            mc.restore_ticket(ctx.clone(), t1.id).await,
+            Err(Error::TicketNotInTrash { .. })
+        ));
+
+        // -- Purge, only what is older than the retention.
+        let purged = mc
+            .purge_trash(OffsetDateTime::now_utc() - Duration::from_secs(60))
+            .await?;
+        assert_eq!(purged, 0);
+        let purged = mc.purge_trash(OffsetDateTime::now_utc()).await?;
+        assert_eq!(purged, 1);
+
+        let page = mc
+            .list_trash(ctx.clone(), TicketListParams::default())
+            .await?;
+        assert_eq!(page.total, 0);
+        let page = mc.list_tickets(ctx, TicketListParams::default()).await?;
+        assert_eq!(page.total, 1);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
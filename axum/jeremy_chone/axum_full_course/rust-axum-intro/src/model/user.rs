@@ -0,0 +1,200 @@
+//! User accounts (login/register).
+
+use crate::crypt::pwd::{hash_pwd, validate_pwd, SchemeStatus};
+use crate::ctx::{Ctx, Role};
+use crate::model::ModelController;
+use crate::{Error, Result};
+use serde::Deserialize;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+// region:    --- User Types
+
+#[derive(Debug, Clone)]
+pub struct User {
+    pub id: u64,
+    pub username: String,
+    pub pwd: String, // `#[scheme]#[hash]`, see `crypt::pwd`
+    pub role: Role,
+    pub ctime: OffsetDateTime,
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UserForRegister {
+    pub username: String,
+    pub pwd: String,
+}
+
+// endregion: --- User Types
+
+impl ModelController {
+    /// Register a new `Member` account.
+    /// The admins are promoted with `set_user_role`, the first one is seeded
+    /// from the config (see `seed_admin`).
+    pub async fn register_user(&self, user_fr: UserForRegister) -> Result<User> {
+        let UserForRegister { username, pwd } = user_fr;
+        let username = normalize_username(&username);
+
+        if username.is_empty() {
+            return Err(Error::RegisterFailEmptyField { field: "username" });
+        }
+        if pwd.is_empty() {
+            return Err(Error::RegisterFailEmptyField { field: "pwd" });
+        }
+
+        let pwd = hash_pwd(pwd).await?;
+
+        self.store
+            .insert_user(
+                username.to_string(),
+                pwd,
+                Role::Member,
+                OffsetDateTime::now_utc(),
+            )
+            .await
+    }
+
+    /// Create the `Admin` account of the config on startup, or promote it if it
+    /// already exists (its pwd is then left as is).
+    pub async fn seed_admin(&self, username: &str, pwd: String) -> Result<User> {
+        let username = normalize_username(username);
+        let now = OffsetDateTime::now_utc();
+
+        match self.store.get_user_by_username(username).await? {
+            Some(user) if user.role == Role::Admin => Ok(user),
+            Some(user) => self
+                .store
+                .update_user_role(user.id, Role::Admin, now)
+                .await?
+                .ok_or(Error::UserNotFound { id: user.id }),
+            None => {
+                let pwd = hash_pwd(pwd).await?;
+                self.store
+                    .insert_user(username.to_string(), pwd, Role::Admin, now)
+                    .await
+            }
+        }
+    }
+
+    pub async fn get_user(&self, id: u64) -> Result<Option<User>> {
+        self.store.get_user(id).await
+    }
+
+    /// Admin only.
+    pub async fn set_user_role(&self, ctx: Ctx, id: u64, role: Role) -> Result<User> {
+        if ctx.role() != Role::Admin {
+            return Err(Error::AccessDenied {
+                action: "user_set_role",
+            });
+        }
+
+        self.store
+            .update_user_role(id, role, OffsetDateTime::now_utc())
+            .await?
+            .ok_or(Error::UserNotFound { id })
+    }
+
+    /// Returns the user if the credentials match.
+    /// All failures are reported as `LoginFail`, to not tell which part was wrong,
+    /// until too many of them lock the username (see `login_lockout`).
+    pub async fn login_user(&self, username: &str, pwd: String) -> Result<User> {
+        let username = normalize_username(username);
+        self.login_lockout.check(username)?;
+
+        let Some(user) = self.store.get_user_by_username(username).await? else {
+            // Spend about the same time as a real validation, so unknown
+            // usernames can't be told apart by the response time.
+            hash_pwd(pwd).await?;
+            self.login_lockout.record_failure(username);
+            return Err(Error::LoginFail);
+        };
+
+        let status = match validate_pwd(pwd.clone(), user.pwd.clone()).await {
+            Ok(status) => status,
+            Err(Error::PwdNotMatching) => {
+                self.login_lockout.record_failure(username);
+                return Err(Error::LoginFail);
+            }
+            Err(ex) => return Err(ex),
+        };
+        self.login_lockout.record_success(username);
+
+        // Upgrade the hash to the default scheme, now that we have the clear pwd.
+        if status == SchemeStatus::Outdated {
+            let pwd = hash_pwd(pwd).await?;
+            self.store
+                .update_user_pwd(user.id, pwd, OffsetDateTime::now_utc())
+                .await?;
+        }
+
+        Ok(user)
+    }
+}
+
+/// The username as stored, looked up, and locked out (e.g., `" demo1"` is `"demo1"`).
+fn normalize_username(username: &str) -> &str {
+    username.trim()
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreConfig;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_login_username_normalized() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory)
+            .await?
+            .with_login_lockout(2, Duration::from_secs(60));
+        let user_fr = UserForRegister {
+            username: " demo1 ".to_string(),
+            pwd: "welcome".to_string(),
+        };
+        assert_eq!(mc.register_user(user_fr).await?.username, "demo1");
+
+        assert_eq!(
+            mc.login_user("demo1 ", "welcome".into()).await?.username,
+            "demo1"
+        );
+
+        // -- Same lockout, whatever the padding.
+        for username in ["demo1", " demo1"] {
+            let res = mc.login_user(username, "nope".into()).await;
+            assert!(matches!(res, Err(Error::LoginFail)));
+        }
+        let res = mc.login_user("demo1\t", "welcome".into()).await;
+        assert!(matches!(res, Err(Error::LoginLocked { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_member_and_seed_admin() -> Result<()> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        let user_fr = |username: &str| UserForRegister {
+            username: username.to_string(),
+            pwd: "welcome".to_string(),
+        };
+
+        // -- Even the first account is a member.
+        assert_eq!(mc.register_user(user_fr("demo1")).await?.role, Role::Member);
+
+        // -- Seeded, created or promoted.
+        let admin = mc.seed_admin("admin", "welcome".into()).await?;
+        assert_eq!(admin.role, Role::Admin);
+        assert_eq!(mc.seed_admin("admin", "other".into()).await?.id, admin.id);
+        mc.login_user("admin", "welcome".into()).await?;
+        assert_eq!(
+            mc.seed_admin("demo1", "nope".into()).await?.role,
+            Role::Admin
+        );
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,481 @@
+//! Outgoing webhooks: the ticket events POSTed to the URLs registered by the admins.
+//!
+//! Each delivery (one event to one webhook) is tried up to `max_attempts`
+//! times, with an exponential backoff, and each attempt is recorded.
+//! A webhook with `disable_after` failed deliveries in a row is disabled,
+//! until an admin enables it back.
+//!
+//! The request body is the `WebhookPayload` json, with the headers:
+//! - `X-Webhook-Event`: the event kind, e.g., `created`.
+//! - `X-Webhook-Delivery`: the delivery id, same for all its attempts.
+//! - `X-Webhook-Timestamp` and `X-Webhook-Signature` (see `crypt::webhook`).
+//!
+//! Note: The pending retries are only in memory, lost on restart.
+
+use crate::config::WebhookConfig;
+use crate::crypt::webhook::{generate_secret, sign_payload};
+use crate::ctx::Ctx;
+use crate::model::label::check_admin;
+use crate::model::{ModelController, Ticket, TicketEvent, TicketEventKind};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Attempts returned by `list_webhook_attempts`, the latest ones.
+const ATTEMPTS_LIST_LIMIT: u64 = 100;
+/// Of the error recorded for an attempt.
+const ATTEMPT_ERROR_MAX_LEN: usize = 500;
+
+// region:    --- Webhook Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    /// The event kinds sent, all of them when empty.
+    pub events: Vec<TicketEventKind>,
+    /// Only returned on create.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// False once disabled, by an admin or after too many failed deliveries.
+    pub active: bool,
+    /// Failed deliveries in a row.
+    pub failures: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+/// The created webhook, with the secret of its signatures.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookCreated {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WebhookForCreate {
+    /// `http` or `https`.
+    pub url: String,
+    /// Event kinds (`created`, `updated`, `deleted`, `restored`), all when omitted or empty.
+    pub events: Option<Vec<String>>,
+}
+
+/// Only the given fields are changed.
+#[derive(Deserialize, Default, ToSchema)]
+pub struct WebhookForUpdate {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    /// `true` enables it back (and resets `failures`).
+    pub active: Option<bool>,
+}
+
+/// One try of a delivery.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookAttempt {
+    pub id: u64,
+    pub webhook_id: u64,
+    /// The `X-Webhook-Delivery` header, same for all the attempts of a delivery.
+    pub delivery_id: Uuid,
+    pub event: TicketEventKind,
+    pub ticket_id: u64,
+    /// 1 for the first try.
+    pub attempt: u32,
+    /// Response status, `None` when no response (e.g., timeout).
+    pub status: Option<u16>,
+    /// `None` when delivered (2xx response).
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub ctime: OffsetDateTime,
+}
+
+/// Body of the webhook requests.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookPayload {
+    pub delivery_id: Uuid,
+    pub event: TicketEventKind,
+    /// For `deleted`, the ticket as it was before the delete.
+    pub ticket: Ticket,
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+}
+
+impl Webhook {
+    fn accepts(&self, event: TicketEventKind) -> bool {
+        self.active && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+// endregion: --- Webhook Types
+
+// region:    --- Webhook CRUD
+
+// Admin only.
+
+impl ModelController {
+    pub async fn create_webhook(
+        &self,
+        ctx: Ctx,
+        webhook_fc: WebhookForCreate,
+    ) -> Result<WebhookCreated> {
+        check_admin(&ctx, "webhook_create")?;
+
+        let now = OffsetDateTime::now_utc();
+        let webhook = Webhook {
+            id: 0, // assigned by the store
+            url: validate_url(webhook_fc.url)?,
+            events: parse_events(webhook_fc.events)?,
+            secret: generate_secret(),
+            active: true,
+            failures: 0,
+            ctime: now,
+            mtime: now,
+        };
+        let webhook = self.store.insert_webhook(webhook).await?;
+
+        Ok(WebhookCreated {
+            secret: webhook.secret.clone(),
+            webhook,
+        })
+    }
+
+    pub async fn get_webhook(&self, ctx: Ctx, id: u64) -> Result<Webhook> {
+        check_admin(&ctx, "webhook_get")?;
+
+        self.store
+            .get_webhook(id)
+            .await?
+            .ok_or(Error::WebhookNotFound { id })
+    }
+
+    /// All the webhooks, by id.
+    pub async fn list_webhooks(&self, ctx: Ctx) -> Result<Vec<Webhook>> {
+        check_admin(&ctx, "webhook_list")?;
+
+        self.store.list_webhooks().await
+    }
+
+    pub async fn update_webhook(
+        &self,
+        ctx: Ctx,
+        id: u64,
+        webhook_fu: WebhookForUpdate,
+    ) -> Result<Webhook> {
+        let mut webhook = self.get_webhook(ctx, id).await?;
+
+        if let Some(url) = webhook_fu.url {
+            webhook.url = validate_url(url)?;
+        }
+        if let Some(events) = webhook_fu.events {
+            webhook.events = parse_events(Some(events))?;
+        }
+        if let Some(active) = webhook_fu.active {
+            if active && !webhook.active {
+                webhook.failures = 0;
+            }
+            webhook.active = active;
+        }
+        webhook.mtime = OffsetDateTime::now_utc();
+
+        self.store
+            .update_webhook(webhook)
+            .await?
+            .ok_or(Error::WebhookNotFound { id })
+    }
+
+    /// Also deletes its recorded attempts.
+    pub async fn delete_webhook(&self, ctx: Ctx, id: u64) -> Result<Webhook> {
+        let webhook = self.get_webhook(ctx, id).await?;
+
+        self.store.delete_webhook(id).await?;
+
+        Ok(webhook)
+    }
+
+    /// The latest attempts of the webhook deliveries, latest first.
+    pub async fn list_webhook_attempts(&self, ctx: Ctx, id: u64) -> Result<Vec<WebhookAttempt>> {
+        self.get_webhook(ctx, id).await?;
+
+        self.store
+            .list_webhook_attempts(id, ATTEMPTS_LIST_LIMIT)
+            .await
+    }
+}
+
+// endregion: --- Webhook CRUD
+
+// region:    --- Delivery
+
+/// Start delivering the ticket events to the webhooks, from now on.
+pub fn spawn_webhook_delivery(mc: ModelController, config: WebhookConfig) -> JoinHandle<()> {
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        // A redirect is a failed delivery, so the receiver gets fixed.
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("FATAL - Cannot build the webhook http client");
+    // Subscribed before returning, so no later event is missed.
+    let mut events = mc.ticket_events.subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("{:<12} - {skipped} events lost, not delivered", "WEBHOOK");
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            let webhooks = match mc.store.list_webhooks().await {
+                Ok(webhooks) => webhooks,
+                Err(ex) => {
+                    warn!("{:<12} - cannot list the webhooks - {ex:?}", "WEBHOOK");
+                    continue;
+                }
+            };
+            for webhook in webhooks.into_iter().filter(|w| w.accepts(event.kind)) {
+                let delivery = Delivery::new(webhook.id, &event);
+                tokio::spawn(mc.clone().deliver(client.clone(), config, delivery));
+            }
+        }
+    })
+}
+
+struct Delivery {
+    webhook_id: u64,
+    id: Uuid,
+    event: TicketEventKind,
+    ticket_id: u64,
+    body: Vec<u8>,
+}
+
+impl Delivery {
+    fn new(webhook_id: u64, event: &TicketEvent) -> Self {
+        let payload = WebhookPayload {
+            delivery_id: Uuid::new_v4(),
+            event: event.kind,
+            ticket: event.ticket.clone(),
+            time: OffsetDateTime::now_utc(),
+        };
+
+        Self {
+            webhook_id,
+            id: payload.delivery_id,
+            event: event.kind,
+            ticket_id: event.ticket.id,
+            // Plain structs, cannot fail.
+            body: serde_json::to_vec(&payload).unwrap_or_default(),
+        }
+    }
+}
+
+impl ModelController {
+    async fn deliver(self, client: reqwest::Client, config: WebhookConfig, delivery: Delivery) {
+        let webhook_id = delivery.webhook_id;
+
+        for attempt in 1..=config.max_attempts {
+            if attempt > 1 {
+                tokio::time::sleep(backoff(config.backoff, attempt)).await;
+            }
+            // Read again, it may have been changed, disabled, or deleted meanwhile.
+            let webhook = match self.store.get_webhook(webhook_id).await {
+                Ok(Some(webhook)) if webhook.active => webhook,
+                Ok(_) => return,
+                Err(ex) => {
+                    warn!(
+                        "{:<12} - cannot get webhook {webhook_id} - {ex:?}",
+                        "WEBHOOK"
+                    );
+                    return;
+                }
+            };
+
+            let start = Instant::now();
+            let (status, error) = send(&client, &webhook, &delivery).await;
+            let attempt = WebhookAttempt {
+                id: 0, // assigned by the store
+                webhook_id,
+                delivery_id: delivery.id,
+                event: delivery.event,
+                ticket_id: delivery.ticket_id,
+                attempt,
+                status,
+                error,
+                duration_ms: start.elapsed().as_millis() as u64,
+                ctime: OffsetDateTime::now_utc(),
+            };
+            let delivered = attempt.error.is_none();
+            if let Err(ex) = self.store.insert_webhook_attempt(attempt).await {
+                warn!("{:<12} - cannot record the attempt - {ex:?}", "WEBHOOK");
+            }
+
+            if delivered {
+                self.record_delivery(webhook_id, true, config).await;
+                return;
+            }
+        }
+
+        self.record_delivery(webhook_id, false, config).await;
+    }
+
+    async fn record_delivery(&self, webhook_id: u64, delivered: bool, config: WebhookConfig) {
+        match self
+            .store
+            .record_webhook_delivery(webhook_id, delivered, config.disable_after)
+            .await
+        {
+            Ok(Some(webhook)) if !delivered && !webhook.active => info!(
+                "{:<12} - webhook {webhook_id} disabled, after {} failed deliveries",
+                "WEBHOOK", webhook.failures
+            ),
+            Ok(_) => (),
+            Err(ex) => warn!("{:<12} - cannot record the delivery - {ex:?}", "WEBHOOK"),
+        }
+    }
+}
+
+/// The response status, and the error if not delivered.
+async fn send(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    delivery: &Delivery,
+) -> (Option<u16>, Option<String>) {
+    let timestamp = OffsetDateTime::now_utc().unix_timestamp() as u64;
+    let signature = sign_payload(&webhook.secret, timestamp, &delivery.body);
+
+    let res = client
+        .post(&webhook.url)
+        .header("content-type", "application/json")
+        .header("x-webhook-event", delivery.event.as_ref())
+        .header("x-webhook-delivery", delivery.id.to_string())
+        .header("x-webhook-timestamp", timestamp.to_string())
+        .header("x-webhook-signature", signature)
+        .body(delivery.body.clone())
+        .send()
+        .await;
+
+    match res {
+        Ok(res) if res.status().is_success() => (Some(res.status().as_u16()), None),
+        Ok(res) => (
+            Some(res.status().as_u16()),
+            Some(format!("response status {}", res.status())),
+        ),
+        Err(ex) => (None, Some(truncate_error(ex.to_string()))),
+    }
+}
+
+/// At most `ATTEMPT_ERROR_MAX_LEN` chars (cut on a char boundary).
+fn truncate_error(mut error: String) -> String {
+    if let Some((idx, _)) = error.char_indices().nth(ATTEMPT_ERROR_MAX_LEN) {
+        error.truncate(idx);
+    }
+
+    error
+}
+
+/// Wait before the `attempt` (from 2): `base`, then doubled for each next one.
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(1 << attempt.saturating_sub(2).min(16))
+}
+
+// endregion: --- Delivery
+
+// region:    --- Validations
+
+fn validate_url(url: String) -> Result<String> {
+    let url = url.trim();
+
+    let valid = reqwest::Url::parse(url)
+        .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.has_host());
+    if !valid {
+        return Err(Error::WebhookUrlInvalid {
+            url: url.to_string(),
+        });
+    }
+
+    Ok(url.to_string())
+}
+
+/// Ascending and without duplicates.
+fn parse_events(events: Option<Vec<String>>) -> Result<Vec<TicketEventKind>> {
+    let mut events = events
+        .unwrap_or_default()
+        .into_iter()
+        .map(|event| {
+            event
+                .parse()
+                .map_err(|_| Error::WebhookEventUnknown { event })
+        })
+        .collect::<Result<Vec<TicketEventKind>>>()?;
+    events.sort_unstable();
+    events.dedup();
+
+    Ok(events)
+}
+
+// endregion: --- Validations
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles() {
+        let base = Duration::from_secs(10);
+
+        assert_eq!(backoff(base, 2), Duration::from_secs(10));
+        assert_eq!(backoff(base, 3), Duration::from_secs(20));
+        assert_eq!(backoff(base, 5), Duration::from_secs(80));
+        // Capped, never overflows.
+        assert_eq!(backoff(base, 100), backoff(base, 18));
+    }
+
+    #[test]
+    fn test_truncate_error_on_char_boundary() {
+        let error = truncate_error("é".repeat(ATTEMPT_ERROR_MAX_LEN + 1));
+        assert_eq!(error, "é".repeat(ATTEMPT_ERROR_MAX_LEN));
+
+        assert_eq!(truncate_error("timed out".to_string()), "timed out");
+    }
+
+    #[test]
+    fn test_validate_url_and_events() -> Result<()> {
+        assert_eq!(
+            validate_url(" https://ci.example.com/hook ".to_string())?,
+            "https://ci.example.com/hook"
+        );
+        for url in ["ftp://example.com", "example.com/hook", "http://"] {
+            let res = validate_url(url.to_string());
+            assert!(matches!(res, Err(Error::WebhookUrlInvalid { .. })), "{url}");
+        }
+
+        let events = parse_events(Some(vec![
+            "deleted".into(),
+            "created".into(),
+            "deleted".into(),
+        ]))?;
+        assert_eq!(
+            events,
+            vec![TicketEventKind::Created, TicketEventKind::Deleted]
+        );
+        let res = parse_events(Some(vec!["purged".into()]));
+        assert!(matches!(res, Err(Error::WebhookEventUnknown { .. })));
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,104 @@
+//! Graceful shutdown.
+//!
+//! On SIGINT or SIGTERM, the server stops accepting connections and drains the
+//! in-flight requests, for up to the configured timeout (long-lived ones, like
+//! the event streams, are cut then). While draining, `/readyz` reports not
+//! ready, so the load balancers stop sending new requests.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::info;
+
+/// Shared between the server (which starts the drain) and `/readyz`.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    draining: AtomicBool,
+    drain_started: Notify,
+}
+
+impl Shutdown {
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn start_draining(&self) {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        self.inner.drain_started.notify_waiters();
+    }
+
+    /// Resolves once the drain started.
+    pub async fn drain_started(&self) {
+        let notified = self.inner.drain_started.notified();
+        if self.is_draining() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Resolves on the first SIGINT or SIGTERM, after starting the drain.
+    /// For `axum::Server::with_graceful_shutdown`.
+    pub async fn on_signal(self) {
+        let signal = wait_signal().await;
+        info!("{:<12} - {signal} received, draining", "SHUTDOWN");
+
+        self.start_draining();
+    }
+}
+
+#[cfg(unix)]
+async fn wait_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate()).expect("FATAL - Cannot listen to SIGTERM");
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_signal() -> &'static str {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("FATAL - Cannot listen to Ctrl-C");
+
+    "Ctrl-C"
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_drain_started() {
+        let shutdown = Shutdown::default();
+        assert!(!shutdown.is_draining());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain_started().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        shutdown.start_draining();
+
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(shutdown.is_draining());
+        // Already started, resolves right away.
+        shutdown.drain_started().await;
+    }
+}
+
+// endregion: --- Tests
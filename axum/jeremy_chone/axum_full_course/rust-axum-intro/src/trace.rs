@@ -0,0 +1,26 @@
+//! Tracing subscriber, for the handlers/middlewares events (to stdout).
+//!
+//! Each request runs in a `request` span (see `mw_req_stamp`) with its `uuid`,
+//! `method`, `route` and `user_id`, so its events can be filtered and correlated.
+//!
+//! Note: This is the dev/ops tracing. The one-line-per-request log is in `log`.
+
+use crate::config::{Config, TraceFormat};
+use tracing_subscriber::EnvFilter;
+
+/// Install the global subscriber. Only the first call has an effect.
+pub fn init_tracing(config: &Config) {
+    // Already validated with the config.
+    let filter =
+        EnvFilter::try_new(&config.trace_filter).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let _ = match config.trace_format {
+        TraceFormat::Pretty => builder.pretty().try_init(),
+        TraceFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .try_init(),
+    };
+}
@@ -0,0 +1,76 @@
+//! CSRF tokens of the html forms (double submit cookie).
+//!
+//! The token is a random value kept in the `csrf-token` cookie (`SameSite=Strict`),
+//! and each form posts it back in its hidden `csrf` field. Another site can
+//! make the browser post a form, but cannot read the cookie to fill the field.
+
+use crate::{Error, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tower_cookies::cookie::SameSite;
+use tower_cookies::{Cookie, Cookies};
+use uuid::Uuid;
+
+const CSRF_COOKIE: &str = "csrf-token";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The token for the forms of the page, set in the cookie when not there yet.
+pub fn csrf_token(cookies: &Cookies) -> String {
+    if let Some(cookie) = cookies.get(CSRF_COOKIE) {
+        return cookie.value().to_string();
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    let mut cookie = Cookie::new(CSRF_COOKIE, token.clone());
+    cookie.set_http_only(true);
+    cookie.set_same_site(SameSite::Strict);
+    cookie.set_path("/");
+    cookies.add(cookie);
+
+    token
+}
+
+/// The token posted by a form must be the one of the cookie.
+pub fn check_csrf(cookies: &Cookies, form_token: &str) -> Result<()> {
+    let cookie_token = cookies.get(CSRF_COOKIE).map(|c| c.value().to_string());
+
+    match cookie_token {
+        Some(cookie_token) if !form_token.is_empty() && tokens_match(&cookie_token, form_token) => {
+            Ok(())
+        }
+        _ => Err(Error::CsrfTokenInvalid),
+    }
+}
+
+/// Compare the tokens through their HMACs (same length, whatever was posted),
+/// as `verify_slice` does a constant time comparison.
+fn tokens_match(cookie_token: &str, form_token: &str) -> bool {
+    let token_mac = |token: &str| {
+        let mut mac = HmacSha256::new_from_slice(CSRF_COOKIE.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(token.as_bytes());
+        mac
+    };
+    let cookie_sign = token_mac(cookie_token).finalize().into_bytes();
+
+    token_mac(form_token).verify_slice(&cookie_sign).is_ok()
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        let token = Uuid::new_v4().simple().to_string();
+
+        assert!(tokens_match(&token, &token));
+        assert!(!tokens_match(&token, &Uuid::new_v4().simple().to_string()));
+        assert!(!tokens_match(&token, &token[..16]));
+    }
+}
+
+// endregion: --- Tests
@@ -1,5 +1,93 @@
+use crate::config::config;
+use crate::crypt::token::generate_token;
+use crate::model::ModelController;
+use crate::Result;
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::USER_AGENT;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tower_cookies::{Cookie, Cookies};
+use uuid::Uuid;
+
+pub mod csrf;
 pub mod mw_auth;
+pub mod mw_rate_limit;
+pub mod mw_req_stamp;
+pub mod routes_audit;
+pub mod routes_board;
+pub mod routes_comments;
+pub mod routes_docs;
+pub mod routes_health;
+pub mod routes_labels;
 pub mod routes_login;
+pub mod routes_metrics;
+pub mod routes_sessions;
+pub mod routes_ticket_events;
 pub mod routes_tickets;
+pub mod routes_users;
+pub mod routes_webhooks;
+
+/// Set (or refresh) the auth token cookie of the session with a fresh expiration.
+fn set_token_cookie(cookies: &Cookies, session_id: Uuid) {
+    let token = generate_token(session_id, config().token_ttl, &config().token_secret);
+
+    let mut cookie = Cookie::new(config().auth_cookie_name.as_str(), token.to_string());
+    cookie.set_http_only(true);
+    cookie.set_path("/");
+
+    cookies.add(cookie);
+}
+
+fn remove_token_cookie(cookies: &Cookies) {
+    let mut cookie = Cookie::named(config().auth_cookie_name.as_str());
+    cookie.set_path("/");
+
+    cookies.remove(cookie);
+}
+
+/// Start a login session for `user_id`, and set its auth token cookie.
+async fn start_session(
+    mc: &ModelController,
+    cookies: &Cookies,
+    user_id: u64,
+    client: LoginClient,
+) -> Result<()> {
+    let session = mc
+        .create_session(user_id, client.user_agent, client.ip)
+        .await?;
+
+    set_token_cookie(cookies, session.id);
+
+    Ok(())
+}
+
+/// The client of a login request, recorded in its session.
+struct LoginClient {
+    user_agent: Option<String>,
+    ip: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for LoginClient {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> core::result::Result<Self, Infallible> {
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|ua| ua.to_str().ok())
+            .map(str::to_string);
+        // Same as the rate limits, the peer address (see `mw_rate_limit`).
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ci| ci.0.ip().to_string());
 
-pub const AUTH_TOKEN: &str = "auth-token";
+        Ok(Self { user_agent, ip })
+    }
+}
@@ -1,6 +1,9 @@
+use crate::config::config;
+use crate::crypt::token::{validate_token, Token};
 use crate::ctx::Ctx;
 use crate::model::ModelController;
-use crate::web::AUTH_TOKEN;
+use crate::web::mw_req_stamp::ReqStamp;
+use crate::web::{remove_token_cookie, set_token_cookie};
 use crate::Error::AuthFailNoAuthTokenCookie;
 use crate::{Error, Result};
 use async_trait::async_trait;
@@ -9,16 +12,15 @@ use axum::http::request::Parts;
 use axum::http::Request;
 use axum::middleware::Next;
 use axum::response::Response;
-use axum::RequestPartsExt;
-use lazy_regex::regex_captures;
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
+use tracing::{debug, Span};
 
 pub async fn mw_require_auth<B>(
     ctx: Result<Ctx>,
     req: Request<B>,
     next: Next<B>,
 ) -> Result<Response> {
-    println!("->> {:<12} - mw_require_auth - {ctx:?}", "MIDDLEWARE");
+    debug!("{:<12} - mw_require_auth - {ctx:?}", "MIDDLEWARE");
 
     ctx?;
 
@@ -26,30 +28,46 @@ pub async fn mw_require_auth<B>(
 }
 
 pub async fn mw_ctx_resolver<B>(
-    state: State<ModelController>,
+    State(mc): State<ModelController>,
     cookies: Cookies,
     mut req: Request<B>,
     next: Next<B>,
 ) -> Result<Response> {
-    println!("->> {:<12} - mw_ctx_resolver", "MIDDLEWARE");
+    debug!("{:<12} - mw_ctx_resolver", "MIDDLEWARE");
 
-    let auth_token = cookies.get(AUTH_TOKEN).map(|c| c.value().to_string());
+    let auth_token = cookies
+        .get(&config().auth_cookie_name)
+        .map(|c| c.value().to_string());
 
     // Compute Result<Ctx>.
-    let result_ctx = match auth_token
-        .ok_or(Error::AuthFailNoAuthTokenCookie)
-        .and_then(parse_token)
-    {
-        Ok((user_id, _exp, _sign)) => {
-            // Todo: Token components validations.
-            Ok(Ctx::new(user_id))
+    let req_uuid = req.extensions().get::<ReqStamp>().map(|stamp| stamp.uuid);
+    let result_ctx = resolve_ctx(&mc, auth_token)
+        .await
+        .map(|ctx| match req_uuid {
+            Some(req_uuid) => ctx.with_req_uuid(req_uuid),
+            None => ctx,
+        });
+
+    match &result_ctx {
+        // Sliding expiration: each authenticated request gets a fresh token.
+        Ok(ctx) => {
+            Span::current().record("user_id", ctx.user_id());
+            if let Some(session_id) = ctx.session_id() {
+                set_token_cookie(&cookies, session_id);
+            }
         }
-        Err(e) => Err(e),
-    };
-
-    // Remove the cookie if something went wrong other than NoAuthTokenCookie
-    if result_ctx.is_err() && !matches!(result_ctx, Err(AuthFailNoAuthTokenCookie)) {
-        cookies.remove(Cookie::named(AUTH_TOKEN))
+        Err(AuthFailNoAuthTokenCookie) => (),
+        // Remove the cookie if the token itself failed.
+        Err(
+            Error::AuthFailTokenWrongFormat
+            | Error::AuthFailTokenExpired
+            | Error::AuthFailTokenBadSignature
+            | Error::AuthFailUserNotFound
+            | Error::AuthFailSessionNotFound
+            | Error::AuthFailSessionExpired,
+        ) => remove_token_cookie(&cookies),
+        // Otherwise (e.g., the store is down), keep the cookie and fail the request.
+        Err(ex) => return Err(ex.clone()),
     }
 
     // Store the ctx_result in the request extension
@@ -57,6 +75,24 @@ pub async fn mw_ctx_resolver<B>(
 
     Ok(next.run(req).await)
 }
+async fn resolve_ctx(mc: &ModelController, auth_token: Option<String>) -> Result<Ctx> {
+    let token: Token = auth_token
+        .ok_or(Error::AuthFailNoAuthTokenCookie)?
+        .parse()?;
+    validate_token(&token, &config().token_secret)?;
+
+    // Revoked and idled out sessions fail here, whatever the token expiration.
+    let session = mc.resolve_session(token.session_id).await?;
+
+    // The role is read on each request, so role changes apply right away.
+    let user = mc
+        .get_user(session.user_id)
+        .await?
+        .ok_or(Error::AuthFailUserNotFound)?;
+
+    Ok(Ctx::new(user.id, user.role).with_session_id(session.id))
+}
+
 // region:   --- Ctx Extractor
 
 #[async_trait]
@@ -64,7 +100,7 @@ impl<S: Send + Sync> FromRequestParts<S> for Ctx {
     type Rejection = Error;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
-        println!("->> {:<12} - Ctx", "EXTRACTOR");
+        debug!("{:<12} - Ctx", "EXTRACTOR");
 
         parts
             .extensions
@@ -75,19 +111,3 @@ impl<S: Send + Sync> FromRequestParts<S> for Ctx {
 }
 
 // endregion: --- Ctx Extractor
-
-/// Parse a token of format `user-[user-id].[expiration].[signature]`
-/// Returns (user_id, expiration, signature)
-fn parse_token(token: String) -> Result<(u64, String, String)> {
-    let (_whole, user_id, exp, sign) = regex_captures!(
-        r#"^user-(\d+)\.(.+)\.(.+)"#, // a literal regex
-        &token
-    )
-    .ok_or(Error::AuthFailTokenWrongFormat)?;
-
-    let user_id: u64 = user_id
-        .parse()
-        .map_err(|_| Error::AuthFailTokenWrongFormat)?;
-
-    Ok((user_id, exp.to_string(), sign.to_string()))
-}
@@ -0,0 +1,147 @@
+//! Token bucket rate limiting, one limiter per route group (see `app`).
+//!
+//! Requests are counted per user for the `per_user` limiters (when there is
+//! one, per client IP otherwise), and per client IP for the `per_ip` ones.
+
+use crate::config::RateLimit;
+use crate::ctx::Ctx;
+use crate::{Error, Result};
+use axum::extract::{ConnectInfo, State};
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::debug;
+
+/// Above this many buckets, the full ones (idle clients) are dropped.
+const PRUNE_AT: usize = 10_000;
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    per_user: bool,
+    buckets: Arc<Mutex<HashMap<RateKey, Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RateKey {
+    Ip(IpAddr),
+    User(u64),
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn per_ip(limit: RateLimit) -> Self {
+        Self::new(limit, false)
+    }
+
+    pub fn per_user(limit: RateLimit) -> Self {
+        Self::new(limit, true)
+    }
+
+    fn new(limit: RateLimit, per_user: bool) -> Self {
+        Self {
+            limit,
+            per_user,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Take a token from the bucket of `key`,
+    /// or fail with `RateLimited` and the seconds until the next one.
+    fn take(&self, key: RateKey) -> Result<()> {
+        let burst = f64::from(self.limit.burst);
+        let per_sec = burst / self.limit.period.as_secs_f64();
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= PRUNE_AT {
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.last).as_secs_f64() * per_sec < burst
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: burst,
+            last: now,
+        });
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(burst);
+        bucket.last = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            Ok(())
+        } else {
+            let retry_after_sec = ((1. - bucket.tokens) / per_sec).ceil() as u64;
+            Err(Error::RateLimited {
+                retry_after_sec: retry_after_sec.max(1),
+            })
+        }
+    }
+}
+
+pub async fn mw_rate_limit<B>(
+    State(limiter): State<RateLimiter>,
+    ctx: Option<Ctx>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response> {
+    debug!("{:<12} - mw_rate_limit", "MIDDLEWARE");
+
+    let key = match ctx {
+        Some(ctx) if limiter.per_user => RateKey::User(ctx.user_id()),
+        _ => RateKey::Ip(client_ip(&req)),
+    };
+    limiter.take(key)?;
+
+    Ok(next.run(req).await)
+}
+
+/// The peer address of the connection.
+/// Note: Behind a proxy, all the clients share the proxy IP (`X-Forwarded-For`
+///       is not trusted, as any client could set it).
+fn client_ip<B>(req: &Request<B>) -> IpAddr {
+    req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ci| ci.0.ip())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limiter_take() {
+        let limiter = RateLimiter::per_ip(RateLimit {
+            burst: 2,
+            period: Duration::from_secs(60),
+        });
+        let ip_1 = RateKey::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let user_1 = RateKey::User(1);
+
+        assert!(limiter.take(ip_1).is_ok());
+        assert!(limiter.take(ip_1).is_ok());
+        // One token every 30s.
+        assert!(matches!(
+            limiter.take(ip_1),
+            Err(Error::RateLimited {
+                retry_after_sec: 30
+            })
+        ));
+        // Other keys have their own bucket.
+        assert!(limiter.take(user_1).is_ok());
+    }
+}
+
+// endregion: --- Tests
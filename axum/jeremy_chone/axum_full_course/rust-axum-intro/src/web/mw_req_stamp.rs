@@ -0,0 +1,87 @@
+use crate::{Error, Result};
+use async_trait::async_trait;
+use axum::extract::{FromRequestParts, MatchedPath};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+use time::OffsetDateTime;
+use tracing::{debug, field, info_span, Instrument};
+use uuid::Uuid;
+
+/// Request id header, accepted from the client (when a valid uuid)
+/// and always echoed back in the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Identity and arrival time of the request, set by the outermost middleware.
+#[derive(Debug, Clone)]
+pub struct ReqStamp {
+    pub uuid: Uuid,
+    pub time_in: OffsetDateTime,
+    /// Monotonic, for the request duration.
+    pub instant_in: Instant,
+}
+
+pub async fn mw_req_stamp<B>(mut req: Request<B>, next: Next<B>) -> Result<Response> {
+    // Keep the caller's id, so the request can be correlated across services.
+    let uuid = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .unwrap_or_else(Uuid::new_v4);
+
+    // `user_id` is recorded by `mw_ctx_resolver`, once resolved.
+    let span = info_span!(
+        "request",
+        %uuid,
+        method = %req.method(),
+        route = field::Empty,
+        user_id = field::Empty,
+    );
+    if let Some(matched_path) = req.extensions().get::<MatchedPath>() {
+        span.record("route", matched_path.as_str());
+    }
+
+    let stamp = ReqStamp {
+        uuid,
+        time_in: OffsetDateTime::now_utc(),
+        instant_in: Instant::now(),
+    };
+
+    req.extensions_mut().insert(stamp);
+
+    async move {
+        debug!("{:<12} - mw_req_stamp", "MIDDLEWARE");
+
+        let mut res = next.run(req).await;
+
+        if let Ok(request_id) = HeaderValue::from_str(&uuid.to_string()) {
+            res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+        }
+
+        Ok(res)
+    }
+    .instrument(span)
+    .await
+}
+
+// region:    --- ReqStamp Extractor
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ReqStamp {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        debug!("{:<12} - ReqStamp", "EXTRACTOR");
+
+        parts
+            .extensions
+            .get::<ReqStamp>()
+            .cloned()
+            .ok_or(Error::ReqStampNotInRequestExt)
+    }
+}
+
+// endregion: --- ReqStamp Extractor
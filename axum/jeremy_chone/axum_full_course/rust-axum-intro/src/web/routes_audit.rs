@@ -0,0 +1,41 @@
+use crate::ctx::Ctx;
+use crate::model::{AuditListParams, AuditPage, ModelController};
+use crate::Result;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::debug;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/audit", get(list_audit))
+        .with_state(mc)
+}
+
+// region: --- REST Handlers
+
+// e.g., `/api/audit?user_id=2&from=2024-01-01T00:00:00Z&limit=50`
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    params(AuditListParams),
+    responses(
+        (status = 200, body = AuditPage),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admins only.", body = ClientErrorBody),
+    )
+)]
+async fn list_audit(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<AuditListParams>,
+) -> Result<Json<AuditPage>> {
+    debug!("{:<12} - list_audit - {params:?}", "HANDLER");
+
+    let page = mc.list_audit(ctx, params).await?;
+
+    Ok(Json(page))
+}
+
+// endregion: --- REST Handlers
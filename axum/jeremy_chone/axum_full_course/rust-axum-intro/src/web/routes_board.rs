@@ -0,0 +1,433 @@
+//! Server rendered html board, on the same model as the json api.
+//!
+//! The forms post back to the `/board` routes, with the CSRF token (see `csrf`).
+//! On success, they redirect to the page (post/redirect/get). On a client error,
+//! the page is rendered again with the error message.
+//!
+//! Note: The templates are in `templates/`, compiled into the binary.
+//!       Their static assets are served from the `static_dir` (see `routes_static`).
+
+use crate::ctx::Ctx;
+use crate::error::ClientError;
+use crate::model::{
+    IfMatch, ModelController, TicketForCreate, TicketListParams, TicketPriority, TicketStatus,
+};
+use crate::web::csrf::{check_csrf, csrf_token};
+use crate::web::{remove_token_cookie, start_session, LoginClient};
+use crate::{Error, Result};
+use askama::Template;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Form, Router};
+use serde::Deserialize;
+use std::collections::HashMap;
+use time::{OffsetDateTime, UtcOffset};
+use tower_cookies::Cookies;
+use tracing::debug;
+
+const BOARD_PATH: &str = "/board";
+const LOGIN_PATH: &str = "/board/login";
+const PAGE_SIZE: u64 = 50;
+
+/// The login and logout forms (under the login rate limit).
+pub fn routes_login(mc: ModelController) -> Router {
+    Router::new()
+        .route("/board/login", get(login_page).post(login_submit))
+        .route("/board/logout", post(logout_submit))
+        .with_state(mc)
+}
+
+/// The board pages, redirecting to the login page when not logged in.
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/board", get(board_page))
+        .route("/board/tickets", post(ticket_create_submit))
+        .route("/board/tickets/:id/delete", post(ticket_delete_submit))
+        .with_state(mc)
+}
+
+// region:    --- Templates
+
+#[derive(Template)]
+#[template(path = "login.html")]
+struct LoginPage {
+    error: Option<String>,
+    csrf: String,
+    username: String,
+}
+
+#[derive(Template)]
+#[template(path = "board.html")]
+struct BoardPage {
+    error: Option<String>,
+    csrf: String,
+    username: String,
+    role: String,
+    filters: BoardFilters,
+    status_options: Vec<SelectOption>,
+    priority_options: Vec<SelectOption>,
+    label_options: Vec<LabelOption>,
+    rows: Vec<TicketRow>,
+    total: u64,
+    next_cursor: Option<String>,
+}
+
+struct SelectOption {
+    value: String,
+    selected: bool,
+}
+
+struct LabelOption {
+    value: u64,
+    name: String,
+    selected: bool,
+}
+
+struct TicketRow {
+    id: u64,
+    title: String,
+    status: String,
+    priority: String,
+    labels: Vec<String>,
+    mtime: String,
+}
+
+// endregion: --- Templates
+
+// region:    --- Form Types
+
+/// The board query, from the filters form (an empty value is no filter).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BoardFilters {
+    status: String,
+    priority: String,
+    /// A label id.
+    label: String,
+    title_contains: String,
+    cursor: String,
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    csrf: String,
+    username: String,
+    pwd: String,
+}
+
+#[derive(Deserialize)]
+struct TicketCreateForm {
+    csrf: String,
+    title: String,
+    description: String,
+    priority: String,
+}
+
+#[derive(Deserialize)]
+struct CsrfForm {
+    csrf: String,
+}
+
+// endregion: --- Form Types
+
+// region:    --- Login Handlers
+
+async fn login_page(ctx: Option<Ctx>, cookies: Cookies) -> Result<Response> {
+    debug!("{:<12} - login_page", "HANDLER");
+
+    if ctx.is_some() {
+        return Ok(Redirect::to(BOARD_PATH).into_response());
+    }
+
+    render_login(&cookies, StatusCode::OK, None, String::new())
+}
+
+async fn login_submit(
+    State(mc): State<ModelController>,
+    cookies: Cookies,
+    client: LoginClient,
+    Form(form): Form<LoginForm>,
+) -> Result<Response> {
+    debug!("{:<12} - login_submit", "HANDLER");
+
+    check_csrf(&cookies, &form.csrf)?;
+
+    match mc.login_user(&form.username, form.pwd).await {
+        Ok(user) => {
+            start_session(&mc, &cookies, user.id, client).await?;
+            Ok(Redirect::to(BOARD_PATH).into_response())
+        }
+        Err(ex) => {
+            let (status, error) = page_error(ex)?;
+            render_login(&cookies, status, Some(error), form.username)
+        }
+    }
+}
+
+async fn logout_submit(
+    State(mc): State<ModelController>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+    Form(form): Form<CsrfForm>,
+) -> Result<Response> {
+    debug!("{:<12} - logout_submit", "HANDLER");
+
+    check_csrf(&cookies, &form.csrf)?;
+
+    if let Some(ctx) = ctx {
+        mc.end_session(&ctx).await?;
+    }
+    remove_token_cookie(&cookies);
+
+    Ok(Redirect::to(LOGIN_PATH).into_response())
+}
+
+// endregion: --- Login Handlers
+
+// region:    --- Board Handlers
+
+async fn board_page(
+    State(mc): State<ModelController>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+    Query(filters): Query<BoardFilters>,
+) -> Result<Response> {
+    debug!("{:<12} - board_page - {filters:?}", "HANDLER");
+
+    let Some(ctx) = ctx else {
+        return Ok(Redirect::to(LOGIN_PATH).into_response());
+    };
+
+    render_board(&mc, ctx, &cookies, filters, None).await
+}
+
+async fn ticket_create_submit(
+    State(mc): State<ModelController>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+    Form(form): Form<TicketCreateForm>,
+) -> Result<Response> {
+    debug!("{:<12} - ticket_create_submit", "HANDLER");
+
+    check_csrf(&cookies, &form.csrf)?;
+    let Some(ctx) = ctx else {
+        return Ok(Redirect::to(LOGIN_PATH).into_response());
+    };
+
+    let ticket_fc = TicketForCreate {
+        title: form.title,
+        description: non_empty(form.description),
+        status: None,
+        priority: non_empty(form.priority),
+        assignee: None,
+        labels: None,
+    };
+
+    match mc.create_ticket(ctx.clone(), ticket_fc).await {
+        Ok(_) => Ok(Redirect::to(BOARD_PATH).into_response()),
+        Err(ex) => render_board(&mc, ctx, &cookies, BoardFilters::default(), Some(ex)).await,
+    }
+}
+
+async fn ticket_delete_submit(
+    State(mc): State<ModelController>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+    Path(id): Path<u64>,
+    Form(form): Form<CsrfForm>,
+) -> Result<Response> {
+    debug!("{:<12} - ticket_delete_submit", "HANDLER");
+
+    check_csrf(&cookies, &form.csrf)?;
+    let Some(ctx) = ctx else {
+        return Ok(Redirect::to(LOGIN_PATH).into_response());
+    };
+
+    match mc.delete_ticket(ctx.clone(), id, IfMatch::Any).await {
+        Ok(_) => Ok(Redirect::to(BOARD_PATH).into_response()),
+        Err(ex) => render_board(&mc, ctx, &cookies, BoardFilters::default(), Some(ex)).await,
+    }
+}
+
+// endregion: --- Board Handlers
+
+// region:    --- Render
+
+fn render_login(
+    cookies: &Cookies,
+    status: StatusCode,
+    error: Option<String>,
+    username: String,
+) -> Result<Response> {
+    let page = LoginPage {
+        error,
+        csrf: csrf_token(cookies),
+        username,
+    };
+
+    render(status, &page)
+}
+
+/// The board page, with the `error` of the failed form if any.
+async fn render_board(
+    mc: &ModelController,
+    ctx: Ctx,
+    cookies: &Cookies,
+    filters: BoardFilters,
+    error: Option<Error>,
+) -> Result<Response> {
+    let (mut status, mut error) = match error {
+        Some(ex) => page_error(ex).map(|(status, error)| (status, Some(error)))?,
+        None => (StatusCode::OK, None),
+    };
+
+    let params = TicketListParams {
+        status: non_empty(filters.status.clone()),
+        priority: non_empty(filters.priority.clone()),
+        label: filters.label.parse().ok(),
+        title_contains: non_empty(filters.title_contains.clone()),
+        order_by: Some("-id".to_string()),
+        limit: Some(PAGE_SIZE),
+        cursor: non_empty(filters.cursor.clone()),
+        ..Default::default()
+    };
+    // A bad filter (e.g., hand edited url) is shown, not failed.
+    let page = match mc.list_tickets(ctx.clone(), params).await {
+        Ok(page) => Some(page),
+        Err(ex) => {
+            (status, error) = page_error(ex).map(|(status, error)| (status, Some(error)))?;
+            None
+        }
+    };
+    let (tickets, total, next_cursor) = page
+        .map(|page| (page.data, page.total, page.next_cursor))
+        .unwrap_or_default();
+
+    let labels = mc.list_labels(ctx.clone()).await?;
+    let label_names: HashMap<u64, &str> = labels.iter().map(|l| (l.id, l.name.as_str())).collect();
+    let rows = tickets
+        .into_iter()
+        .map(|ticket| TicketRow {
+            id: ticket.id,
+            labels: ticket
+                .labels
+                .iter()
+                .filter_map(|id| label_names.get(id).map(|name| name.to_string()))
+                .collect(),
+            title: ticket.title,
+            status: ticket.status.as_ref().to_string(),
+            priority: ticket.priority.as_ref().to_string(),
+            mtime: format_time(ticket.mtime),
+        })
+        .collect();
+
+    let username = mc
+        .get_user(ctx.user_id())
+        .await?
+        .map(|user| user.username)
+        .unwrap_or_default();
+
+    let page = BoardPage {
+        error,
+        csrf: csrf_token(cookies),
+        username,
+        role: ctx.role().as_ref().to_string(),
+        status_options: select_options(
+            [
+                TicketStatus::Open,
+                TicketStatus::InProgress,
+                TicketStatus::Closed,
+            ],
+            &filters.status,
+        ),
+        priority_options: select_options(
+            [
+                TicketPriority::Low,
+                TicketPriority::Medium,
+                TicketPriority::High,
+            ],
+            &filters.priority,
+        ),
+        label_options: labels
+            .iter()
+            .map(|label| LabelOption {
+                value: label.id,
+                name: label.name.clone(),
+                selected: filters.label == label.id.to_string(),
+            })
+            .collect(),
+        filters,
+        rows,
+        total,
+        next_cursor,
+    };
+
+    render(status, &page)
+}
+
+fn render(status: StatusCode, page: &impl Template) -> Result<Response> {
+    let html = page.render().map_err(|ex| Error::TemplateRenderFail {
+        cause: ex.to_string(),
+    })?;
+
+    Ok((status, Html(html)).into_response())
+}
+
+/// The status and message of a client error, to show in the page.
+/// The server errors are returned, for the main response mapper.
+fn page_error(ex: Error) -> Result<(StatusCode, String)> {
+    let (status, client_error) = ex.client_status_and_error();
+    if status.is_server_error() {
+        return Err(ex);
+    }
+    debug!("{:<12} - page_error - {ex:?}", "HANDLER");
+
+    let message = match client_error {
+        ClientError::LOGIN_FAIL => "Wrong username or password.".to_string(),
+        ClientError::LOGIN_LOCKED => "Too many failed logins, try again later.".to_string(),
+        ClientError::RATE_LIMITED => "Too many requests, try again later.".to_string(),
+        ClientError::ACCESS_DENIED => "You are not allowed to do that.".to_string(),
+        _ => format!("Invalid request ({}).", ex.as_ref()),
+    };
+
+    Ok((status, message))
+}
+
+// endregion: --- Render
+
+// region:    --- Support
+
+fn select_options(
+    values: impl IntoIterator<Item = impl AsRef<str>>,
+    selected: &str,
+) -> Vec<SelectOption> {
+    values
+        .into_iter()
+        .map(|value| SelectOption {
+            selected: value.as_ref() == selected,
+            value: value.as_ref().to_string(),
+        })
+        .collect()
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// e.g., `2024-01-31 17:05 UTC`
+fn format_time(time: OffsetDateTime) -> String {
+    let time = time.to_offset(UtcOffset::UTC);
+
+    format!(
+        "{} {:02}:{:02} UTC",
+        time.date(),
+        time.hour(),
+        time.minute()
+    )
+}
+
+// endregion: --- Support
@@ -0,0 +1,156 @@
+use crate::ctx::Ctx;
+use crate::model::{Comment, CommentForCreate, CommentForUpdate, ModelController};
+use crate::Result;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::debug;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route(
+            "/tickets/:id/comments",
+            get(list_comments).post(create_comment),
+        )
+        .route(
+            "/tickets/:id/comments/:comment_id",
+            get(get_comment)
+                .patch(update_comment)
+                .delete(delete_comment),
+        )
+        .with_state(mc)
+}
+
+// region: --- REST Handlers
+
+#[utoipa::path(
+    post,
+    path = "/api/tickets/{id}/comments",
+    tag = "comments",
+    params(("id" = u64, Path, description = "Ticket id")),
+    request_body = CommentForCreate,
+    responses(
+        (status = 200, body = Comment),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`", body = ClientErrorBody),
+    )
+)]
+async fn create_comment(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    Json(comment_fc): Json<CommentForCreate>,
+) -> Result<Json<Comment>> {
+    debug!("{:<12} - create_comment", "HANDLER");
+
+    let comment = mc.create_comment(ctx, id, comment_fc).await?;
+
+    Ok(Json(comment))
+}
+
+/// All the comments of the ticket, by id (threads by `parent_id`).
+#[utoipa::path(
+    get,
+    path = "/api/tickets/{id}/comments",
+    tag = "comments",
+    params(("id" = u64, Path, description = "Ticket id")),
+    responses(
+        (status = 200, body = [Comment]),
+        (status = 400, description = "`INVALID_PARAMS`, ticket not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`", body = ClientErrorBody),
+    )
+)]
+async fn list_comments(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Json<Vec<Comment>>> {
+    debug!("{:<12} - list_comments", "HANDLER");
+
+    let comments = mc.list_comments(ctx, id).await?;
+
+    Ok(Json(comments))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tickets/{id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("id" = u64, Path, description = "Ticket id"),
+        ("comment_id" = u64, Path, description = "Comment id"),
+    ),
+    responses(
+        (status = 200, body = Comment),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`", body = ClientErrorBody),
+    )
+)]
+async fn get_comment(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path((id, comment_id)): Path<(u64, u64)>,
+) -> Result<Json<Comment>> {
+    debug!("{:<12} - get_comment", "HANDLER");
+
+    let comment = mc.get_comment(ctx, id, comment_id).await?;
+
+    Ok(Json(comment))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/tickets/{id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("id" = u64, Path, description = "Ticket id"),
+        ("comment_id" = u64, Path, description = "Comment id"),
+    ),
+    request_body = CommentForUpdate,
+    responses(
+        (status = 200, body = Comment),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, author only.", body = ClientErrorBody),
+    )
+)]
+async fn update_comment(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path((id, comment_id)): Path<(u64, u64)>,
+    Json(comment_fu): Json<CommentForUpdate>,
+) -> Result<Json<Comment>> {
+    debug!("{:<12} - update_comment", "HANDLER");
+
+    let comment = mc.update_comment(ctx, id, comment_id, comment_fu).await?;
+
+    Ok(Json(comment))
+}
+
+/// Deletes the comment with all its replies.
+#[utoipa::path(
+    delete,
+    path = "/api/tickets/{id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("id" = u64, Path, description = "Ticket id"),
+        ("comment_id" = u64, Path, description = "Comment id"),
+    ),
+    responses(
+        (status = 200, body = Comment),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, author or admin only.", body = ClientErrorBody),
+    )
+)]
+async fn delete_comment(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path((id, comment_id)): Path<(u64, u64)>,
+) -> Result<Json<Comment>> {
+    debug!("{:<12} - delete_comment", "HANDLER");
+
+    let comment = mc.delete_comment(ctx, id, comment_id).await?;
+
+    Ok(Json(comment))
+}
+
+// endregion: --- REST Handlers
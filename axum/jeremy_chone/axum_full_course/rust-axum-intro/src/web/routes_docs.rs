@@ -0,0 +1,326 @@
+//! OpenAPI document of the routes, and a Redoc page to browse it.
+//!
+//! Each handler documents itself with `#[utoipa::path]`, and is listed in
+//! `ApiDoc`. The tests below fail on a route missing from the document.
+
+use crate::config::config;
+use crate::ctx::Role;
+use crate::error::{ClientError, ClientErrorBody, ClientErrorDetail};
+use crate::model::user::UserForRegister;
+use crate::model::{
+    AuditAction, AuditEntry, AuditPage, Comment, CommentForCreate, CommentForUpdate, Label,
+    LabelForCreate, LabelForUpdate, Session, Ticket, TicketEventKind, TicketForCreate,
+    TicketForReplace, TicketForUpdate, TicketImportReport, TicketImportRowError, TicketPage,
+    TicketPriority, TicketSearchHit, TicketSearchPage, TicketStatus, Webhook, WebhookAttempt,
+    WebhookCreated, WebhookForCreate, WebhookForUpdate, WebhookPayload,
+};
+use crate::web::routes_health::HealthStatus;
+use crate::web::routes_login::LoginPayload;
+use crate::web::routes_sessions::SessionsRevoked;
+use crate::web::routes_users::RolePayload;
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::debug;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::openapi::OpenApi as OpenApiDoc;
+use utoipa::{Modify, OpenApi};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/api-docs", get(api_docs_page))
+        .route("/api-docs/openapi.json", get(openapi_json))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rust-axum-intro", description = "Ticket API."),
+    paths(
+        crate::handler_hello,
+        crate::handler_hello2,
+        crate::web::routes_docs::api_docs_page,
+        crate::web::routes_docs::openapi_json,
+        crate::web::routes_login::api_login,
+        crate::web::routes_login::api_logout,
+        crate::web::routes_login::api_register,
+        crate::web::routes_metrics::get_metrics,
+        crate::web::routes_health::get_healthz,
+        crate::web::routes_health::get_readyz,
+        crate::web::routes_tickets::create_ticket,
+        crate::web::routes_tickets::list_tickets,
+        crate::web::routes_tickets::get_ticket,
+        crate::web::routes_tickets::replace_ticket,
+        crate::web::routes_tickets::update_ticket,
+        crate::web::routes_tickets::delete_ticket,
+        crate::web::routes_tickets::delete_ticket_legacy,
+        crate::web::routes_tickets::search_tickets,
+        crate::web::routes_tickets::import_tickets,
+        crate::web::routes_tickets::export_tickets,
+        crate::web::routes_tickets::list_trash,
+        crate::web::routes_tickets::restore_ticket,
+        crate::web::routes_comments::create_comment,
+        crate::web::routes_comments::list_comments,
+        crate::web::routes_comments::get_comment,
+        crate::web::routes_comments::update_comment,
+        crate::web::routes_comments::delete_comment,
+        crate::web::routes_labels::create_label,
+        crate::web::routes_labels::list_labels,
+        crate::web::routes_labels::update_label,
+        crate::web::routes_labels::delete_label,
+        crate::web::routes_ticket_events::ticket_events_sse,
+        crate::web::routes_ticket_events::ticket_events_ws,
+        crate::web::routes_users::set_user_role,
+        crate::web::routes_audit::list_audit,
+        crate::web::routes_webhooks::create_webhook,
+        crate::web::routes_webhooks::list_webhooks,
+        crate::web::routes_webhooks::get_webhook,
+        crate::web::routes_webhooks::update_webhook,
+        crate::web::routes_webhooks::delete_webhook,
+        crate::web::routes_webhooks::list_webhook_attempts,
+        crate::web::routes_sessions::list_sessions,
+        crate::web::routes_sessions::revoke_session,
+        crate::web::routes_sessions::revoke_all_sessions,
+    ),
+    components(schemas(
+        Ticket,
+        TicketStatus,
+        TicketPriority,
+        TicketForCreate,
+        TicketForReplace,
+        TicketForUpdate,
+        TicketPage,
+        TicketSearchHit,
+        TicketSearchPage,
+        TicketImportReport,
+        TicketImportRowError,
+        Comment,
+        CommentForCreate,
+        CommentForUpdate,
+        Label,
+        LabelForCreate,
+        LabelForUpdate,
+        AuditEntry,
+        AuditAction,
+        AuditPage,
+        Webhook,
+        WebhookCreated,
+        WebhookForCreate,
+        WebhookForUpdate,
+        WebhookAttempt,
+        WebhookPayload,
+        TicketEventKind,
+        LoginPayload,
+        Session,
+        SessionsRevoked,
+        UserForRegister,
+        RolePayload,
+        Role,
+        HealthStatus,
+        ClientErrorBody,
+        ClientErrorDetail,
+        ClientError,
+    )),
+    modifiers(&AuthCookie),
+    security(("auth_cookie" = [])),
+    tags(
+        (name = "auth", description = "Login, logout, and register."),
+        (name = "sessions", description = "Login sessions of the user, and their revocation."),
+        (name = "tickets", description = "Tickets CRUD, with the trash."),
+        (name = "comments", description = "Threaded comments of the tickets."),
+        (name = "labels", description = "Labels to set on the tickets."),
+        (name = "events", description = "Live feeds of the ticket changes."),
+        (name = "users", description = "User admin."),
+        (name = "audit", description = "Audit trail of the ticket changes."),
+        (name = "webhooks", description = "Outgoing webhooks of the ticket events (admin)."),
+        (name = "misc"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The auth token cookie set by login (its name is from the config).
+struct AuthCookie;
+
+impl Modify for AuthCookie {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let cookie = ApiKey::Cookie(ApiKeyValue::new(config().auth_cookie_name.as_str()));
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme("auth_cookie", SecurityScheme::ApiKey(cookie));
+    }
+}
+
+// region:    --- Handlers
+
+/// The OpenAPI 3 document.
+#[utoipa::path(
+    get,
+    path = "/api-docs/openapi.json",
+    tag = "misc",
+    security(()),
+    responses((status = 200, content_type = "application/json", body = Object)),
+)]
+async fn openapi_json() -> Json<OpenApiDoc> {
+    debug!("{:<12} - openapi_json", "HANDLER");
+
+    Json(ApiDoc::openapi())
+}
+
+/// Redoc page of the OpenAPI document.
+#[utoipa::path(
+    get,
+    path = "/api-docs",
+    tag = "misc",
+    security(()),
+    responses((status = 200, content_type = "text/html", body = String)),
+)]
+async fn api_docs_page() -> Html<&'static str> {
+    debug!("{:<12} - api_docs_page", "HANDLER");
+
+    Html(API_DOCS_PAGE)
+}
+
+// Note: Redoc itself is loaded from its CDN, only this page is embedded.
+const API_DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>rust-axum-intro - API</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <redoc spec-url="/api-docs/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.1.5/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+// endregion: --- Handlers
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_regex::regex;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+    use utoipa::openapi::PathItemType;
+
+    /// The source files with `.route(`s, and the path they are nested under in `app`.
+    /// A new routes file must be added here.
+    const ROUTE_FILES: &[(&str, &str)] = &[
+        ("lib.rs", ""),
+        ("web/routes_docs.rs", ""),
+        ("web/routes_login.rs", ""),
+        ("web/routes_metrics.rs", ""),
+        ("web/routes_health.rs", ""),
+        ("web/routes_audit.rs", "/api"),
+        ("web/routes_comments.rs", "/api"),
+        ("web/routes_labels.rs", "/api"),
+        ("web/routes_ticket_events.rs", "/api"),
+        ("web/routes_tickets.rs", "/api"),
+        ("web/routes_users.rs", "/api"),
+        ("web/routes_webhooks.rs", "/api"),
+        ("web/routes_sessions.rs", "/api"),
+    ];
+
+    /// The source files with html page routes, not part of the api doc.
+    const HTML_ROUTE_FILES: &[&str] = &["web/routes_board.rs"];
+
+    /// The (method, path) of all the routes, in the OpenAPI path syntax.
+    fn source_routes() -> BTreeSet<(String, String)> {
+        let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut routes = BTreeSet::new();
+
+        for (file, prefix) in ROUTE_FILES {
+            let content = fs::read_to_string(src_dir.join(file)).unwrap();
+            let content = content.split("#[cfg(test)]").next().unwrap_or_default();
+
+            for route in content.split(".route(").skip(1) {
+                // The route args end before the next statement or fn end.
+                let end = [route.find(';'), route.find("\n}")]
+                    .into_iter()
+                    .flatten()
+                    .min()
+                    .unwrap_or(route.len());
+                let route = &route[..end];
+
+                let path = regex!(r#"^\s*"([^"]+)""#).captures(route).unwrap()[1].to_string();
+                let path = regex!(r":(\w+)").replace_all(&path, "{$1}");
+                for method in regex!(r"\b(get|post|put|patch|delete)\(").captures_iter(route) {
+                    routes.insert((method[1].to_string(), format!("{prefix}{path}")));
+                }
+            }
+        }
+
+        routes
+    }
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let openapi = ApiDoc::openapi();
+        let mut routes = BTreeSet::new();
+
+        for (path, item) in openapi.paths.paths {
+            for method in item.operations.keys() {
+                let method = match method {
+                    PathItemType::Get => "get",
+                    PathItemType::Post => "post",
+                    PathItemType::Put => "put",
+                    PathItemType::Patch => "patch",
+                    PathItemType::Delete => "delete",
+                    _ => panic!("unexpected method on {path}"),
+                };
+                routes.insert((method.to_string(), path.clone()));
+            }
+        }
+
+        routes
+    }
+
+    #[test]
+    fn test_openapi_all_route_files_listed() {
+        fn visit(dir: &Path, files: &mut Vec<String>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, files);
+                } else if fs::read_to_string(&path).unwrap().contains(".route(") {
+                    files.push(path.display().to_string());
+                }
+            }
+        }
+        let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut files = Vec::new();
+        visit(&src_dir, &mut files);
+
+        for file in files {
+            let listed = ROUTE_FILES
+                .iter()
+                .map(|(listed, _)| listed)
+                .chain(HTML_ROUTE_FILES)
+                .any(|listed| src_dir.join(listed).display().to_string() == file);
+            assert!(listed, "{file} has routes, add it to ROUTE_FILES");
+        }
+    }
+
+    #[test]
+    fn test_openapi_documents_all_routes() {
+        let source_routes = source_routes();
+        let documented_routes = documented_routes();
+
+        let undocumented: Vec<_> = source_routes.difference(&documented_routes).collect();
+        assert!(
+            undocumented.is_empty(),
+            "routes without `#[utoipa::path]` in `ApiDoc`: {undocumented:?}"
+        );
+        let unknown: Vec<_> = documented_routes.difference(&source_routes).collect();
+        assert!(
+            unknown.is_empty(),
+            "documented routes not found: {unknown:?}"
+        );
+    }
+}
+
+// endregion: --- Tests
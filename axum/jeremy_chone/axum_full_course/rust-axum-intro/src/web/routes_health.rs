@@ -0,0 +1,75 @@
+use crate::model::ModelController;
+use crate::shutdown::Shutdown;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+#[derive(Clone)]
+struct HealthState {
+    mc: ModelController,
+    shutdown: Shutdown,
+}
+
+/// Probes for the orchestrator, without auth nor rate limit.
+pub fn routes(mc: ModelController, shutdown: Shutdown) -> Router {
+    Router::new()
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .with_state(HealthState { mc, shutdown })
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthStatus {
+    /// `ok`, `ready`, `draining` or `store_unavailable`
+    pub status: &'static str,
+}
+
+/// Liveness, ok as long as the process serves requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "misc",
+    security(()),
+    responses(
+        (status = 200, body = HealthStatus),
+    )
+)]
+async fn get_healthz() -> Json<HealthStatus> {
+    debug!("{:<12} - get_healthz", "HANDLER");
+
+    Json(HealthStatus { status: "ok" })
+}
+
+/// Readiness, not ready while draining on shutdown, or when the store fails.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "misc",
+    security(()),
+    responses(
+        (status = 200, body = HealthStatus),
+        (status = 503, description = "`draining` or `store_unavailable`", body = HealthStatus),
+    )
+)]
+async fn get_readyz(State(state): State<HealthState>) -> impl IntoResponse {
+    debug!("{:<12} - get_readyz", "HANDLER");
+
+    if state.shutdown.is_draining() {
+        let status = HealthStatus { status: "draining" };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(status));
+    }
+    if let Err(ex) = state.mc.check_store().await {
+        warn!("{:<12} - store unavailable - {ex:?}", "READYZ");
+        let status = HealthStatus {
+            status: "store_unavailable",
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(status));
+    }
+
+    (StatusCode::OK, Json(HealthStatus { status: "ready" }))
+}
@@ -0,0 +1,107 @@
+use crate::ctx::Ctx;
+use crate::model::{Label, LabelForCreate, LabelForUpdate, ModelController};
+use crate::Result;
+use axum::extract::{Path, State};
+use axum::routing::{get, patch};
+use axum::{Json, Router};
+use tracing::debug;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/labels", get(list_labels).post(create_label))
+        .route("/labels/:id", patch(update_label).delete(delete_label))
+        .with_state(mc)
+}
+
+// region: --- REST Handlers
+
+#[utoipa::path(
+    post,
+    path = "/api/labels",
+    tag = "labels",
+    request_body = LabelForCreate,
+    responses(
+        (status = 200, body = Label),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, not for viewers.", body = ClientErrorBody),
+        (status = 409, description = "`LABEL_NAME_TAKEN`", body = ClientErrorBody),
+    )
+)]
+async fn create_label(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Json(label_fc): Json<LabelForCreate>,
+) -> Result<Json<Label>> {
+    debug!("{:<12} - create_label", "HANDLER");
+
+    let label = mc.create_label(ctx, label_fc).await?;
+
+    Ok(Json(label))
+}
+
+/// All the labels, by name.
+#[utoipa::path(
+    get,
+    path = "/api/labels",
+    tag = "labels",
+    responses((status = 200, body = [Label])),
+)]
+async fn list_labels(State(mc): State<ModelController>, ctx: Ctx) -> Result<Json<Vec<Label>>> {
+    debug!("{:<12} - list_labels", "HANDLER");
+
+    let labels = mc.list_labels(ctx).await?;
+
+    Ok(Json(labels))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/labels/{id}",
+    tag = "labels",
+    params(("id" = u64, Path, description = "Label id")),
+    request_body = LabelForUpdate,
+    responses(
+        (status = 200, body = Label),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admins only.", body = ClientErrorBody),
+        (status = 409, description = "`LABEL_NAME_TAKEN`", body = ClientErrorBody),
+    )
+)]
+async fn update_label(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    Json(label_fu): Json<LabelForUpdate>,
+) -> Result<Json<Label>> {
+    debug!("{:<12} - update_label", "HANDLER");
+
+    let label = mc.update_label(ctx, id, label_fu).await?;
+
+    Ok(Json(label))
+}
+
+/// Also removes the label from the tickets having it.
+#[utoipa::path(
+    delete,
+    path = "/api/labels/{id}",
+    tag = "labels",
+    params(("id" = u64, Path, description = "Label id")),
+    responses(
+        (status = 200, body = Label),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admins only.", body = ClientErrorBody),
+    )
+)]
+async fn delete_label(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Json<Label>> {
+    debug!("{:<12} - delete_label", "HANDLER");
+
+    let label = mc.delete_label(ctx, id).await?;
+
+    Ok(Json(label))
+}
+
+// endregion: --- REST Handlers
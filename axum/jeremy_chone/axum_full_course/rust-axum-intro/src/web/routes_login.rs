@@ -1,25 +1,49 @@
-use crate::web::AUTH_TOKEN;
-use crate::{Error, Result};
+use crate::ctx::Ctx;
+use crate::model::user::UserForRegister;
+use crate::model::ModelController;
+use crate::web::{remove_token_cookie, start_session, LoginClient};
+use crate::Result;
+use axum::extract::State;
 use axum::routing::post;
 use axum::{Json, Router};
 use serde::Deserialize;
 use serde_json::{json, Value};
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
+use tracing::debug;
+use utoipa::ToSchema;
 
-pub fn routes() -> Router {
-    Router::new().route("/api/login", post(api_login))
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/api/login", post(api_login))
+        .route("/api/logout", post(api_logout))
+        .route("/api/register", post(api_register))
+        .with_state(mc)
 }
 
-async fn api_login(cookies: Cookies, payload: Json<LoginPayload>) -> Result<Json<Value>> {
-    println!("->> {:<12} - api_login", "HANDLER");
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    request_body = LoginPayload,
+    responses(
+        (status = 200, description = "Logged in, starts a session and sets its auth cookie.", body = Object,
+            example = json!({ "result": { "success": true } })),
+        (status = 403, description = "`LOGIN_FAIL`", body = ClientErrorBody),
+        (status = 429, description = "`LOGIN_LOCKED` or `RATE_LIMITED`, see `Retry-After`.",
+            body = ClientErrorBody),
+    )
+)]
+async fn api_login(
+    State(mc): State<ModelController>,
+    cookies: Cookies,
+    client: LoginClient,
+    Json(payload): Json<LoginPayload>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_login", "HANDLER");
 
-    // Todo: Implement real db/auth logic.
-    if payload.username != "demo1" || payload.pwd != "welcome" {
-        return Err(Error::LoginFail);
-    }
+    let user = mc.login_user(&payload.username, payload.pwd).await?;
 
-    // FIXME: Implement real auth-token generation/signature.
-    cookies.add(Cookie::new(AUTH_TOKEN, "user-1.exp.sign"));
+    start_session(&mc, &cookies, user.id, client).await?;
 
     // Create the success body.
     let body = Json(json!({
@@ -31,8 +55,68 @@ async fn api_login(cookies: Cookies, payload: Json<LoginPayload>) -> Result<Json
     Ok(body)
 }
 
-#[derive(Debug, Deserialize)]
-struct LoginPayload {
+#[utoipa::path(
+    post,
+    path = "/api/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Ends the session, and removes the auth cookie.", body = Object,
+            example = json!({ "result": { "success": true } })),
+    )
+)]
+async fn api_logout(
+    State(mc): State<ModelController>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_logout", "HANDLER");
+
+    if let Some(ctx) = ctx {
+        mc.end_session(&ctx).await?;
+    }
+    remove_token_cookie(&cookies);
+
+    let body = Json(json!({
+        "result" : {
+            "success": true
+        }
+    }));
+
+    Ok(body)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/register",
+    tag = "auth",
+    request_body = UserForRegister,
+    responses(
+        (status = 200, description = "Registered (not logged in).", body = Object,
+            example = json!({ "result": { "success": true, "user_id": 1 } })),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 409, description = "`USERNAME_TAKEN`", body = ClientErrorBody),
+    )
+)]
+async fn api_register(
+    State(mc): State<ModelController>,
+    Json(user_fr): Json<UserForRegister>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - api_register", "HANDLER");
+
+    let user = mc.register_user(user_fr).await?;
+
+    let body = Json(json!({
+        "result" : {
+            "success": true,
+            "user_id": user.id,
+        }
+    }));
+
+    Ok(body)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginPayload {
     username: String,
     pwd: String,
 }
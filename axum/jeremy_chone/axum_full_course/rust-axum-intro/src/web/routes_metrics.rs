@@ -0,0 +1,38 @@
+use crate::metrics;
+use crate::model::ModelController;
+use crate::Result;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use tracing::debug;
+
+const CONTENT_TYPE_PROMETHEUS: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(mc)
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "misc",
+    security(()),
+    responses(
+        (status = 200, content_type = "text/plain; version=0.0.4",
+            description = "Prometheus text format."),
+    )
+)]
+async fn get_metrics(State(mc): State<ModelController>) -> Result<impl IntoResponse> {
+    debug!("{:<12} - get_metrics", "HANDLER");
+
+    metrics::set_store_stats(mc.store_stats().await?);
+
+    Ok((
+        [(header::CONTENT_TYPE, CONTENT_TYPE_PROMETHEUS)],
+        metrics::render(),
+    ))
+}
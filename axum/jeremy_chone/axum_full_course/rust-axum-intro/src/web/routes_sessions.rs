@@ -0,0 +1,92 @@
+use crate::ctx::Ctx;
+use crate::model::{ModelController, Session};
+use crate::web::remove_token_cookie;
+use crate::Result;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Serialize;
+use tower_cookies::Cookies;
+use tracing::debug;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/sessions", get(list_sessions).delete(revoke_all_sessions))
+        .route("/sessions/:id", delete(revoke_session))
+        .with_state(mc)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionsRevoked {
+    pub revoked: u64,
+}
+
+// region: --- REST Handlers
+
+/// The live login sessions of the user (`current` is the one of this request).
+#[utoipa::path(
+    get,
+    path = "/api/sessions",
+    tag = "sessions",
+    responses((status = 200, body = [Session]))
+)]
+async fn list_sessions(State(mc): State<ModelController>, ctx: Ctx) -> Result<Json<Vec<Session>>> {
+    debug!("{:<12} - list_sessions", "HANDLER");
+
+    let sessions = mc.list_sessions(ctx).await?;
+
+    Ok(Json(sessions))
+}
+
+/// Revoke a session, its next request fails with `NO_AUTH`.
+///
+/// The users can revoke their own sessions, the admins any session.
+#[utoipa::path(
+    delete,
+    path = "/api/sessions/{id}",
+    tag = "sessions",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses(
+        (status = 200, description = "The revoked session.", body = Session),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+    )
+)]
+async fn revoke_session(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    cookies: Cookies,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Session>> {
+    debug!("{:<12} - revoke_session", "HANDLER");
+
+    let session = mc.revoke_session(ctx, id).await?;
+    if session.current {
+        remove_token_cookie(&cookies);
+    }
+
+    Ok(Json(session))
+}
+
+/// Log out everywhere, revoking all the sessions of the user (this one included).
+#[utoipa::path(
+    delete,
+    path = "/api/sessions",
+    tag = "sessions",
+    responses((status = 200, body = SessionsRevoked))
+)]
+async fn revoke_all_sessions(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    cookies: Cookies,
+) -> Result<Json<SessionsRevoked>> {
+    debug!("{:<12} - revoke_all_sessions", "HANDLER");
+
+    let revoked = mc.revoke_all_sessions(ctx).await?;
+    remove_token_cookie(&cookies);
+
+    Ok(Json(SessionsRevoked { revoked }))
+}
+
+// endregion: --- REST Handlers
@@ -0,0 +1,134 @@
+//! Ticket change feed, as Server-Sent Events and over a WebSocket.
+//!
+//! Both send the `TicketEvent`s visible to the user. A consumer too slow to
+//! keep up gets a final `lagged` message (with the number of skipped events)
+//! and is disconnected; it should re-fetch the tickets and subscribe again.
+
+use crate::ctx::Ctx;
+use crate::model::{ModelController, TicketFeed, TicketFeedItem};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use futures::stream::{self, Stream};
+use serde_json::json;
+use std::convert::Infallible;
+use tracing::debug;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/tickets/events", get(ticket_events_sse))
+        .route("/tickets/ws", get(ticket_events_ws))
+        .with_state(mc)
+}
+
+// region:    --- SSE
+
+/// Server-Sent Events of the ticket changes.
+#[utoipa::path(
+    get,
+    path = "/api/tickets/events",
+    tag = "events",
+    responses(
+        (status = 200, content_type = "text/event-stream",
+            description = "`event: created|updated|deleted|restored` with the `Ticket` as data, \
+                or a final `event: lagged` with `{\"skipped\": n}`."),
+    )
+)]
+async fn ticket_events_sse(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    debug!("{:<12} - ticket_events_sse", "HANDLER");
+
+    let feed = mc.subscribe_ticket_events(ctx);
+    let events = stream::unfold(feed, |mut feed| async move {
+        let event = sse_event(feed.recv().await?);
+        Some((Ok(event), feed))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// `event: created|updated|deleted` with the ticket as data,
+/// or `event: lagged` with `{"skipped": n}`.
+fn sse_event(item: TicketFeedItem) -> Event {
+    match item {
+        TicketFeedItem::Event(event) => Event::default()
+            .event(event.kind.as_ref())
+            .json_data(&event.ticket)
+            .unwrap_or_default(),
+        TicketFeedItem::Lagged { skipped } => Event::default()
+            .event("lagged")
+            .data(json!({ "skipped": skipped }).to_string()),
+    }
+}
+
+// endregion: --- SSE
+
+// region:    --- WebSocket
+
+/// WebSocket of the ticket changes.
+///
+/// Text messages `{"kind": "created", "ticket": {...}}`,
+/// or a final `{"kind": "lagged", "skipped": n}`.
+#[utoipa::path(
+    get,
+    path = "/api/tickets/ws",
+    tag = "events",
+    responses(
+        (status = 101, description = "Switching protocols."),
+    )
+)]
+async fn ticket_events_ws(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    ws: WebSocketUpgrade,
+) -> Response {
+    debug!("{:<12} - ticket_events_ws", "HANDLER");
+
+    let feed = mc.subscribe_ticket_events(ctx);
+
+    ws.on_upgrade(move |socket| send_feed(socket, feed))
+}
+
+/// Send the feed as text messages, `{"kind": "...", "ticket": {...}}`
+/// or `{"kind": "lagged", "skipped": n}`, until either side ends.
+async fn send_feed(mut socket: WebSocket, mut feed: TicketFeed) {
+    loop {
+        let item = tokio::select! {
+            item = feed.recv() => item,
+            // Incoming messages are ignored, only the close matters.
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let (msg, last) = match item {
+            Some(TicketFeedItem::Event(event)) => (json!(event), false),
+            Some(TicketFeedItem::Lagged { skipped }) => {
+                (json!({ "kind": "lagged", "skipped": skipped }), true)
+            }
+            None => break,
+        };
+
+        if socket.send(Message::Text(msg.to_string())).await.is_err() {
+            return;
+        }
+        if last {
+            break;
+        }
+    }
+
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: axum::extract::ws::close_code::AGAIN,
+            reason: "ticket feed ended".into(),
+        })))
+        .await;
+}
+
+// endregion: --- WebSocket
@@ -1,50 +1,464 @@
+use crate::config::config;
 use crate::ctx::Ctx;
-use crate::model::{ModelController, Ticket, TicketForCreate};
+use crate::model::{
+    BulkFormat, IfMatch, ModelController, Ticket, TicketExportParams, TicketForCreate,
+    TicketForReplace, TicketForUpdate, TicketImportParams, TicketListParams, TicketPage,
+    TicketSearchPage, TicketSearchParams,
+};
 use crate::Result;
-use axum::extract::{Path, State};
+use axum::body::{Bytes, StreamBody};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::routing::{delete, get, post};
 use axum::{Json, Router};
-use serde_json::json;
+use futures::StreamExt;
+use tracing::{debug, warn};
+
+/// For the import body (the default limit is 2 MB).
+const IMPORT_MAX_BYTES: usize = 16 * 1024 * 1024;
 
 pub fn routes(mc: ModelController) -> Router {
     Router::new()
         .route("/tickets", post(create_ticket).get(list_tickets))
-        .route("/ticket/:id", delete(delete_ticket))
+        .route("/tickets/search", get(search_tickets))
+        .route(
+            "/tickets/import",
+            post(import_tickets).layer(DefaultBodyLimit::max(IMPORT_MAX_BYTES)),
+        )
+        .route("/tickets/export", get(export_tickets))
+        .route("/tickets/trash", get(list_trash))
+        .route("/tickets/:id/restore", post(restore_ticket))
+        .route(
+            "/tickets/:id",
+            get(get_ticket)
+                .put(replace_ticket)
+                .patch(update_ticket)
+                .delete(delete_ticket),
+        )
+        // Kept for the existing clients.
+        .route("/ticket/:id", delete(delete_ticket_legacy))
         .with_state(mc)
 }
 
 // region: --- REST Handlers
 
+/// Header of the client chosen key, to safely retry a create (see `idempotency`).
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on the response of a create which was replayed for its key.
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+#[utoipa::path(
+    post,
+    path = "/api/tickets",
+    tag = "tickets",
+    params(
+        ("Idempotency-Key" = Option<String>, Header,
+            description = "Repeats with the same key (and body) return the first created ticket."),
+    ),
+    request_body = TicketForCreate,
+    responses(
+        (status = 200, description = "Created, or replayed with `Idempotent-Replayed: true`.",
+            body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 422, description = "`IDEMPOTENCY_KEY_REUSED`, for another body.", body = ClientErrorBody),
+    )
+)]
 async fn create_ticket(
     State(mc): State<ModelController>,
     ctx: Ctx,
+    headers: HeaderMap,
     Json(ticket_fc): Json<TicketForCreate>,
-) -> Result<Json<Ticket>> {
-    println!("->> {:<12} - create_ticket", "HANDLER");
+) -> Result<Response> {
+    debug!("{:<12} - create_ticket", "HANDLER");
+
+    let Some(key) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        let ticket = mc.create_ticket(ctx, ticket_fc).await?;
+        return Ok(ticket_response(ticket));
+    };
 
-    let ticket = mc.create_ticket(ctx, ticket_fc).await?;
+    let key = String::from_utf8_lossy(key.as_bytes()).into_owned();
+    let created = mc
+        .create_ticket_idempotent(ctx, key, ticket_fc, config().idempotency_ttl)
+        .await?;
 
-    Ok(Json(ticket))
+    let mut res = ticket_response(created.ticket);
+    if created.replayed {
+        res.headers_mut()
+            .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+
+    Ok(res)
 }
 
-async fn list_tickets(State(mc): State<ModelController>, ctx: Ctx) -> Result<Json<Vec<Ticket>>> {
-    println!("->> {:<12} - list_tickets", "HANDLER");
+// e.g., `/api/tickets?status=open&order_by=-mtime&limit=20`
+#[utoipa::path(
+    get,
+    path = "/api/tickets",
+    tag = "tickets",
+    params(TicketListParams),
+    responses(
+        (status = 200, body = TicketPage),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+    )
+)]
+async fn list_tickets(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<TicketListParams>,
+) -> Result<Json<TicketPage>> {
+    debug!("{:<12} - list_tickets - {params:?}", "HANDLER");
+
+    let page = mc.list_tickets(ctx, params).await?;
+
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tickets/{id}",
+    tag = "tickets",
+    params(
+        ("id" = u64, Path, description = "Ticket id"),
+        ("If-None-Match" = Option<String>, Header,
+            description = "ETag(s) the client has, for a `304` if still current."),
+    ),
+    responses(
+        (status = 200, body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 304, description = "Not modified.", headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+    )
+)]
+async fn get_ticket(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    debug!("{:<12} - get_ticket", "HANDLER");
+
+    let ticket = mc.get_ticket(ctx, id).await?;
+
+    if if_none_match(&headers, &ticket) {
+        return Ok((StatusCode::NOT_MODIFIED, [etag_header(&ticket)]).into_response());
+    }
+
+    Ok(ticket_response(ticket))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/tickets/{id}",
+    tag = "tickets",
+    params(("id" = u64, Path, description = "Ticket id"), ("If-Match" = Option<String>, Header, description = "ETag(s) the ticket must be at, e.g., `\"3\"`, or `*`.")),
+    request_body = TicketForReplace,
+    responses(
+        (status = 200, body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 412, description = "`PRECONDITION_FAILED`, changed since.", body = ClientErrorBody),
+    )
+)]
+async fn replace_ticket(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+    Json(ticket_fr): Json<TicketForReplace>,
+) -> Result<Response> {
+    debug!("{:<12} - replace_ticket", "HANDLER");
 
-    let tickets = mc.list_tickets(ctx).await?;
+    let ticket = mc
+        .replace_ticket(ctx, id, ticket_fr, if_match(&headers))
+        .await?;
 
-    Ok(Json(tickets))
+    Ok(ticket_response(ticket))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/tickets/{id}",
+    tag = "tickets",
+    params(("id" = u64, Path, description = "Ticket id"), ("If-Match" = Option<String>, Header, description = "ETag(s) the ticket must be at, e.g., `\"3\"`, or `*`.")),
+    request_body = TicketForUpdate,
+    responses(
+        (status = 200, body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 412, description = "`PRECONDITION_FAILED`, changed since.", body = ClientErrorBody),
+    )
+)]
+async fn update_ticket(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+    Json(ticket_fu): Json<TicketForUpdate>,
+) -> Result<Response> {
+    debug!("{:<12} - update_ticket", "HANDLER");
+
+    let ticket = mc
+        .update_ticket(ctx, id, ticket_fu, if_match(&headers))
+        .await?;
+
+    Ok(ticket_response(ticket))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tickets/{id}",
+    tag = "tickets",
+    params(("id" = u64, Path, description = "Ticket id"), ("If-Match" = Option<String>, Header, description = "ETag(s) the ticket must be at, e.g., `\"3\"`, or `*`.")),
+    responses(
+        (status = 200, description = "Moved to the trash.", body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 412, description = "`PRECONDITION_FAILED`, changed since.", body = ClientErrorBody),
+    )
+)]
 async fn delete_ticket(
     State(mc): State<ModelController>,
     ctx: Ctx,
     Path(id): Path<u64>,
-) -> Result<Json<Ticket>> {
-    println!("->> {:<12} - delete_ticket", "HANDLER");
+    headers: HeaderMap,
+) -> Result<Response> {
+    debug!("{:<12} - delete_ticket", "HANDLER");
+
+    let ticket = mc.delete_ticket(ctx, id, if_match(&headers)).await?;
+
+    Ok(ticket_response(ticket))
+}
 
-    let ticket = mc.delete_ticket(ctx, id).await?;
+/// Legacy path of `DELETE /api/tickets/{id}`, kept for the existing clients.
+#[utoipa::path(
+    delete,
+    path = "/api/ticket/{id}",
+    tag = "tickets",
+    params(("id" = u64, Path, description = "Ticket id")),
+    responses(
+        (status = 200, description = "Moved to the trash.", body = Ticket),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+    )
+)]
+async fn delete_ticket_legacy(
+    mc: State<ModelController>,
+    ctx: Ctx,
+    id: Path<u64>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    delete_ticket(mc, ctx, id, headers).await
+}
+
+// e.g., `/api/tickets/search?q=login+crash&limit=20`
+/// Ranked full-text search of the titles and descriptions.
+#[utoipa::path(
+    get,
+    path = "/api/tickets/search",
+    tag = "tickets",
+    params(TicketSearchParams),
+    responses(
+        (status = 200, body = TicketSearchPage),
+        (status = 400, description = "`INVALID_PARAMS`, no word in `q`.", body = ClientErrorBody),
+    )
+)]
+async fn search_tickets(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<TicketSearchParams>,
+) -> Result<Json<TicketSearchPage>> {
+    debug!("{:<12} - search_tickets - {params:?}", "HANDLER");
 
-    Ok(Json(ticket))
+    let page = mc.search_tickets(ctx, params).await?;
+
+    Ok(Json(page))
+}
+
+// e.g., `curl -X POST -H 'Content-Type: text/csv' --data-binary @tickets.csv
+//        '/api/tickets/import?all_or_nothing=true'`
+/// Create the tickets of a CSV (with a header row) or NDJSON body, with the
+/// report of the failed rows.
+///
+/// CSV columns, by name: `title`, `description`, `status`, `priority`, `assignee`
+/// and `labels` (ids separated by `;`), the other ones are ignored.
+#[utoipa::path(
+    post,
+    path = "/api/tickets/import",
+    tag = "tickets",
+    params(TicketImportParams),
+    request_body(content = String, content_type = "text/csv",
+        description = "Or `application/x-ndjson`, one `TicketForCreate` per line."),
+    responses(
+        (status = 200, description = "Imported, but for the `failed` rows.", body = TicketImportReport),
+        (status = 400, description = "`INVALID_PARAMS`, unknown format, no title column, or too many rows.",
+            body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`", body = ClientErrorBody),
+        (status = 422, description = "Nothing imported, some rows failed with `all_or_nothing`.",
+            body = TicketImportReport),
+    )
+)]
+async fn import_tickets(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<TicketImportParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    debug!("{:<12} - import_tickets - {params:?}", "HANDLER");
+
+    let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    let format = BulkFormat::resolve(params.format.clone(), content_type)?;
+    let report = mc.import_tickets(ctx, format, params, &body).await?;
+
+    let status = if report.committed {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    Ok((status, Json(report)).into_response())
+}
+
+// e.g., `/api/tickets/export?format=ndjson&status=open`
+/// All the tickets matching the filters (as in the list), streamed.
+#[utoipa::path(
+    get,
+    path = "/api/tickets/export",
+    tag = "tickets",
+    params(TicketExportParams),
+    responses(
+        (status = 200, description = "CSV with a header row, or NDJSON of `Ticket`.",
+            content_type = "text/csv", body = String),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+    )
+)]
+async fn export_tickets(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<TicketExportParams>,
+) -> Result<Response> {
+    debug!("{:<12} - export_tickets - {params:?}", "HANDLER");
+
+    let (format, chunks) = mc.export_tickets(ctx, params).await?;
+
+    // Once streaming, an error can only cut the response short.
+    let chunks = chunks.map(|chunk| {
+        chunk.map(Bytes::from).map_err(|ex| {
+            warn!("{:<12} - export failed - {ex:?}", "HANDLER");
+            ex
+        })
+    });
+    let filename = format!("attachment; filename=\"tickets.{}\"", format.as_ref());
+
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, filename),
+        ],
+        StreamBody::new(chunks),
+    )
+        .into_response())
+}
+
+// e.g., `/api/tickets/trash?order_by=-deleted`
+#[utoipa::path(
+    get,
+    path = "/api/tickets/trash",
+    tag = "tickets",
+    params(TicketListParams),
+    responses(
+        (status = 200, body = TicketPage),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+    )
+)]
+async fn list_trash(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Query(params): Query<TicketListParams>,
+) -> Result<Json<TicketPage>> {
+    debug!("{:<12} - list_trash - {params:?}", "HANDLER");
+
+    let page = mc.list_trash(ctx, params).await?;
+
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tickets/{id}/restore",
+    tag = "tickets",
+    params(("id" = u64, Path, description = "Ticket id")),
+    responses(
+        (status = 200, body = Ticket, headers(("ETag" = String, description = "Ticket version, e.g., `\"3\"`."))),
+        (status = 400, description = "`INVALID_PARAMS`, not in the trash.", body = ClientErrorBody),
+    )
+)]
+async fn restore_ticket(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Response> {
+    debug!("{:<12} - restore_ticket", "HANDLER");
+
+    let ticket = mc.restore_ticket(ctx, id).await?;
+
+    Ok(ticket_response(ticket))
 }
 
 // endregion: --- REST Handlers
+
+// region:    --- ETag
+
+// The ETag of a ticket is its quoted version, e.g., `"3"`.
+
+fn etag_header(ticket: &Ticket) -> (HeaderName, HeaderValue) {
+    let etag = HeaderValue::from_str(&format!("\"{}\"", ticket.version))
+        .expect("a quoted number is a valid header value");
+
+    (ETAG, etag)
+}
+
+fn ticket_response(ticket: Ticket) -> Response {
+    ([etag_header(&ticket)], Json(ticket)).into_response()
+}
+
+/// `If-Match` uses the strong comparison, so weak tags (`W/"3"`) never match.
+fn if_match(headers: &HeaderMap) -> IfMatch {
+    match etag_list(headers, IF_MATCH) {
+        None => IfMatch::Any,
+        Some(tags) => IfMatch::Versions(tags.iter().filter_map(|tag| parse_version(tag)).collect()),
+    }
+}
+
+/// True if the client already has this version (weak comparison).
+fn if_none_match(headers: &HeaderMap, ticket: &Ticket) -> bool {
+    etag_list(headers, IF_NONE_MATCH).is_some_and(|tags| {
+        tags.iter().any(|tag| {
+            let tag = tag.strip_prefix("W/").unwrap_or(tag);
+            parse_version(tag) == Some(ticket.version)
+        })
+    })
+}
+
+/// The tags of all the `name` headers, `None` if absent or `*` (any version).
+fn etag_list(headers: &HeaderMap, name: HeaderName) -> Option<Vec<String>> {
+    let mut tags = Vec::new();
+    for value in headers.get_all(name) {
+        // Not visible ASCII, can't be one of our tags.
+        let Ok(value) = value.to_str() else {
+            tags.push(String::new());
+            continue;
+        };
+        for tag in value.split(',').map(str::trim) {
+            if tag == "*" {
+                return None;
+            }
+            tags.push(tag.to_string());
+        }
+    }
+
+    (!tags.is_empty()).then_some(tags)
+}
+
+fn parse_version(tag: &str) -> Option<u64> {
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+// endregion: --- ETag
@@ -0,0 +1,59 @@
+use crate::ctx::{Ctx, Role};
+use crate::model::ModelController;
+use crate::Result;
+use axum::extract::{Path, State};
+use axum::routing::put;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::debug;
+use utoipa::ToSchema;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/users/:id/role", put(set_user_role))
+        .with_state(mc)
+}
+
+// region: --- REST Handlers
+
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}/role",
+    tag = "users",
+    params(("id" = u64, Path, description = "User id")),
+    request_body = RolePayload,
+    responses(
+        (status = 200, body = Object,
+            example = json!({ "result": { "id": 2, "username": "demo2", "role": "viewer" } })),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admins only.", body = ClientErrorBody),
+    )
+)]
+async fn set_user_role(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    Json(payload): Json<RolePayload>,
+) -> Result<Json<Value>> {
+    debug!("{:<12} - set_user_role", "HANDLER");
+
+    let user = mc.set_user_role(ctx, id, payload.role).await?;
+
+    let body = Json(json!({
+        "result": {
+            "id": user.id,
+            "username": user.username,
+            "role": user.role,
+        }
+    }));
+
+    Ok(body)
+}
+
+// endregion: --- REST Handlers
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RolePayload {
+    role: Role,
+}
@@ -0,0 +1,166 @@
+use crate::ctx::Ctx;
+use crate::model::{
+    ModelController, Webhook, WebhookAttempt, WebhookCreated, WebhookForCreate, WebhookForUpdate,
+};
+use crate::Result;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::debug;
+
+pub fn routes(mc: ModelController) -> Router {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/:id",
+            get(get_webhook)
+                .patch(update_webhook)
+                .delete(delete_webhook),
+        )
+        .route("/webhooks/:id/attempts", get(list_webhook_attempts))
+        .with_state(mc)
+}
+
+// region: --- REST Handlers
+
+/// Register a URL to POST the ticket events to (see `WebhookPayload`).
+///
+/// The requests are signed with the returned `secret`: `X-Webhook-Signature` is
+/// `v1=` and the base64url (no pad) HMAC-SHA256 of `[X-Webhook-Timestamp].[body]`.
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    request_body = WebhookForCreate,
+    responses(
+        (status = 200, description = "The `secret` is only returned here.", body = WebhookCreated),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn create_webhook(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Json(webhook_fc): Json<WebhookForCreate>,
+) -> Result<Json<WebhookCreated>> {
+    debug!("{:<12} - create_webhook", "HANDLER");
+
+    let webhook = mc.create_webhook(ctx, webhook_fc).await?;
+
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, body = [Webhook]),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn list_webhooks(State(mc): State<ModelController>, ctx: Ctx) -> Result<Json<Vec<Webhook>>> {
+    debug!("{:<12} - list_webhooks", "HANDLER");
+
+    let webhooks = mc.list_webhooks(ctx).await?;
+
+    Ok(Json(webhooks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = u64, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = Webhook),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn get_webhook(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Json<Webhook>> {
+    debug!("{:<12} - get_webhook", "HANDLER");
+
+    let webhook = mc.get_webhook(ctx, id).await?;
+
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = u64, Path, description = "Webhook id")),
+    request_body = WebhookForUpdate,
+    responses(
+        (status = 200, body = Webhook),
+        (status = 400, description = "`INVALID_PARAMS`", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn update_webhook(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+    Json(webhook_fu): Json<WebhookForUpdate>,
+) -> Result<Json<Webhook>> {
+    debug!("{:<12} - update_webhook", "HANDLER");
+
+    let webhook = mc.update_webhook(ctx, id, webhook_fu).await?;
+
+    Ok(Json(webhook))
+}
+
+/// Deletes the webhook, with its recorded attempts.
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = u64, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = Webhook),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn delete_webhook(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Json<Webhook>> {
+    debug!("{:<12} - delete_webhook", "HANDLER");
+
+    let webhook = mc.delete_webhook(ctx, id).await?;
+
+    Ok(Json(webhook))
+}
+
+/// The latest 100 delivery attempts, latest first.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/attempts",
+    tag = "webhooks",
+    params(("id" = u64, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = [WebhookAttempt]),
+        (status = 400, description = "`INVALID_PARAMS`, not found.", body = ClientErrorBody),
+        (status = 403, description = "`ACCESS_DENIED`, admin only.", body = ClientErrorBody),
+    )
+)]
+async fn list_webhook_attempts(
+    State(mc): State<ModelController>,
+    ctx: Ctx,
+    Path(id): Path<u64>,
+) -> Result<Json<Vec<WebhookAttempt>>> {
+    debug!("{:<12} - list_webhook_attempts", "HANDLER");
+
+    let attempts = mc.list_webhook_attempts(ctx, id).await?;
+
+    Ok(Json(attempts))
+}
+
+// endregion: --- REST Handlers
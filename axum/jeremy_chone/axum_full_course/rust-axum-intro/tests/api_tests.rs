@@ -0,0 +1,1290 @@
+mod common;
+
+use crate::common::{assert_client_error, login_admin, register_and_login, TestApp};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_hello_ok() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc.do_get("/hello2/Mike").await?;
+
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.text_body()?, "Hello <strong>Mike</strong>");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_no_auth_err() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc.do_get("/api/tickets").await?;
+
+    assert_client_error(&res, 403, "NO_AUTH")
+}
+
+#[tokio::test]
+async fn test_login_fail_same_error() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+
+    let hc = app.client()?;
+    let wrong_pwd = json!({ "username": "demo1", "pwd": "nope" });
+    let unknown_user = json!({ "username": "nobody", "pwd": "welcome" });
+
+    for payload in [wrong_pwd, unknown_user] {
+        let res = hc.do_post("/api/login", payload).await?;
+        assert_client_error(&res, 403, "LOGIN_FAIL")?;
+        assert!(hc.cookie_value("auth-token").is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_crud_flow() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    let user_id = register_and_login(&hc, "demo1", "welcome").await?;
+
+    // -- Create
+    let res = hc
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let id = res.json_value::<u64>("/id")?;
+    assert_eq!(res.json_value::<u64>("/cid")?, user_id);
+    assert_eq!(res.json_value::<String>("/status")?, "open");
+
+    // -- List
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<u64>("/total")?, 1);
+    assert_eq!(res.json_value::<u64>("/data/0/id")?, id);
+
+    // -- Update
+    let res = hc
+        .do_patch(
+            &format!("/api/tickets/{id}"),
+            json!({ "status": "closed", "description": "done" }),
+        )
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<String>("/status")?, "closed");
+
+    let res = hc
+        .do_patch(
+            &format!("/api/tickets/{id}"),
+            json!({ "status": "unknown" }),
+        )
+        .await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    // -- Get
+    let res = hc.do_get(&format!("/api/tickets/{id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<String>("/description")?, "done");
+
+    // -- Delete
+    let res = hc.do_delete(&format!("/api/tickets/{id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    let res = hc.do_delete(&format!("/api/tickets/{id}")).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_trash_and_restore() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let id = hc
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?
+        .json_value::<u64>("/id")?;
+
+    // -- Deleted, hidden from the list and get, in the trash.
+    let res = hc.do_delete(&format!("/api/tickets/{id}")).await?;
+    assert!(res.json_value::<String>("/deleted").is_ok());
+    let res = hc.do_get(&format!("/api/tickets/{id}")).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 0);
+    let res = hc.do_get("/api/tickets/trash").await?;
+    assert_eq!(res.json_value::<u64>("/data/0/id")?, id);
+
+    // -- Restored.
+    let res = hc
+        .do_post(&format!("/api/tickets/{id}/restore"), json!({}))
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<Value>("/deleted")?, Value::Null);
+    let res = hc.do_get(&format!("/api/tickets/{id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let res = hc.do_get("/api/tickets/trash").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_search_ranked_and_highlighted() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    register_and_login(&hc_1, "member1", "welcome").await?;
+
+    let crash = hc_1
+        .do_post(
+            "/api/tickets",
+            json!({ "title": "App crashes on login", "description": "Crashing since <v2>." }),
+        )
+        .await?
+        .json_value::<u64>("/id")?;
+    let slow = hc_1
+        .do_post(
+            "/api/tickets",
+            json!({ "title": "Slow search", "description": "Not a crash, just slow." }),
+        )
+        .await?
+        .json_value::<u64>("/id")?;
+    hc_admin
+        .do_post("/api/tickets", json!({ "title": "Crash in admin" }))
+        .await?;
+
+    // -- Stemmed, ranked, highlighted, and members only see their own.
+    let res = hc_1.do_get("/api/tickets/search?q=Crash").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 2);
+    assert_eq!(res.json_value::<u64>("/data/0/ticket/id")?, crash);
+    assert_eq!(
+        res.json_value::<String>("/data/0/title_html")?,
+        "App <mark>crashes</mark> on login"
+    );
+    assert_eq!(
+        res.json_value::<String>("/data/0/snippet_html")?,
+        "<mark>Crashing</mark> since &lt;v2&gt;."
+    );
+    let res = hc_admin.do_get("/api/tickets/search?q=crash").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 3);
+
+    // -- Kept up to date on update and delete.
+    hc_1.do_patch(
+        &format!("/api/tickets/{slow}"),
+        json!({ "description": "Slow." }),
+    )
+    .await?;
+    hc_1.do_delete(&format!("/api/tickets/{crash}")).await?;
+    let res = hc_1.do_get("/api/tickets/search?q=crash").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 0);
+
+    let res = hc_1.do_get("/api/tickets/search?q=%20-%20").await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_etag_conditional_requests() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let reqwest = hc.reqwest_client();
+    let res = hc
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?;
+    let id = res.json_value::<u64>("/id")?;
+    assert_eq!(res.json_value::<u64>("/version")?, 1);
+    let url = format!("{}/api/tickets/{id}", app.base_url);
+
+    // -- ETag on get, 304 when the client has it.
+    let res = hc.do_get(&format!("/api/tickets/{id}")).await?;
+    assert_eq!(res.header("etag").as_deref(), Some("\"1\""));
+    let res = reqwest
+        .get(&url)
+        .header("if-none-match", "W/\"1\"")
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 304);
+    assert_eq!(res.headers()["etag"], "\"1\"");
+
+    // -- Replace, from the current version.
+    let res = reqwest
+        .put(&url)
+        .header("if-match", "\"1\"")
+        .json(&json!({ "title": "Ticket BBB", "status": "closed" }))
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.headers()["etag"], "\"2\"");
+    let ticket: Value = res.json().await?;
+    assert_eq!(ticket["title"], "Ticket BBB");
+    assert_eq!(ticket["status"], "closed");
+    assert_eq!(ticket["priority"], "medium");
+
+    // -- Stale version, 412 on replace and delete.
+    let res = reqwest
+        .put(&url)
+        .header("if-match", "\"1\"")
+        .json(&json!({ "title": "Ticket CCC" }))
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 412);
+    let body: Value = res.json().await?;
+    assert_eq!(body["error"]["type"], "PRECONDITION_FAILED");
+    let res = reqwest
+        .delete(&url)
+        .header("if-match", "\"1\"")
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 412);
+
+    // -- Old version no longer matches, any version does.
+    let res = reqwest
+        .get(&url)
+        .header("if-none-match", "\"1\"")
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let res = reqwest.delete(&url).header("if-match", "*").send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.headers()["etag"], "\"3\"");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_create_idempotency_key() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let reqwest = hc.reqwest_client();
+    let url = format!("{}/api/tickets", app.base_url);
+    let create = |key: &'static str, title: &'static str| {
+        reqwest
+            .post(&url)
+            .header("idempotency-key", key)
+            .json(&json!({ "title": title }))
+            .send()
+    };
+
+    // -- First create, then replayed.
+    let res = create("key-1", "Ticket AAA").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert!(res.headers().get("idempotent-replayed").is_none());
+    let first: Value = res.json().await?;
+    let res = create("key-1", "Ticket AAA").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.headers()["idempotent-replayed"], "true");
+    let again: Value = res.json().await?;
+    assert_eq!(again["id"], first["id"]);
+
+    // -- Reused for another create.
+    let res = create("key-1", "Ticket BBB").await?;
+    assert_eq!(res.status().as_u16(), 422);
+    let body: Value = res.json().await?;
+    assert_eq!(body["error"]["type"], "IDEMPOTENCY_KEY_REUSED");
+
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_member_cannot_delete_others_ticket() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    register_and_login(&hc_1, "member1", "welcome").await?;
+    let hc_2 = app.client()?;
+    register_and_login(&hc_2, "member2", "welcome").await?;
+
+    let res = hc_1
+        .do_post("/api/tickets", json!({ "title": "From member1" }))
+        .await?;
+    let id = res.json_value::<u64>("/id")?;
+
+    // -- Member2 neither sees nor deletes it.
+    let res = hc_2.do_get("/api/tickets").await?;
+    assert_eq!(res.json_value::<Vec<Value>>("/data")?.len(), 0);
+    let res = hc_2.do_delete(&format!("/api/tickets/{id}")).await?;
+    assert_client_error(&res, 403, "ACCESS_DENIED")?;
+
+    // -- Admin does.
+    let res = hc_admin.do_delete(&format!("/api/tickets/{id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    Ok(())
+}
+
+// region:    --- Middleware Ordering
+
+#[tokio::test]
+async fn test_auth_cookie_refreshed_on_each_request() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+
+    // The ctx resolver (before the handler) sets the refreshed token,
+    // and the cookie manager (outside of it) writes the header.
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert!(res.res_cookie_value("auth-token").is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bad_token_cookie_removed() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc
+        .reqwest_client()
+        .get(format!("{}/api/tickets", app.base_url))
+        .header(
+            "cookie",
+            "auth-token=session-0123456789abcdef0123456789abcdef.9999999999.bad-sign",
+        )
+        .send()
+        .await?;
+
+    assert_eq!(res.status().as_u16(), 403);
+    let set_cookie = res
+        .headers()
+        .get("set-cookie")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    assert!(set_cookie.starts_with("auth-token=;"), "{set_cookie}");
+
+    // The error went through the main response mapper.
+    let body: Value = res.json().await?;
+    assert_eq!(body["error"]["type"], "NO_AUTH");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_req_uuid_unique_per_request() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res_1 = hc.do_get("/api/tickets").await?;
+    let res_2 = hc.do_get("/api/tickets").await?;
+
+    assert_ne!(
+        res_1.json_value::<String>("/error/req_uuid")?,
+        res_2.json_value::<String>("/error/req_uuid")?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_request_id_header() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    // -- Generated, and the same as in the error body.
+    let res = hc.do_get("/api/tickets").await?;
+    let request_id = res.header("x-request-id").unwrap_or_default();
+    assert_eq!(res.json_value::<String>("/error/req_uuid")?, request_id);
+
+    // -- Accepted from the client.
+    let request_id = "0b7ce4a6-5c49-4c6d-9a4a-e5a1f3c0d2b1";
+    let res = hc
+        .reqwest_client()
+        .get(format!("{}/api/tickets", app.base_url))
+        .header("x-request-id", request_id)
+        .send()
+        .await?;
+    assert_eq!(res.headers()["x-request-id"], request_id);
+    let body: Value = res.json().await?;
+    assert_eq!(body["error"]["req_uuid"], request_id);
+
+    // -- Not a uuid, replaced.
+    let res = hc
+        .reqwest_client()
+        .get(format!("{}/hello", app.base_url))
+        .header("x-request-id", "not-a-uuid")
+        .send()
+        .await?;
+    let request_id = res.headers()["x-request-id"].to_str()?;
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+
+    Ok(())
+}
+
+// endregion: --- Middleware Ordering
+
+// region:    --- Sessions
+
+#[tokio::test]
+async fn test_sessions_list_revoke_and_logout_everywhere() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_1 = app.client()?;
+    register_and_login(&hc_1, "demo1", "welcome").await?;
+    let login = |hc: httpc_test::Client| async move {
+        let res = hc
+            .do_post(
+                "/api/login",
+                json!({ "username": "demo1", "pwd": "welcome" }),
+            )
+            .await?;
+        assert_eq!(res.status().as_u16(), 200);
+        anyhow::Ok((hc, res.res_cookie_value("auth-token").unwrap_or_default()))
+    };
+    let (hc_2, _) = login(app.client()?).await?;
+    let with_cookie = |token: &str| {
+        app.client().map(|hc| {
+            let req = hc
+                .reqwest_client()
+                .get(format!("{}/api/tickets", app.base_url))
+                .header("cookie", format!("auth-token={token}"));
+            (hc, req)
+        })
+    };
+
+    // -- List, with the current one.
+    let res = hc_1.do_get("/api/sessions").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let sessions = res.json_body()?;
+    let sessions = sessions.as_array().cloned().unwrap_or_default();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|s| s["current"] == true).count(), 1);
+    assert!(sessions.iter().all(|s| s["ip"] == "127.0.0.1"));
+    let other_id = sessions
+        .iter()
+        .find(|s| s["current"] == false)
+        .and_then(|s| s["id"].as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    // -- Revoked, the other client is logged out.
+    let res = hc_1.do_delete(&format!("/api/sessions/{other_id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let res = hc_2.do_get("/api/tickets").await?;
+    assert_client_error(&res, 403, "NO_AUTH")?;
+
+    let hc_3 = app.client()?;
+    register_and_login(&hc_3, "demo2", "welcome").await?;
+    let res = hc_1.do_get("/api/sessions").await?;
+    let own_id = res.json_value::<String>("/0/id")?;
+    let res = hc_3.do_delete(&format!("/api/sessions/{own_id}")).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    // -- Logout, the token is not valid anymore.
+    let (hc_4, token) = login(app.client()?).await?;
+    hc_4.do_post("/api/logout", json!({})).await?;
+    let (_hc, req) = with_cookie(&token)?;
+    assert_eq!(req.send().await?.status().as_u16(), 403);
+
+    // -- Log out everywhere.
+    let (hc_5, _) = login(app.client()?).await?;
+    let res = hc_1.do_delete("/api/sessions").await?;
+    assert_eq!(res.json_value::<u64>("/revoked")?, 2);
+    for hc in [&hc_1, &hc_5] {
+        let res = hc.do_get("/api/tickets").await?;
+        assert_client_error(&res, 403, "NO_AUTH")?;
+    }
+    let res = hc_3.do_get("/api/tickets").await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    Ok(())
+}
+
+// endregion: --- Sessions
+
+// region:    --- Metrics
+
+#[tokio::test]
+async fn test_metrics_ok() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let res = hc
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?;
+    let id = res.json_value::<u64>("/id")?;
+    hc.do_get(&format!("/api/tickets/{id}")).await?;
+    hc.do_get("/api/tickets/9999").await?;
+
+    let res = hc.do_get("/metrics").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let body = res.text_body()?;
+
+    // Labeled by the route template, not the raw path.
+    for line_start in [
+        r#"http_requests_total{method="GET",route="/api/tickets/:id",status="200"}"#,
+        r#"http_requests_total{method="GET",route="/api/tickets/:id",status="400"}"#,
+        r#"http_request_duration_seconds_bucket{method="POST",route="/api/tickets",status="200",le="#,
+        r#"service_errors_total{error="TicketNotFound"}"#,
+        r#"client_errors_total{client_error="INVALID_PARAMS"}"#,
+        // Store of this app only (the seeded admin and demo1).
+        "store_tickets 1",
+        "store_users 2",
+    ] {
+        assert!(
+            body.lines().any(|l| l.starts_with(line_start)),
+            "missing: {line_start}"
+        );
+    }
+    assert!(!body.contains("/api/tickets/9999"));
+
+    Ok(())
+}
+
+// endregion: --- Metrics
+
+// region:    --- Health
+
+#[tokio::test]
+async fn test_health_probes_no_auth_and_draining() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc.do_get("/healthz").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<String>("/status")?, "ok");
+    let res = hc.do_get("/readyz").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.json_value::<String>("/status")?, "ready");
+
+    // -- As on SIGTERM: not ready anymore, but still alive and serving.
+    app.shutdown.start_draining();
+    let res = hc.do_get("/readyz").await?;
+    assert_eq!(res.status().as_u16(), 503);
+    assert_eq!(res.json_value::<String>("/status")?, "draining");
+    let res = hc.do_get("/healthz").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    Ok(())
+}
+
+// endregion: --- Health
+
+// region:    --- Ticket Events
+
+#[tokio::test]
+async fn test_ticket_events_sse() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    register_and_login(&hc_1, "member1", "welcome").await?;
+    let hc_2 = app.client()?;
+    register_and_login(&hc_2, "member2", "welcome").await?;
+
+    let mut res = hc_2
+        .reqwest_client()
+        .get(format!("{}/api/tickets/events", app.base_url))
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.headers()["content-type"], "text/event-stream");
+
+    // -- Member2 only gets the events of its own tickets.
+    hc_1.do_post("/api/tickets", json!({ "title": "From member1" }))
+        .await?;
+    let id = hc_2
+        .do_post("/api/tickets", json!({ "title": "From member2" }))
+        .await?
+        .json_value::<u64>("/id")?;
+
+    let mut received = String::new();
+    while !received.contains("\n\n") {
+        let chunk = res.chunk().await?.expect("should have an event");
+        received.push_str(std::str::from_utf8(&chunk)?);
+    }
+    let (event, data) = received
+        .trim()
+        .split_once('\n')
+        .expect("should be event and data lines");
+    assert_eq!(event, "event:created");
+    let ticket: Value = serde_json::from_str(data.trim_start_matches("data:"))?;
+    assert_eq!(ticket["id"], id);
+    assert_eq!(ticket["title"], "From member2");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ticket_events_ws() -> Result<()> {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+
+    // -- Without auth, no upgrade.
+    let url = format!("{}/api/tickets/ws", app.base_url.replace("http", "ws"));
+    assert!(tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .is_err());
+
+    let mut req = url.as_str().into_client_request()?;
+    let auth_token = hc.cookie_value("auth-token").expect("should be logged in");
+    req.headers_mut()
+        .insert("cookie", format!("auth-token={auth_token}").parse()?);
+    let (mut ws, _) = tokio_tungstenite::connect_async(req).await?;
+
+    let id = hc
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?
+        .json_value::<u64>("/id")?;
+    hc.do_delete(&format!("/api/tickets/{id}")).await?;
+
+    for kind in ["created", "deleted"] {
+        let Some(Ok(Message::Text(msg))) = ws.next().await else {
+            panic!("should be a text message");
+        };
+        let event: Value = serde_json::from_str(&msg)?;
+        assert_eq!(event["kind"], kind);
+        assert_eq!(event["ticket"]["id"], id);
+    }
+
+    Ok(())
+}
+
+// endregion: --- Ticket Events
+
+// region:    --- Audit
+
+#[tokio::test]
+async fn test_audit_admin_only_and_filters() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    let user_1 = register_and_login(&hc_1, "member1", "welcome").await?;
+
+    let res = hc_1
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?;
+    let id = res.json_value::<u64>("/id")?;
+    let create_req_uuid = res.header("x-request-id");
+    hc_1.do_patch(
+        &format!("/api/tickets/{id}"),
+        json!({ "title": "Ticket BBB" }),
+    )
+    .await?;
+    hc_admin
+        .do_post("/api/tickets", json!({ "title": "From admin" }))
+        .await?;
+    hc_admin.do_delete(&format!("/api/tickets/{id}")).await?;
+
+    // -- Members can't read the audit.
+    let res = hc_1.do_get("/api/audit").await?;
+    assert_client_error(&res, 403, "ACCESS_DENIED")?;
+
+    // -- By ticket, in order, with the snapshots.
+    let res = hc_admin
+        .do_get(&format!("/api/audit?ticket_id={id}"))
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let entries = res.json_value::<Vec<Value>>("/data")?;
+    let actions: Vec<&str> = entries
+        .iter()
+        .map(|e| e["action"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(actions, ["ticket_create", "ticket_update", "ticket_delete"]);
+    assert_eq!(entries[0]["actor"], user_1);
+    assert_eq!(entries[0]["req_uuid"].as_str(), create_req_uuid.as_deref());
+    assert_eq!(entries[1]["before"]["title"], "Ticket AAA");
+    assert_eq!(entries[1]["after"]["title"], "Ticket BBB");
+    assert!(entries[2]["after"]["deleted"].is_string());
+
+    // -- By user.
+    let res = hc_admin
+        .do_get(&format!("/api/audit?user_id={user_1}"))
+        .await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 2);
+
+    // -- By time range.
+    let res = hc_admin
+        .do_get("/api/audit?from=2000-01-01T00:00:00Z&to=2000-01-02T00:00:00Z")
+        .await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 0);
+    let res = hc_admin
+        .do_get("/api/audit?from=2000-01-01T00:00:00Z")
+        .await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 4);
+    let res = hc_admin.do_get("/api/audit?from=yesterday").await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    Ok(())
+}
+
+// endregion: --- Audit
+
+// region:    --- Comments & Labels
+
+#[tokio::test]
+async fn test_ticket_comments_threaded() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    let user_1 = register_and_login(&hc_1, "member1", "welcome").await?;
+    let id = hc_1
+        .do_post("/api/tickets", json!({ "title": "Ticket AAA" }))
+        .await?
+        .json_value::<u64>("/id")?;
+    let comments_url = format!("/api/tickets/{id}/comments");
+
+    // -- A comment and a reply, authored by the logged in users.
+    let res = hc_1
+        .do_post(&comments_url, json!({ "body": "First" }))
+        .await?;
+    assert_eq!(res.json_value::<u64>("/author")?, user_1);
+    let c1 = res.json_value::<u64>("/id")?;
+    let res = hc_admin
+        .do_post(&comments_url, json!({ "body": "Reply", "parent_id": c1 }))
+        .await?;
+    let c2 = res.json_value::<u64>("/id")?;
+    let res = hc_1.do_post(&comments_url, json!({ "body": "  " })).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    let res = hc_1.do_get(&comments_url).await?;
+    assert_eq!(res.json_value::<u64>("/1/parent_id")?, c1);
+
+    // -- Only the author edits.
+    let res = hc_admin
+        .do_patch(&format!("{comments_url}/{c1}"), json!({ "body": "Edited" }))
+        .await?;
+    assert_client_error(&res, 403, "ACCESS_DENIED")?;
+    let res = hc_1
+        .do_patch(&format!("{comments_url}/{c1}"), json!({ "body": "Edited" }))
+        .await?;
+    assert_eq!(res.json_value::<String>("/body")?, "Edited");
+
+    // -- Deleted with its replies.
+    let res = hc_1.do_delete(&format!("{comments_url}/{c1}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let res = hc_1.do_get(&format!("{comments_url}/{c2}")).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    // -- Hidden with the ticket in the trash.
+    hc_1.do_post(&comments_url, json!({ "body": "Again" }))
+        .await?;
+    hc_1.do_delete(&format!("/api/tickets/{id}")).await?;
+    let res = hc_1.do_get(&comments_url).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_labels_and_ticket_label_filter() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc_admin = app.client()?;
+    login_admin(&hc_admin).await?;
+    let hc_1 = app.client()?;
+    register_and_login(&hc_1, "member1", "welcome").await?;
+
+    let res = hc_1
+        .do_post("/api/labels", json!({ "name": "bug", "color": "#D73A4A" }))
+        .await?;
+    assert_eq!(res.json_value::<String>("/color")?, "#d73a4a");
+    let bug = res.json_value::<u64>("/id")?;
+    let res = hc_1
+        .do_post("/api/labels", json!({ "name": "Bug" }))
+        .await?;
+    assert_client_error(&res, 409, "LABEL_NAME_TAKEN")?;
+    let res = hc_1
+        .do_post("/api/labels", json!({ "name": "ui", "color": "red" }))
+        .await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    // -- Set on tickets, and filtered on.
+    let res = hc_1
+        .do_post("/api/tickets", json!({ "title": "Crash", "labels": [bug] }))
+        .await?;
+    assert_eq!(res.json_value::<Vec<u64>>("/labels")?, vec![bug]);
+    let crash = res.json_value::<u64>("/id")?;
+    hc_1.do_post("/api/tickets", json!({ "title": "Feature" }))
+        .await?;
+    let res = hc_1
+        .do_post("/api/tickets", json!({ "title": "Other", "labels": [99] }))
+        .await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    let res = hc_1.do_get(&format!("/api/tickets?label={bug}")).await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 1);
+    assert_eq!(res.json_value::<u64>("/data/0/id")?, crash);
+
+    // -- Only admins rename and delete, deleting removes it from the tickets.
+    let res = hc_1
+        .do_patch(&format!("/api/labels/{bug}"), json!({ "name": "defect" }))
+        .await?;
+    assert_client_error(&res, 403, "ACCESS_DENIED")?;
+    let res = hc_admin
+        .do_patch(&format!("/api/labels/{bug}"), json!({ "name": "defect" }))
+        .await?;
+    assert_eq!(res.json_value::<String>("/name")?, "defect");
+    let res = hc_admin.do_delete(&format!("/api/labels/{bug}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    let res = hc_1.do_get(&format!("/api/tickets/{crash}")).await?;
+    assert_eq!(res.json_value::<Vec<u64>>("/labels")?, Vec::<u64>::new());
+    let res = hc_1.do_get("/api/labels").await?;
+    assert_eq!(res.json_value::<Vec<Value>>("")?.len(), 0);
+
+    Ok(())
+}
+
+// endregion: --- Comments & Labels
+
+// region:    --- Import & Export
+
+#[tokio::test]
+async fn test_ticket_import_report_and_export() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+    let reqwest = hc.reqwest_client();
+    let import = |query: &'static str, content_type: &'static str, body: &'static str| {
+        reqwest
+            .post(format!("{}/api/tickets/import{query}", app.base_url))
+            .header("content-type", content_type)
+            .body(body)
+            .send()
+    };
+    let csv = "title,status,priority\nFirst,open,high\nSecond,done,\nThird,,low\n";
+
+    // -- All or nothing, rejected for the bad status of row 2.
+    let res = import("?all_or_nothing=true", "text/csv", csv).await?;
+    assert_eq!(res.status().as_u16(), 422);
+    let report: Value = res.json().await?;
+    assert_eq!(report["committed"], false);
+    assert_eq!(report["created"], json!([]));
+    assert_eq!(report["failed"][0]["row"], 2);
+    assert_eq!(report["failed"][0]["error"]["type"], "TicketStatusUnknown");
+    let res = hc.do_get("/api/tickets").await?;
+    assert_eq!(res.json_value::<u64>("/total")?, 0);
+
+    // -- Otherwise, the valid rows are imported.
+    let res = import("", "text/csv; charset=utf-8", csv).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let report: Value = res.json().await?;
+    assert_eq!(report["total"], 3);
+    assert_eq!(report["created"].as_array().map(Vec::len), Some(2));
+    assert_eq!(report["failed"].as_array().map(Vec::len), Some(1));
+
+    let ndjson = "{\"title\": \"Fourth\"}\n\n{\"description\": \"no title\"}\n";
+    let res = import("", "application/x-ndjson", ndjson).await?;
+    let report: Value = res.json().await?;
+    assert_eq!(report["created"].as_array().map(Vec::len), Some(1));
+    assert_eq!(report["failed"][0]["row"], 2);
+    assert_eq!(
+        report["failed"][0]["error"]["type"],
+        "TicketImportRowInvalid"
+    );
+
+    let res = import("", "application/json", ndjson).await?;
+    assert_eq!(res.status().as_u16(), 400);
+
+    // -- Export, with the list filters.
+    let export = |query: &'static str| {
+        reqwest
+            .get(format!("{}/api/tickets/export{query}", app.base_url))
+            .send()
+    };
+    let res = export("").await?;
+    assert_eq!(
+        res.headers()["content-type"].to_str()?,
+        "text/csv; charset=utf-8"
+    );
+    let body = res.text().await?;
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("id,cid,title,description,status,priority"));
+    assert!(lines[1].contains(",First,,open,high,"));
+
+    let res = export("?format=ndjson&priority=low").await?;
+    let body = res.text().await?;
+    let tickets: Vec<Value> = body
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<std::result::Result<_, _>>()?;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0]["title"], "Third");
+
+    let res = export("?format=xml").await?;
+    assert_eq!(res.status().as_u16(), 400);
+
+    Ok(())
+}
+
+// endregion: --- Import & Export
+
+// region:    --- Webhooks
+
+#[tokio::test]
+async fn test_webhooks_signed_retried_and_disabled() -> Result<()> {
+    use axum::body::Bytes;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use std::sync::{Arc, Mutex};
+
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    login_admin(&hc).await?;
+    let hc_member = app.client()?;
+    register_and_login(&hc_member, "demo2", "welcome").await?;
+
+    // -- Receiver, recording the requests to `/ok`, and failing those to `/fail`.
+    let received = Arc::new(Mutex::new(Vec::<(HeaderMap, Bytes)>::new()));
+    let receiver = axum::Router::new()
+        .route(
+            "/ok",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: Bytes| async move {
+                    received.lock().unwrap().push((headers, body));
+                }
+            }),
+        )
+        .route(
+            "/fail",
+            post(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+        );
+    let server =
+        axum::Server::bind(&([127, 0, 0, 1], 0).into()).serve(receiver.into_make_service());
+    let receiver_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    // -- Admin only, and validated.
+    let res = hc_member.do_get("/api/webhooks").await?;
+    assert_client_error(&res, 403, "ACCESS_DENIED")?;
+    let res = hc
+        .do_post("/api/webhooks", json!({ "url": "ftp://example.com" }))
+        .await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+    let res = hc
+        .do_post(
+            "/api/webhooks",
+            json!({ "url": "http://example.com", "events": ["closed"] }),
+        )
+        .await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    let res = hc
+        .do_post(
+            "/api/webhooks",
+            json!({ "url": format!("{receiver_url}/ok"), "events": ["created"] }),
+        )
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let secret = res.json_value::<String>("/secret")?;
+    let res = hc
+        .do_post(
+            "/api/webhooks",
+            json!({ "url": format!("{receiver_url}/fail") }),
+        )
+        .await?;
+    let fail_id = res.json_value::<u64>("/id")?;
+
+    let res = hc.do_get("/api/webhooks").await?;
+    let webhooks = res.json_body()?;
+    assert_eq!(webhooks.as_array().map(Vec::len), Some(2));
+    assert!(webhooks[0].get("secret").is_none());
+
+    // -- Created and updated, only the first one for the `/ok` webhook.
+    let res = hc
+        .do_post("/api/tickets", json!({ "title": "Hooked" }))
+        .await?;
+    let ticket_id = res.json_value::<u64>("/id")?;
+    let res = hc
+        .do_patch(
+            &format!("/api/tickets/{ticket_id}"),
+            json!({ "status": "closed" }),
+        )
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    // Failing twice 3 attempts disables the `/fail` webhook.
+    let mut fail_webhook = Value::Null;
+    for _ in 0..100 {
+        let res = hc.do_get(&format!("/api/webhooks/{fail_id}")).await?;
+        fail_webhook = res.json_body()?;
+        if fail_webhook["active"] == false {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(fail_webhook["active"], false);
+    assert_eq!(fail_webhook["failures"], 2);
+
+    let res = hc
+        .do_get(&format!("/api/webhooks/{fail_id}/attempts"))
+        .await?;
+    let attempts = res.json_body()?;
+    let attempts = attempts.as_array().cloned().unwrap_or_default();
+    assert_eq!(attempts.len(), 6);
+    assert!(attempts.iter().all(|a| a["status"] == 500));
+    let mut numbers: Vec<u64> = attempts
+        .iter()
+        .filter_map(|a| a["attempt"].as_u64())
+        .collect();
+    numbers.sort_unstable();
+    assert_eq!(numbers, [1, 1, 2, 2, 3, 3]);
+
+    // -- Signed payload.
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers["x-webhook-event"], "created");
+    let timestamp = headers["x-webhook-timestamp"].to_str()?;
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    let signature = format!("v1={}", URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()));
+    assert_eq!(headers["x-webhook-signature"].to_str()?, signature);
+    let payload: Value = serde_json::from_slice(body)?;
+    assert_eq!(payload["event"], "created");
+    assert_eq!(payload["ticket"]["id"], ticket_id);
+    assert_eq!(
+        payload["delivery_id"].as_str(),
+        headers["x-webhook-delivery"].to_str().ok()
+    );
+
+    // -- Enabled back by an admin.
+    let res = hc
+        .do_patch(
+            &format!("/api/webhooks/{fail_id}"),
+            json!({ "active": true }),
+        )
+        .await?;
+    assert!(res.json_value::<bool>("/active")?);
+    assert_eq!(res.json_value::<u64>("/failures")?, 0);
+
+    let res = hc.do_delete(&format!("/api/webhooks/{fail_id}")).await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let res = hc.do_get(&format!("/api/webhooks/{fail_id}")).await?;
+    assert_client_error(&res, 400, "INVALID_PARAMS")?;
+
+    Ok(())
+}
+
+// endregion: --- Webhooks
+
+// region:    --- Board
+
+#[tokio::test]
+async fn test_board_login_create_filter_delete() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    let res = hc
+        .do_post(
+            "/api/register",
+            json!({ "username": "demo1", "pwd": "welcome" }),
+        )
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    // Follows the redirects, with the cookies.
+    let reqwest = hc.reqwest_client();
+    let url = |path: &str| format!("{}{path}", app.base_url);
+    let csrf_of = |html: &str| {
+        html.split(r#"name="csrf" value=""#)
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .map(str::to_string)
+            .unwrap_or_default()
+    };
+
+    // -- Not logged in, redirected to the login page.
+    let res = reqwest.get(url("/board")).send().await?;
+    assert_eq!(res.url().path(), "/board/login");
+    let csrf = csrf_of(&res.text().await?);
+    assert!(!csrf.is_empty());
+
+    // -- Login, with the csrf token.
+    let login = |csrf: String, pwd: &'static str| {
+        reqwest
+            .post(url("/board/login"))
+            .form(&[("csrf", csrf.as_str()), ("username", "demo1"), ("pwd", pwd)])
+            .send()
+    };
+    let res = login("not-the-token".to_string(), "welcome").await?;
+    assert_eq!(res.status().as_u16(), 403);
+    let res = login(csrf.clone(), "wrong").await?;
+    assert_eq!(res.status().as_u16(), 403);
+    assert!(res.text().await?.contains("Wrong username or password."));
+    let res = login(csrf.clone(), "welcome").await?;
+    assert_eq!(res.url().path(), "/board");
+    assert!(res.text().await?.contains("No tickets."));
+
+    // -- Create, escaped in the page.
+    let create = |title: &'static str, priority: &'static str| {
+        reqwest
+            .post(url("/board/tickets"))
+            .form(&[
+                ("csrf", csrf.as_str()),
+                ("title", title),
+                ("description", ""),
+                ("priority", priority),
+            ])
+            .send()
+    };
+    let res = create("<b>Bold</b> bug", "high").await?;
+    assert_eq!(res.url().path(), "/board");
+    let html = res.text().await?;
+    assert!(html.contains("&lt;b&gt;Bold&lt;/b&gt; bug"));
+    assert!(!html.contains("<b>Bold</b>"));
+    create("Other", "").await?;
+
+    let res = create(" ", "low").await?;
+    assert_eq!(res.status().as_u16(), 400);
+    assert!(res.text().await?.contains("TicketTitleEmpty"));
+
+    // -- Filters.
+    let res = reqwest
+        .get(url("/board?status=&priority=high&label=&title_contains="))
+        .send()
+        .await?;
+    let html = res.text().await?;
+    assert!(html.contains("Bold"));
+    assert!(!html.contains("Other"));
+    assert!(html.contains(r#"<option value="high" selected>"#));
+
+    let res = reqwest.get(url("/board?status=nope")).send().await?;
+    assert_eq!(res.status().as_u16(), 400);
+
+    // -- Delete.
+    let res = hc.do_get("/api/tickets?title_contains=Other").await?;
+    let id = res.json_value::<u64>("/data/0/id")?;
+    let res = reqwest
+        .post(url(&format!("/board/tickets/{id}/delete")))
+        .form(&[("csrf", csrf.as_str())])
+        .send()
+        .await?;
+    assert_eq!(res.url().path(), "/board");
+    assert!(!res.text().await?.contains("Other"));
+
+    // -- Logout, back to the login page.
+    let res = reqwest
+        .post(url("/board/logout"))
+        .form(&[("csrf", csrf.as_str())])
+        .send()
+        .await?;
+    assert_eq!(res.url().path(), "/board/login");
+    let res = reqwest.get(url("/board")).send().await?;
+    assert_eq!(res.url().path(), "/board/login");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_static_dir_assets_only() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc.do_get("/css/board.css").await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    // Not the crate root anymore.
+    for path in ["/Cargo.toml", "/src/main.rs", "/config/dev.toml"] {
+        let res = hc.do_get(path).await?;
+        assert_eq!(res.status().as_u16(), 404, "{path}");
+    }
+
+    Ok(())
+}
+
+// endregion: --- Board
+
+// region:    --- Rate Limit
+
+#[tokio::test]
+async fn test_rate_limit_login_routes_per_ip() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    // Default login group limit: burst of 10 per IP.
+    for _ in 0..10 {
+        let res = hc.do_post("/api/logout", json!({})).await?;
+        assert_eq!(res.status().as_u16(), 200);
+    }
+    let res = hc.do_post("/api/logout", json!({})).await?;
+    assert_client_error(&res, 429, "RATE_LIMITED")?;
+    let retry_after: u64 = res.header("retry-after").unwrap_or_default().parse()?;
+    assert!(retry_after >= 1);
+
+    // Other route groups have their own buckets.
+    let res = hc.do_get("/hello").await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_login_lockout_after_failures() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+    register_and_login(&hc, "demo1", "welcome").await?;
+
+    let hc = app.client()?;
+    // Default: 5 failures in a row lock the username.
+    for _ in 0..5 {
+        let res = hc
+            .do_post("/api/login", json!({ "username": "demo1", "pwd": "nope" }))
+            .await?;
+        assert_client_error(&res, 403, "LOGIN_FAIL")?;
+    }
+
+    // -- Locked, even with the right pwd.
+    let res = hc
+        .do_post(
+            "/api/login",
+            json!({ "username": "demo1", "pwd": "welcome" }),
+        )
+        .await?;
+    assert_client_error(&res, 429, "LOGIN_LOCKED")?;
+    assert!(res.header("retry-after").is_some());
+    assert!(hc.cookie_value("auth-token").is_none());
+
+    Ok(())
+}
+
+// endregion: --- Rate Limit
+
+// region:    --- API Docs
+
+#[tokio::test]
+async fn test_api_docs_no_auth() -> Result<()> {
+    let app = TestApp::spawn().await?;
+    let hc = app.client()?;
+
+    let res = hc.do_get("/api-docs/openapi.json").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert!(res.json_value::<String>("/openapi")?.starts_with("3."));
+    let ticket = res.json_value::<Value>("/components/schemas/Ticket")?;
+    assert!(ticket["properties"]["version"].is_object());
+    let login = res.json_value::<Value>("/paths/~1api~1login/post")?;
+    assert!(login["responses"]["403"].is_object());
+
+    let res = hc.do_get("/api-docs").await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert!(res.text_body()?.contains("/api-docs/openapi.json"));
+
+    Ok(())
+}
+
+// endregion: --- API Docs
@@ -0,0 +1,100 @@
+//! In-process app harness for the integration tests.
+
+use anyhow::Result;
+use httpc_test::Client;
+use rust_axum_intro::app;
+use rust_axum_intro::config::{StoreConfig, WebhookConfig};
+use rust_axum_intro::model::{spawn_webhook_delivery, ModelController};
+use rust_axum_intro::shutdown::Shutdown;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// The admin account seeded in each `TestApp`.
+pub const ADMIN_USERNAME: &str = "admin";
+pub const ADMIN_PWD: &str = "welcome";
+
+pub struct TestApp {
+    pub base_url: String,
+    /// To start draining, as on SIGTERM.
+    pub shutdown: Shutdown,
+}
+
+impl TestApp {
+    /// Start the app on an ephemeral port, with a fresh in-memory store.
+    pub async fn spawn() -> Result<Self> {
+        let mc = ModelController::new(&StoreConfig::Memory).await?;
+        mc.seed_admin(ADMIN_USERNAME, ADMIN_PWD.to_string()).await?;
+        let shutdown = Shutdown::default();
+        // Fast retries, for the tests to see them.
+        spawn_webhook_delivery(
+            mc.clone(),
+            WebhookConfig {
+                max_attempts: 3,
+                backoff: Duration::from_millis(50),
+                disable_after: 2,
+                timeout: Duration::from_secs(2),
+            },
+        );
+
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app(mc, shutdown.clone()).into_make_service_with_connect_info::<SocketAddr>());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        Ok(Self {
+            base_url: format!("http://{addr}"),
+            shutdown,
+        })
+    }
+
+    /// New client for this app, with its own cookie store.
+    pub fn client(&self) -> Result<Client> {
+        Ok(httpc_test::new_client(self.base_url.as_str())?)
+    }
+}
+
+/// Register a new account and log in with it.
+/// Returns the new user id.
+pub async fn register_and_login(hc: &Client, username: &str, pwd: &str) -> Result<u64> {
+    let res = hc
+        .do_post("/api/register", json!({ "username": username, "pwd": pwd }))
+        .await?;
+    assert_eq!(res.status().as_u16(), 200, "register {username}");
+    let user_id = res.json_value::<u64>("/result/user_id")?;
+
+    login(hc, username, pwd).await?;
+
+    Ok(user_id)
+}
+
+pub async fn login(hc: &Client, username: &str, pwd: &str) -> Result<()> {
+    let res = hc
+        .do_post("/api/login", json!({ "username": username, "pwd": pwd }))
+        .await?;
+    assert_eq!(res.status().as_u16(), 200, "login {username}");
+
+    Ok(())
+}
+
+pub async fn login_admin(hc: &Client) -> Result<()> {
+    login(hc, ADMIN_USERNAME, ADMIN_PWD).await
+}
+
+/// Assert the JSON error envelope of the main response mapper.
+pub fn assert_client_error(
+    res: &httpc_test::Response,
+    status: u16,
+    error_type: &str,
+) -> Result<()> {
+    assert_eq!(res.status().as_u16(), status);
+    assert_eq!(res.json_value::<String>("/error/type")?, error_type);
+
+    let req_uuid = res.json_value::<String>("/error/req_uuid")?;
+    assert!(
+        uuid::Uuid::parse_str(&req_uuid).is_ok(),
+        "req_uuid: {req_uuid}"
+    );
+
+    Ok(())
+}
@@ -3,12 +3,26 @@
 use anyhow::Result;
 use serde_json::json;
 
+/// Manual dev check, against a server started with `cargo run`.
+/// Run with `cargo test -q quick_dev -- --ignored --nocapture`
+/// (the automated suite is in `api_tests.rs`).
 #[tokio::test]
+#[ignore]
 async fn quick_dev() -> Result<()> {
     let hc = httpc_test::new_client("http://localhost:8080")?;
 
     hc.do_get("/hello2/Mike").await?.print().await?;
 
+    // Fails with USERNAME_TAKEN after the first run, which is fine.
+    let req_register = hc.do_post(
+        "/api/register",
+        json!({
+            "username": "demo1",
+            "pwd": "welcome"
+        }),
+    );
+    req_register.await?.print().await?;
+
     let req_login = hc.do_post(
         "/api/login",
         json!({
@@ -33,7 +47,3 @@ async fn quick_dev() -> Result<()> {
 
     Ok(())
 }
-
-
-
-